@@ -0,0 +1,11 @@
+pub mod conditions;
+pub mod door_graph;
+pub mod enemy_table;
+pub mod item_table;
+pub mod lint;
+pub mod reachability;
+pub mod room_state;
+pub mod script_diff;
+pub mod table;
+pub mod validate;
+pub mod xref;
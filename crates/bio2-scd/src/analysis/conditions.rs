@@ -0,0 +1,261 @@
+use crate::fileio::opcode_data::Opcode;
+use crate::fileio::rdt_scd::{ScdFunction, ScdInstruction};
+use std::fmt;
+
+/// Operators used by Compare and MemberCmp, indexed by the operation parameter
+pub const COMPARE_OPERATORS: &[&str] = &["==", ">", ">=", "<", "<=", "!=", "&"];
+
+/// A single test made by a condition instruction or a switch case
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum Condition {
+    /// CheckBit(bit_array, bit_number, value)
+    Bit { bit_array: i32, bit_number: i32, value: i32 },
+    /// Compare(dummy, var_id, operation, value)
+    Compare { var_id: i32, operation: i32, value: i32 },
+    /// MemberCmp(flag, member, operation, value)
+    Member { member: i32, operation: i32, value: i32 },
+    /// Case of a Switch on a variable
+    Case { var_id: i32, value: i32 },
+    /// Any other condition instruction, kept as pseudocode
+    Other(String),
+}
+
+/// All conditions of one IfStart/WhileStart block or switch case
+///
+/// A negated clause is the else branch, which runs when any of its conditions fails
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Clause {
+    pub conditions: Vec<Condition>,
+    pub negated: bool,
+}
+
+/// Conjunction of the clauses of every block enclosing an instruction
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Guard {
+    pub clauses: Vec<Clause>,
+}
+
+impl Condition {
+    /// Reads the condition tested by an instruction, if it is a condition instruction
+    pub fn from_instruction(instruction: &ScdInstruction) -> Option<Condition> {
+        if instruction.error.is_some() {
+            return None;
+        }
+        let p = &instruction.params;
+        match num::FromPrimitive::from_u8(instruction.opcode)? {
+            Opcode::CheckBit => Some(Condition::Bit { bit_array: p[0], bit_number: p[1], value: p[2] }),
+            Opcode::Compare => Some(Condition::Compare { var_id: p[1], operation: p[2], value: p[3] }),
+            Opcode::MemberCmp => Some(Condition::Member { member: p[1], operation: p[2], value: p[3] }),
+            Opcode::DirCk => Some(Condition::Other(instruction.code_line())),
+            _ => None,
+        }
+    }
+
+    /// The condition that holds exactly when this one fails, if it can be written as one
+    pub fn negated(&self) -> Option<Condition> {
+        // ==/!=, >/<= and >=/< are each other's negation; & has none
+        let negate_operation = |operation: i32| [5, 4, 3, 2, 1, 0].get(operation as usize).copied();
+        match *self {
+            Condition::Bit { bit_array, bit_number, value } => Some(Condition::Bit {
+                bit_array,
+                bit_number,
+                value: (value == 0) as i32,
+            }),
+            Condition::Compare { var_id, operation, value } => Some(Condition::Compare {
+                var_id,
+                operation: negate_operation(operation)?,
+                value,
+            }),
+            Condition::Member { member, operation, value } => Some(Condition::Member {
+                member,
+                operation: negate_operation(operation)?,
+                value,
+            }),
+            Condition::Case { var_id, value } => Some(Condition::Compare { var_id, operation: 5, value }),
+            Condition::Other(_) => None,
+        }
+    }
+}
+
+impl Clause {
+    /// True if both clauses can never hold at the same time
+    pub fn contradicts(&self, other: &Clause) -> bool {
+        if self.conditions == other.conditions && self.negated != other.negated {
+            return true;
+        }
+        // A plain clause requires every condition, so compare them pairwise
+        if self.negated || other.negated {
+            return false;
+        }
+        self.conditions.iter().any(|a| other.conditions.iter().any(|b| conditions_contradict(a, b)))
+    }
+}
+
+impl Guard {
+    /// True if no flag state satisfies both guards
+    pub fn excludes(&self, other: &Guard) -> bool {
+        self.clauses.iter().any(|a| other.clauses.iter().any(|b| a.contradicts(b)))
+    }
+}
+
+/// Same bit or switch variable tested for different values
+fn conditions_contradict(a: &Condition, b: &Condition) -> bool {
+    match (a, b) {
+        (
+            Condition::Bit { bit_array: a1, bit_number: n1, value: v1 },
+            Condition::Bit { bit_array: a2, bit_number: n2, value: v2 },
+        ) => a1 == a2 && n1 == n2 && (v1 != &0) != (v2 != &0),
+        (Condition::Case { var_id: a1, value: v1 }, Condition::Case { var_id: a2, value: v2 }) => a1 == a2 && v1 != v2,
+        _ => false,
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Bit { bit_array, bit_number, value } => write!(f, "bit[{}][{}] == {}", bit_array, bit_number, value),
+            Condition::Compare { var_id, operation, value } => write!(f, "var[{}] {} {}", var_id, operator(*operation), value),
+            Condition::Member { member, operation, value } => write!(f, "member[{}] {} {}", member, operator(*operation), value),
+            Condition::Case { var_id, value } => write!(f, "switch[{}] == {}", var_id, value),
+            Condition::Other(code) => write!(f, "{}", code),
+        }
+    }
+}
+
+impl fmt::Display for Clause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self.conditions.iter().map(|c| c.to_string()).collect();
+        match (self.negated, parts.len()) {
+            (false, _) => write!(f, "{}", parts.join(" && ")),
+            (true, 1) => write!(f, "!({})", parts[0]),
+            (true, _) => write!(f, "!({})", parts.join(" && ")),
+        }
+    }
+}
+
+impl fmt::Display for Guard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.clauses.is_empty() {
+            return write!(f, "always");
+        }
+        let parts: Vec<String> = self.clauses.iter().map(|c| c.to_string()).collect();
+        write!(f, "{}", parts.join(" && "))
+    }
+}
+
+fn operator(operation: i32) -> &'static str {
+    COMPARE_OPERATORS.get(operation as usize).copied().unwrap_or("?")
+}
+
+/// Kind of block that is currently open while walking a function
+enum Block {
+    /// IfStart or WhileStart; the flag is true while its conditions are still being read
+    Conditional(Clause, bool),
+    Loop,
+    Switch(i32),
+    Case(Clause),
+}
+
+/// Computes the guard of every instruction in a function
+///
+/// The result is indexed like `function.instructions`
+pub fn instruction_guards(function: &ScdFunction) -> Vec<Guard> {
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut guards = Vec::with_capacity(function.instructions.len());
+
+    for instruction in &function.instructions {
+        let condition = Condition::from_instruction(instruction);
+
+        // Conditions directly after IfStart/WhileStart belong to that block
+        if let Some(Block::Conditional(clause, reading @ true)) = blocks.last_mut() {
+            match &condition {
+                Some(c) => clause.conditions.push(c.clone()),
+                None => *reading = false,
+            }
+        }
+
+        let opcode = match instruction.error {
+            None => num::FromPrimitive::from_u8(instruction.opcode),
+            Some(_) => None,
+        };
+        // Block ends take effect before computing the guard so EndIf itself is unguarded
+        match opcode {
+            Some(Opcode::EndIf) | Some(Opcode::WhileEnd) => pop_conditional(&mut blocks),
+            Some(Opcode::ForEnd) | Some(Opcode::DoEnd) => {
+                if let Some(Block::Loop) = blocks.last() {
+                    blocks.pop();
+                }
+            }
+            Some(Opcode::EndSwitch) => {
+                if let Some(Block::Case(_)) = blocks.last() {
+                    blocks.pop();
+                }
+                if let Some(Block::Switch(_)) = blocks.last() {
+                    blocks.pop();
+                }
+            }
+            Some(Opcode::ElseStart) => {
+                if let Some(Block::Conditional(clause, reading)) = blocks.last_mut() {
+                    clause.negated = true;
+                    *reading = false;
+                }
+            }
+            Some(Opcode::Case) => {
+                if let Some(Block::Case(_)) = blocks.last() {
+                    blocks.pop();
+                }
+            }
+            _ => {}
+        }
+
+        guards.push(current_guard(&blocks));
+
+        match opcode {
+            Some(Opcode::IfStart) | Some(Opcode::WhileStart) => blocks.push(Block::Conditional(
+                Clause {
+                    conditions: Vec::new(),
+                    negated: false,
+                },
+                true,
+            )),
+            Some(Opcode::ForStart) | Some(Opcode::DoStart) => blocks.push(Block::Loop),
+            Some(Opcode::Switch) => blocks.push(Block::Switch(instruction.params[0])),
+            Some(Opcode::Case) => {
+                if let Some(Block::Switch(var_id)) = blocks.last() {
+                    let var_id = *var_id;
+                    blocks.push(Block::Case(Clause {
+                        conditions: vec![Condition::Case {
+                            var_id,
+                            value: instruction.params[2],
+                        }],
+                        negated: false,
+                    }));
+                }
+            }
+            _ => {}
+        }
+    }
+    guards
+}
+
+/// Computes the guards of every function of a script, indexed by function then instruction
+pub fn script_guards(functions: &[ScdFunction]) -> Vec<Vec<Guard>> {
+    functions.iter().map(instruction_guards).collect()
+}
+
+fn pop_conditional(blocks: &mut Vec<Block>) {
+    if let Some(Block::Conditional(..)) = blocks.last() {
+        blocks.pop();
+    }
+}
+
+fn current_guard(blocks: &[Block]) -> Guard {
+    let clauses = blocks
+        .iter()
+        .filter_map(|block| match block {
+            Block::Conditional(clause, false) | Block::Case(clause) if !clause.conditions.is_empty() => Some(clause.clone()),
+            _ => None,
+        })
+        .collect();
+    Guard { clauses }
+}
@@ -0,0 +1,107 @@
+use crate::file_handler::LoadedRoom;
+use crate::fileio::name_tables::NameTables;
+use crate::fileio::room_id::RoomId;
+use crate::fileio::room_layout::ScriptLocation;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// A DoorAotSet leading from one room to another
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct DoorEdge {
+    pub from: String,
+    pub to: String,
+    pub aot: i32,
+    pub next_camera: i32,
+    pub next_pos: (i32, i32, i32),
+    pub key_id: i32,
+    pub key_type: i32,
+    pub source: ScriptLocation,
+    /// False when no RDT with the destination name was loaded
+    pub target_exists: bool,
+}
+
+/// Connections between all rooms of a game directory
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct DoorGraph {
+    pub rooms: Vec<String>,
+    pub edges: Vec<DoorEdge>,
+}
+
+impl DoorEdge {
+    /// Key requirement of the door, if any
+    pub fn key_label(&self) -> Option<String> {
+        if self.key_id == 0 {
+            return None;
+        }
+        Some(format!("key {} (type {})", self.key_id, self.key_type))
+    }
+}
+
+impl DoorGraph {
+    /// Builds the graph from the DoorAotSet instructions of every room
+    pub fn build(rooms: &[LoadedRoom]) -> Self {
+        let names: BTreeSet<&str> = rooms.iter().map(|r| r.name.as_str()).collect();
+        let mut edges = Vec::new();
+
+        for room in rooms {
+            // Doors lead to the room of the same player character
+            let player = RoomId::parse(&room.name).map_or(0, |id| id.player);
+            for door in &room.data.room_layout.doors {
+                let to = RoomId::from_door(door.next_stage, door.next_room, player).to_string();
+                edges.push(DoorEdge {
+                    from: room.name.clone(),
+                    target_exists: names.contains(to.as_str()),
+                    to,
+                    aot: door.area.aot,
+                    next_camera: door.next_camera,
+                    next_pos: door.next_pos,
+                    key_id: door.key_id,
+                    key_type: door.key_type,
+                    source: door.area.source,
+                });
+            }
+        }
+
+        DoorGraph {
+            rooms: names.into_iter().map(String::from).collect(),
+            edges,
+        }
+    }
+
+    /// Doors whose destination room was not found
+    pub fn missing_targets(&self) -> impl Iterator<Item = &DoorEdge> {
+        self.edges.iter().filter(|e| !e.target_exists)
+    }
+
+    /// Export in Graphviz DOT format, with the room names as node labels
+    pub fn to_dot(&self, names: &NameTables) -> String {
+        let mut dot = String::from("digraph doors {\n    node [shape=box];\n");
+        for room in &self.rooms {
+            match RoomId::parse(room).and_then(|id| names.room_name(id)) {
+                Some(name) => {
+                    let _ = writeln!(dot, "    \"{}\" [label=\"{}\\n{}\"];", room, room, name.replace('"', "\\\""));
+                }
+                None => {
+                    let _ = writeln!(dot, "    \"{}\";", room);
+                }
+            }
+        }
+        for edge in self.missing_targets() {
+            let _ = writeln!(dot, "    \"{}\" [color=red, style=dashed];", edge.to);
+        }
+        for edge in &self.edges {
+            let mut attributes = vec![format!("label=\"cam {}{}\"", edge.next_camera, edge.key_label().map(|k| format!(", {}", k)).unwrap_or_default())];
+            if !edge.target_exists {
+                attributes.push("color=red".to_string());
+            }
+            let _ = writeln!(dot, "    \"{}\" -> \"{}\" [{}];", edge.from, edge.to, attributes.join(", "));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Export as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| format!("JSON error: {}", e))
+    }
+}
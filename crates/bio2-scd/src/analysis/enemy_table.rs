@@ -0,0 +1,123 @@
+use crate::analysis::conditions::{script_guards, Guard};
+use crate::analysis::table::PlacementRow;
+use crate::file_handler::LoadedRoom;
+use crate::fileio::name_tables::enemy_name;
+use crate::fileio::room_layout::{ScriptKind, ScriptLocation};
+
+/// One SceEmSet found in a room script
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct EnemyPlacement {
+    pub room: String,
+    pub source: ScriptLocation,
+    /// Absolute offset of the instruction in the RDT file
+    pub offset: usize,
+    /// Entity slot the enemy is loaded into
+    pub em_no: i32,
+    pub id: i32,
+    pub name: String,
+    pub enemy_type: i32,
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub dir: i32,
+    pub status: i32,
+    pub motion: i32,
+    pub em_set_flag: i32,
+    /// Enclosing CheckBit/Compare conditions required to reach the instruction
+    pub guard: Guard,
+    /// Another SceEmSet of the room can fill the same slot under the same conditions
+    pub duplicate_slot: bool,
+}
+
+impl PlacementRow for EnemyPlacement {
+    const HEADERS: &'static [&'static str] = &[
+        "Room", "Script", "Function", "Offset", "Slot", "Enemy", "Type", "X", "Y", "Z",
+        "Direction", "Status", "Motion", "Set flag", "Conditions", "Duplicate",
+    ];
+
+    fn room(&self) -> &str {
+        &self.room
+    }
+
+    fn source(&self) -> ScriptLocation {
+        self.source
+    }
+
+    fn table_row(&self) -> Vec<String> {
+        vec![
+            self.room.clone(),
+            self.source.script.name().to_string(),
+            self.source.function.to_string(),
+            format!("0x{:x}", self.offset),
+            self.em_no.to_string(),
+            self.name.clone(),
+            self.enemy_type.to_string(),
+            self.x.to_string(),
+            self.y.to_string(),
+            self.z.to_string(),
+            self.dir.to_string(),
+            self.status.to_string(),
+            self.motion.to_string(),
+            self.em_set_flag.to_string(),
+            self.guard.to_string(),
+            if self.duplicate_slot { "yes" } else { "" }.to_string(),
+        ]
+    }
+
+    fn summary(rows: &[Self]) -> String {
+        let duplicates = rows.iter().filter(|e| e.duplicate_slot).count();
+        format!("{} enemies, {} with a duplicate slot", rows.len(), duplicates)
+    }
+}
+
+/// Gathers every SceEmSet of every room and flags slots that are set twice
+pub fn collect_enemies(rooms: &[LoadedRoom]) -> Vec<EnemyPlacement> {
+    let mut enemies = Vec::new();
+    for room in rooms {
+        let init_guards = script_guards(&room.data.init_functions);
+        let main_guards = script_guards(&room.data.main_functions);
+
+        let first = enemies.len();
+        for enemy in &room.data.room_layout.enemies {
+            let source = enemy.source;
+            let guards = match source.script {
+                ScriptKind::Init => &init_guards,
+                ScriptKind::Main => &main_guards,
+            };
+            enemies.push(EnemyPlacement {
+                room: room.name.clone(),
+                source,
+                offset: room.data.instruction(source).offset,
+                em_no: enemy.em_no,
+                id: enemy.id,
+                name: enemy_name(enemy.id),
+                enemy_type: enemy.enemy_type,
+                x: enemy.x,
+                y: enemy.y,
+                z: enemy.z,
+                dir: enemy.dir,
+                status: enemy.status,
+                motion: enemy.motion,
+                em_set_flag: enemy.em_set_flag,
+                guard: guards[source.function][source.instruction].clone(),
+                duplicate_slot: false,
+            });
+        }
+        mark_duplicate_slots(&mut enemies[first..]);
+    }
+    enemies
+}
+
+/// Two placements clash when they share a slot and their guards can both hold
+fn mark_duplicate_slots(room_enemies: &mut [EnemyPlacement]) {
+    for a in 0..room_enemies.len() {
+        for b in a + 1..room_enemies.len() {
+            if room_enemies[a].em_no == room_enemies[b].em_no
+                && !room_enemies[a].guard.excludes(&room_enemies[b].guard)
+            {
+                room_enemies[a].duplicate_slot = true;
+                room_enemies[b].duplicate_slot = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::analysis::conditions::{script_guards, Guard};
+use crate::analysis::table::PlacementRow;
+use crate::file_handler::LoadedRoom;
+use crate::fileio::name_tables::item_name;
+use crate::fileio::room_layout::{ScriptKind, ScriptLocation};
+
+/// One ItemAotSet found in a room script
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct ItemPlacement {
+    pub room: String,
+    pub source: ScriptLocation,
+    /// Absolute offset of the instruction in the RDT file
+    pub offset: usize,
+    pub aot: i32,
+    pub x: i32,
+    pub z: i32,
+    pub width: i32,
+    pub depth: i32,
+    pub item_id: i32,
+    pub name: String,
+    pub amount: i32,
+    pub item_picked_index: i32,
+    /// Enclosing CheckBit/Compare conditions required to reach the instruction
+    pub guard: Guard,
+}
+
+impl PlacementRow for ItemPlacement {
+    const HEADERS: &'static [&'static str] = &[
+        "Room", "Script", "Function", "Offset", "AOT", "X", "Z", "Width", "Depth",
+        "Item id", "Item", "Amount", "Picked index", "Conditions",
+    ];
+
+    fn room(&self) -> &str {
+        &self.room
+    }
+
+    fn source(&self) -> ScriptLocation {
+        self.source
+    }
+
+    fn table_row(&self) -> Vec<String> {
+        vec![
+            self.room.clone(),
+            self.source.script.name().to_string(),
+            self.source.function.to_string(),
+            format!("0x{:x}", self.offset),
+            self.aot.to_string(),
+            self.x.to_string(),
+            self.z.to_string(),
+            self.width.to_string(),
+            self.depth.to_string(),
+            self.item_id.to_string(),
+            self.name.clone(),
+            self.amount.to_string(),
+            self.item_picked_index.to_string(),
+            self.guard.to_string(),
+        ]
+    }
+
+    fn summary(rows: &[Self]) -> String {
+        format!("{} items", rows.len())
+    }
+}
+
+/// Gathers every ItemAotSet of every room
+pub fn collect_items(rooms: &[LoadedRoom]) -> Vec<ItemPlacement> {
+    let mut items = Vec::new();
+    for room in rooms {
+        let init_guards = script_guards(&room.data.init_functions);
+        let main_guards = script_guards(&room.data.main_functions);
+
+        for item in &room.data.room_layout.items {
+            let source = item.area.source;
+            let guards = match source.script {
+                ScriptKind::Init => &init_guards,
+                ScriptKind::Main => &main_guards,
+            };
+            let (min_x, min_z) = item.area.points[0];
+            let (max_x, max_z) = item.area.points[2];
+            items.push(ItemPlacement {
+                room: room.name.clone(),
+                source,
+                offset: room.data.instruction(source).offset,
+                aot: item.area.aot,
+                x: min_x,
+                z: min_z,
+                width: max_x - min_x,
+                depth: max_z - min_z,
+                item_id: item.item_id,
+                name: item_name(item.item_id),
+                amount: item.amount,
+                item_picked_index: item.item_picked_index,
+                guard: guards[source.function][source.instruction].clone(),
+            });
+        }
+    }
+    items
+}
@@ -0,0 +1,477 @@
+use crate::analysis::conditions::script_guards;
+use crate::file_handler::RdtFileData;
+use crate::fileio::opcode_data::Opcode;
+use crate::fileio::rdt_scd::{ScdDecodeError, ScdFunction};
+use crate::fileio::room_layout::{AotShape, ScriptKind, ScriptLocation};
+use crate::vm::structure::{closed_by, FunctionStructure, PARAM_BLOCK_LENGTH};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Parameter of ObjModelSet indexing the models embedded in the RDT
+const PARAM_OBJ_MODEL_INDEX: usize = 0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Deserialize, serde::Serialize)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// A check the linter can run
+pub struct LintRule {
+    pub id: &'static str,
+    pub severity: Severity,
+    pub description: &'static str,
+}
+
+pub const RULES: &[LintRule] = &[
+    LintRule {
+        id: "block-nesting",
+        severity: Severity::Error,
+        description: "IfStart/ElseStart/EndIf, loops and switches must be closed in the order they were opened",
+    },
+    LintRule {
+        id: "block-length",
+        severity: Severity::Warning,
+        description: "Block length must match the bytes up to the matching ElseStart, EndIf, loop end, Case or EndSwitch",
+    },
+    LintRule {
+        id: "unknown-opcode",
+        severity: Severity::Error,
+        description: "Opcode not in the opcode table",
+    },
+    LintRule {
+        id: "invalid-parameters",
+        severity: Severity::Error,
+        description: "Instruction parameters could not be decoded",
+    },
+    LintRule {
+        id: "duplicate-aot",
+        severity: Severity::Warning,
+        description: "AOT index set twice in one script under conditions that can hold together",
+    },
+    LintRule {
+        id: "camera-range",
+        severity: Severity::Error,
+        description: "CutChg camera id must be below the camera count of the RDT header",
+    },
+    LintRule {
+        id: "model-range",
+        severity: Severity::Error,
+        description: "ObjModelSet index must be below the model count of the RDT header",
+    },
+    LintRule {
+        id: "break-outside-loop",
+        severity: Severity::Error,
+        description: "Break must be inside a For, While, Do or Switch block",
+    },
+    LintRule {
+        id: "code-after-evt-end",
+        severity: Severity::Warning,
+        description: "Bytes between EvtEnd and the next function are never run",
+    },
+];
+
+/// Rules turned off and severities changed from their defaults
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct LintConfig {
+    pub disabled: BTreeSet<String>,
+    pub severities: BTreeMap<String, Severity>,
+}
+
+/// One finding of the linter
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Problem {
+    pub rule: String,
+    pub severity: Severity,
+    pub location: ScriptLocation,
+    pub message: String,
+}
+
+impl LintConfig {
+    /// Reads a configuration from a JSON file
+    pub fn load_file(path: &std::path::Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read '{}': {}", path.display(), e))?;
+        let config: LintConfig = serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid lint configuration '{}': {}", path.display(), e))?;
+        let unknown = config
+            .disabled
+            .iter()
+            .chain(config.severities.keys())
+            .find(|id| !RULES.iter().any(|rule| rule.id == id.as_str()));
+        match unknown {
+            Some(id) => Err(format!("Unknown lint rule '{}' in '{}'", id, path.display())),
+            None => Ok(config),
+        }
+    }
+
+    /// Severity a rule reports with, or None if it is turned off
+    pub fn severity(&self, rule: &LintRule) -> Option<Severity> {
+        if self.disabled.contains(rule.id) {
+            return None;
+        }
+        Some(self.severities.get(rule.id).copied().unwrap_or(rule.severity))
+    }
+}
+
+/// Collects problems, dropping those of disabled rules
+struct Linter<'a> {
+    config: &'a LintConfig,
+    problems: Vec<Problem>,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, rule_id: &str, location: ScriptLocation, message: String) {
+        let Some(rule) = RULES.iter().find(|rule| rule.id == rule_id) else {
+            return;
+        };
+        if let Some(severity) = self.config.severity(rule) {
+            self.problems.push(Problem {
+                rule: rule.id.to_string(),
+                severity,
+                location,
+                message,
+            });
+        }
+    }
+}
+
+/// Runs every enabled rule over both scripts of a room
+pub fn lint_room(data: &RdtFileData, config: &LintConfig) -> Vec<Problem> {
+    let mut linter = Linter {
+        config,
+        problems: Vec::new(),
+    };
+    for script in [ScriptKind::Init, ScriptKind::Main] {
+        let functions = data.functions(script);
+        for function in functions {
+            check_instructions(&mut linter, data, script, function);
+            check_nesting(&mut linter, script, function);
+            check_block_lengths(&mut linter, script, function);
+        }
+        check_trailing_code(&mut linter, script, functions);
+        check_duplicate_aots(&mut linter, data, script);
+    }
+    linter.problems
+}
+
+fn location(script: ScriptKind, function: &ScdFunction, instruction: usize) -> ScriptLocation {
+    ScriptLocation {
+        script,
+        function: function.index,
+        instruction,
+    }
+}
+
+/// Decode errors, out of range cameras and models, and breaks outside loops
+fn check_instructions(linter: &mut Linter<'_>, data: &RdtFileData, script: ScriptKind, function: &ScdFunction) {
+    let structure = FunctionStructure::new(function);
+    for (index, instruction) in function.instructions.iter().enumerate() {
+        let here = location(script, function, index);
+        let p = &instruction.params;
+        match &instruction.error {
+            Some(ScdDecodeError::UnknownOpcode) => {
+                linter.report("unknown-opcode", here, format!("Unknown opcode 0x{:02x}", instruction.opcode));
+                continue;
+            }
+            Some(ScdDecodeError::InvalidParameters(e)) => {
+                linter.report("invalid-parameters", here, format!("{}: {}", instruction.name, e));
+                continue;
+            }
+            None => {}
+        }
+        match num::FromPrimitive::from_u8(instruction.opcode) {
+            Some(Opcode::CutChg) if p[0] >= data.header.num_cameras as i32 => linter.report(
+                "camera-range",
+                here,
+                format!("CutChg to camera {} but the room has {} cameras", p[0], data.header.num_cameras),
+            ),
+            Some(Opcode::ObjModelSet) if p[PARAM_OBJ_MODEL_INDEX] >= data.header.num_models as i32 => linter.report(
+                "model-range",
+                here,
+                format!(
+                    "ObjModelSet uses model {} but the room has {} models",
+                    p[PARAM_OBJ_MODEL_INDEX], data.header.num_models
+                ),
+            ),
+            Some(Opcode::Break) if structure.break_target[index].is_none() => {
+                linter.report("break-outside-loop", here, "Break outside of a loop or switch".to_string())
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Walks the block instructions of a function with a stack of open blocks
+fn check_nesting(linter: &mut Linter<'_>, script: ScriptKind, function: &ScdFunction) {
+    let name = |index: usize| function.instructions[index].name.clone();
+    let mut open: Vec<(Opcode, usize)> = Vec::new();
+    for (index, instruction) in function.instructions.iter().enumerate() {
+        if instruction.error.is_some() {
+            continue;
+        }
+        let Some(opcode) = num::FromPrimitive::from_u8(instruction.opcode) else {
+            continue;
+        };
+        let here = location(script, function, index);
+        match opcode {
+            Opcode::IfStart | Opcode::WhileStart | Opcode::ForStart | Opcode::DoStart | Opcode::Switch => {
+                open.push((opcode, index));
+            }
+            Opcode::ElseStart => match open.last() {
+                Some((Opcode::IfStart, _)) => {
+                    open.pop();
+                    open.push((opcode, index));
+                }
+                _ => linter.report("block-nesting", here, "ElseStart without an open IfStart".to_string()),
+            },
+            Opcode::Case if !open.iter().any(|(op, _)| *op == Opcode::Switch) => {
+                linter.report("block-nesting", here, "Case outside of a Switch".to_string());
+            }
+            _ => {
+                let Some(openers) = closed_by(opcode) else {
+                    continue;
+                };
+                let Some(depth) = open.iter().rposition(|(op, _)| openers.contains(op)) else {
+                    linter.report("block-nesting", here, format!("{} without an open {}", instruction.name, name_of(openers[0])));
+                    continue;
+                };
+                // Blocks opened after the one being closed were never closed themselves
+                for &(_, start) in &open[depth + 1..] {
+                    let message = format!("{} at line {} is not closed before {}", name(start), start, instruction.name);
+                    linter.report("block-nesting", here, message);
+                }
+                open.truncate(depth);
+            }
+        }
+    }
+    for (_, start) in open {
+        let message = format!("{} is never closed", name(start));
+        linter.report("block-nesting", location(script, function, start), message);
+    }
+}
+
+fn name_of(opcode: Opcode) -> &'static str {
+    match opcode {
+        Opcode::IfStart => "IfStart",
+        Opcode::ElseStart => "ElseStart",
+        Opcode::WhileStart => "WhileStart",
+        Opcode::ForStart => "ForStart",
+        Opcode::DoStart => "DoStart",
+        _ => "Switch",
+    }
+}
+
+/// Compares the block length of each opener with the bytes up to its matching instruction
+///
+/// A block length counts the bytes from the end of the opener to the start of its partner:
+/// the ElseStart or EndIf of an IfStart, the EndIf of an ElseStart, the end of a loop, the
+/// next Case or the EndSwitch of a Case, and the EndSwitch of a Switch.
+fn check_block_lengths(linter: &mut Linter<'_>, script: ScriptKind, function: &ScdFunction) {
+    let instructions = &function.instructions;
+    let ends = FunctionStructure::new(function).block_ends(function);
+
+    for (start, end) in ends {
+        let Some(&block_length) = instructions[start].params.get(PARAM_BLOCK_LENGTH) else {
+            continue;
+        };
+        let actual = instructions[end].offset as i32 - instructions[start + 1].offset as i32;
+        if block_length != actual {
+            let message = format!(
+                "{} block length is {} but {} starts {} bytes later",
+                instructions[start].name, block_length, instructions[end].name, actual
+            );
+            linter.report("block-length", location(script, function, start), message);
+        }
+    }
+}
+
+/// Reports bytes left between the EvtEnd of a function and the start of the next one
+fn check_trailing_code(linter: &mut Linter<'_>, script: ScriptKind, functions: &[ScdFunction]) {
+    for pair in functions.windows(2) {
+        let (Some(last), Some(next)) = (pair[0].instructions.last(), pair[1].instructions.first()) else {
+            continue;
+        };
+        let end = last.offset + last.raw.len();
+        if last.opcode == Opcode::EvtEnd as u8 && next.offset > end {
+            let message = format!("{} bytes after EvtEnd are never run", next.offset - end);
+            linter.report("code-after-evt-end", location(script, &pair[0], pair[0].instructions.len() - 1), message);
+        }
+    }
+}
+
+/// Reports AOT indices set twice in one script unless the two sets exclude each other
+fn check_duplicate_aots(linter: &mut Linter<'_>, data: &RdtFileData, script: ScriptKind) {
+    let guards = script_guards(data.functions(script));
+    let layout = &data.room_layout;
+    let mut areas: Vec<&AotShape> = layout
+        .aots
+        .iter()
+        .chain(layout.doors.iter().map(|door| &door.area))
+        .chain(layout.items.iter().map(|item| &item.area))
+        .filter(|area| area.source.script == script)
+        .collect();
+    areas.sort_by_key(|area| (area.source.function, area.source.instruction));
+
+    let guard = |area: &AotShape| &guards[area.source.function][area.source.instruction];
+    for (index, area) in areas.iter().enumerate() {
+        let earlier = areas[..index]
+            .iter()
+            .find(|other| other.aot == area.aot && !guard(other).excludes(guard(area)));
+        if let Some(other) = earlier {
+            let message = format!(
+                "AOT {} is already set at {} {}:{}",
+                area.aot,
+                other.source.script.name(),
+                other.source.function,
+                other.source.instruction
+            );
+            linter.report("duplicate-aot", area.source, message);
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        f.pad(name)
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}] {} {}:{}: {}",
+            self.severity,
+            self.rule,
+            self.location.script.name(),
+            self.location.function,
+            self.location.instruction,
+            self.message
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rooms::{load, room};
+
+    /// A room with an empty init script and the given main functions
+    fn main_room(main: &[&str]) -> RdtFileData {
+        load(&room(&["EvtEnd()"], main))
+    }
+
+    /// Problems of one rule as (function, instruction, message)
+    fn found(data: &RdtFileData, rule: &str) -> Vec<(usize, usize, String)> {
+        lint_room(data, &LintConfig::default())
+            .into_iter()
+            .filter(|problem| problem.rule == rule)
+            .map(|problem| (problem.location.function, problem.location.instruction, problem.message))
+            .collect()
+    }
+
+    fn aot_set(aot: i32) -> String {
+        format!("AotSet({}, 1, 0, 0, 0, 0, 0, 1000, 1000, 0, 0, 0, 0, 0, 0)", aot)
+    }
+
+    #[test]
+    fn block_nesting() {
+        let data = main_room(&[
+            "IfStart(0, 0)\nCheckBit(1, 5, 1)\nWhileStart(0, 0)\nEndIf()\nEvtEnd()",
+            "EndIf()\nEvtEnd()",
+        ]);
+        assert_eq!(found(&data, "block-nesting"), [
+            (0, 3, "WhileStart at line 2 is not closed before EndIf".to_string()),
+            (1, 0, "EndIf without an open IfStart".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn block_length() {
+        let data = main_room(&[
+            "IfStart(0, 4)\nCheckBit(1, 5, 1)\nEndIf()\nEvtEnd()",
+            "IfStart(0, 6)\nCheckBit(1, 5, 1)\nEndIf()\nEvtEnd()",
+        ]);
+        assert_eq!(found(&data, "block-length"), [(1, 0, "IfStart block length is 6 but EndIf starts 4 bytes later".to_string())]);
+    }
+
+    #[test]
+    fn unknown_opcode() {
+        let data = main_room(&["SetBit(1, 0, 1)\nRaw(fe)\nEvtEnd()"]);
+        assert_eq!(found(&data, "unknown-opcode"), [(0, 1, "Unknown opcode 0xfe".to_string())]);
+    }
+
+    #[test]
+    fn duplicate_aot_unless_the_guards_exclude_each_other() {
+        let exclusive = format!(
+            "IfStart(0, 0)\nCheckBit(1, 5, 1)\n{}\nElseStart(0, 0)\n{}\nEndIf()\nEvtEnd()",
+            aot_set(3),
+            aot_set(3)
+        );
+        assert!(found(&main_room(&[&exclusive]), "duplicate-aot").is_empty());
+
+        let twice = format!("{}\nIfStart(0, 0)\nCheckBit(1, 5, 1)\n{}\nEndIf()\nEvtEnd()", aot_set(3), aot_set(3));
+        assert_eq!(found(&main_room(&[&twice]), "duplicate-aot"), [(0, 3, "AOT 3 is already set at main 0:0".to_string())]);
+    }
+
+    #[test]
+    fn camera_range() {
+        let mut data = main_room(&["CutChg(1)\nCutChg(2)\nEvtEnd()"]);
+        data.header.num_cameras = 2;
+        assert_eq!(found(&data, "camera-range"), [(0, 1, "CutChg to camera 2 but the room has 2 cameras".to_string())]);
+    }
+
+    #[test]
+    fn model_range() {
+        let model = |index: i32| format!("ObjModelSet({}{})", index, ", 0".repeat(21));
+        let mut data = main_room(&[&format!("{}\n{}\nEvtEnd()", model(0), model(1))]);
+        data.header.num_models = 1;
+        assert_eq!(found(&data, "model-range"), [(0, 1, "ObjModelSet uses model 1 but the room has 1 models".to_string())]);
+    }
+
+    #[test]
+    fn break_outside_loop() {
+        let data = main_room(&["WhileStart(0, 0)\nBreak(0)\nWhileEnd(0)\nBreak(0)\nEvtEnd()"]);
+        assert_eq!(found(&data, "break-outside-loop"), [(0, 3, "Break outside of a loop or switch".to_string())]);
+    }
+
+    #[test]
+    fn code_after_evt_end() {
+        let data = main_room(&["EvtEnd()\nRaw(22 01 00 01)", "EvtEnd()"]);
+        assert_eq!(found(&data, "code-after-evt-end"), [(0, 0, "4 bytes after EvtEnd are never run".to_string())]);
+    }
+
+    #[test]
+    fn disabled_rules_and_severities_apply() {
+        let data = main_room(&["Break(0)\nEvtEnd()"]);
+        let mut config = LintConfig::default();
+        config.severities.insert("break-outside-loop".to_string(), Severity::Info);
+        assert_eq!(lint_room(&data, &config)[0].severity, Severity::Info);
+        config.disabled.insert("break-outside-loop".to_string());
+        assert!(lint_room(&data, &config).is_empty());
+    }
+
+    #[test]
+    fn config_file_with_unknown_rule_is_rejected() {
+        let path = std::env::temp_dir().join(format!("bio2-scd-{}-lint.json", std::process::id()));
+        std::fs::write(&path, r#"{"disabled": ["block-length"], "severities": {"no-such-rule": "Info"}}"#).unwrap();
+        let result = LintConfig::load_file(&path);
+        std::fs::write(&path, r#"{"disabled": ["block-length"], "severities": {"camera-range": "Warning"}}"#).unwrap();
+        let valid = LintConfig::load_file(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert!(result.unwrap_err().starts_with("Unknown lint rule 'no-such-rule'"));
+        let valid = valid.unwrap();
+        let block_length = RULES.iter().find(|rule| rule.id == "block-length").unwrap();
+        assert_eq!(valid.severity(block_length), None);
+        assert_eq!(valid.severities["camera-range"], Severity::Warning);
+    }
+}
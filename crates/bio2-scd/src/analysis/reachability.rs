@@ -0,0 +1,458 @@
+use crate::analysis::conditions::{script_guards, Clause, Condition, Guard};
+use crate::analysis::xref::XrefTarget;
+use crate::fileio::opcode_data::Opcode;
+use crate::fileio::rdt_scd::ScdFunction;
+use crate::fileio::room_layout::{ScriptKind, ScriptLocation};
+use crate::vm::state::GameState;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Functions the game starts without a call: init function 0 and main functions 0 and 1
+const ENTRY_POINTS: &[(ScriptKind, usize)] = &[(ScriptKind::Init, 0), (ScriptKind::Main, 0), (ScriptKind::Main, 1)];
+
+/// Simplified condition under which an instruction runs
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PathCondition {
+    /// Conditions that must all hold
+    pub required: Vec<Condition>,
+    /// Else branches of blocks with several conditions; at least one condition of each must fail
+    pub alternatives: Vec<Clause>,
+    /// The only GoSub or EvtExec entering the function, whose path condition is included
+    pub caller: Option<ScriptLocation>,
+    /// Why no flag state reaches the instruction
+    pub contradiction: Option<String>,
+}
+
+/// Path conditions of every instruction of a room, indexed by function then instruction
+#[derive(Clone, Debug, Default)]
+pub struct Reachability {
+    pub init: Vec<Vec<PathCondition>>,
+    pub main: Vec<Vec<PathCondition>>,
+}
+
+impl PathCondition {
+    /// Simplifies the conjunction of the clauses of a guard
+    ///
+    /// Duplicate and implied conditions are dropped, else branches of a single condition become
+    /// its negation, and the result is checked for conditions that can never hold together.
+    pub fn from_guard(guard: &Guard, caller: Option<ScriptLocation>) -> Self {
+        let mut path = PathCondition {
+            caller,
+            ..Default::default()
+        };
+        for clause in &guard.clauses {
+            match (clause.negated, clause.conditions.as_slice()) {
+                (false, conditions) => path.required.extend(conditions.iter().cloned()),
+                (true, [condition]) if condition.negated().is_some() => path.required.extend(condition.negated()),
+                (true, _) => path.alternatives.push(clause.clone()),
+            }
+        }
+        path.simplify();
+        path
+    }
+
+    pub fn is_reachable(&self) -> bool {
+        self.contradiction.is_none()
+    }
+
+    fn simplify(&mut self) {
+        loop {
+            self.required = remove_implied(&self.required);
+            if let Some(reason) = find_contradiction(&self.required) {
+                self.contradiction = Some(reason);
+                return;
+            }
+
+            let mut narrowed = false;
+            let mut alternatives = Vec::new();
+            for clause in std::mem::take(&mut self.alternatives) {
+                // A condition the required ones rule out always fails, so the else branch always runs
+                if clause.conditions.iter().any(|c| excluded_by(&self.required, c)) {
+                    continue;
+                }
+                let open: Vec<Condition> = clause
+                    .conditions
+                    .iter()
+                    .filter(|c| !self.required.iter().any(|r| implies(r, c)))
+                    .cloned()
+                    .collect();
+                match open.as_slice() {
+                    [] => {
+                        let holding: Vec<String> = self
+                            .required
+                            .iter()
+                            .filter(|r| clause.conditions.iter().any(|c| implies(r, c)))
+                            .map(|r| r.to_string())
+                            .collect();
+                        self.contradiction = Some(format!("{} contradicts {}", clause, holding.join(" && ")));
+                        return;
+                    }
+                    [condition] if condition.negated().is_some() => {
+                        self.required.extend(condition.negated());
+                        narrowed = true;
+                    }
+                    _ => alternatives.push(Clause {
+                        conditions: open,
+                        negated: true,
+                    }),
+                }
+            }
+            self.alternatives = alternatives;
+            if !narrowed {
+                return;
+            }
+        }
+    }
+}
+
+impl Reachability {
+    /// Computes the path condition of every instruction of both scripts
+    ///
+    /// A function entered from exactly one GoSub or EvtExec inherits the path condition of
+    /// that call; functions with several callers are taken as always entered.
+    pub fn new(init_functions: &[ScdFunction], main_functions: &[ScdFunction]) -> Self {
+        let guards = HashMap::from([
+            (ScriptKind::Init, script_guards(init_functions)),
+            (ScriptKind::Main, script_guards(main_functions)),
+        ]);
+
+        let mut callers: HashMap<(ScriptKind, usize), Vec<ScriptLocation>> = HashMap::new();
+        for (script, functions) in [(ScriptKind::Init, init_functions), (ScriptKind::Main, main_functions)] {
+            for (function, scd_function) in functions.iter().enumerate() {
+                for (instruction, scd_instruction) in scd_function.instructions.iter().enumerate() {
+                    if scd_instruction.error.is_some() {
+                        continue;
+                    }
+                    let p = &scd_instruction.params;
+                    let callee = match num::FromPrimitive::from_u8(scd_instruction.opcode) {
+                        Some(Opcode::GoSub) => (script, p[0] as usize),
+                        Some(Opcode::EvtExec) => (ScriptKind::Main, p[2] as usize),
+                        _ => continue,
+                    };
+                    callers.entry(callee).or_default().push(ScriptLocation {
+                        script,
+                        function,
+                        instruction,
+                    });
+                }
+            }
+        }
+
+        let mut entry_guards = HashMap::new();
+        let mut path_conditions = |script: ScriptKind| -> Vec<Vec<PathCondition>> {
+            guards[&script]
+                .iter()
+                .enumerate()
+                .map(|(function, function_guards)| {
+                    let (entry, caller) = entry_guard((script, function), &guards, &callers, &mut entry_guards, &mut Vec::new());
+                    function_guards
+                        .iter()
+                        .map(|guard| {
+                            let mut clauses = entry.clauses.clone();
+                            clauses.extend(guard.clauses.iter().cloned());
+                            PathCondition::from_guard(&Guard { clauses }, caller)
+                        })
+                        .collect()
+                })
+                .collect()
+        };
+        Reachability {
+            init: path_conditions(ScriptKind::Init),
+            main: path_conditions(ScriptKind::Main),
+        }
+    }
+
+    pub fn script(&self, script: ScriptKind) -> &[Vec<PathCondition>] {
+        match script {
+            ScriptKind::Init => &self.init,
+            ScriptKind::Main => &self.main,
+        }
+    }
+
+    pub fn get(&self, location: ScriptLocation) -> Option<&PathCondition> {
+        self.script(location.script).get(location.function)?.get(location.instruction)
+    }
+
+    /// Every instruction of both scripts with its path condition, init script first
+    pub fn iter(&self) -> impl Iterator<Item = (ScriptLocation, &PathCondition)> {
+        [(ScriptKind::Init, &self.init), (ScriptKind::Main, &self.main)]
+            .into_iter()
+            .flat_map(|(script, functions)| {
+                functions.iter().enumerate().flat_map(move |(function, paths)| {
+                    paths.iter().enumerate().map(move |(instruction, path)| {
+                        let location = ScriptLocation {
+                            script,
+                            function,
+                            instruction,
+                        };
+                        (location, path)
+                    })
+                })
+            })
+    }
+}
+
+/// Guard under which a function is entered, and the single call site it was taken from
+fn entry_guard(
+    function: (ScriptKind, usize),
+    guards: &HashMap<ScriptKind, Vec<Vec<Guard>>>,
+    callers: &HashMap<(ScriptKind, usize), Vec<ScriptLocation>>,
+    memo: &mut HashMap<(ScriptKind, usize), (Guard, Option<ScriptLocation>)>,
+    visiting: &mut Vec<(ScriptKind, usize)>,
+) -> (Guard, Option<ScriptLocation>) {
+    if let Some(entry) = memo.get(&function) {
+        return entry.clone();
+    }
+    let caller = match callers.get(&function).map(Vec::as_slice) {
+        Some([caller]) if !ENTRY_POINTS.contains(&function) && !visiting.contains(&function) => *caller,
+        _ => return (Guard::default(), None),
+    };
+
+    visiting.push(function);
+    let (mut entry, _) = entry_guard((caller.script, caller.function), guards, callers, memo, visiting);
+    visiting.pop();
+    if let Some(call_guard) = guards[&caller.script][caller.function].get(caller.instruction) {
+        entry.clauses.extend(call_guard.clauses.iter().cloned());
+    }
+    memo.insert(function, (entry.clone(), Some(caller)));
+    (entry, Some(caller))
+}
+
+/// The flag, variable or member a condition tests, with its operator and value
+fn comparison(condition: &Condition) -> Option<(XrefTarget, i32, i32)> {
+    match *condition {
+        Condition::Bit { bit_array, bit_number, value } => Some((XrefTarget::Flag(bit_array, bit_number), 0, value)),
+        Condition::Compare { var_id, operation, value } => Some((XrefTarget::Variable(var_id), operation, value)),
+        Condition::Member { member, operation, value } => Some((XrefTarget::Member(member), operation, value)),
+        Condition::Case { var_id, value } => Some((XrefTarget::Variable(var_id), 0, value)),
+        Condition::Other(_) => None,
+    }
+}
+
+/// Values at which the conditions on one target can change outcome
+///
+/// Every interval between two values tests the same, so checking these is enough for
+/// everything except & masks, which also get each of their bits.
+fn candidates(target: XrefTarget, conditions: &[&Condition]) -> Vec<i32> {
+    let (min, max) = match target {
+        XrefTarget::Flag(..) => return vec![0, 1],
+        XrefTarget::Variable(_) => (i16::MIN as i32, i16::MAX as i32),
+        XrefTarget::Member(_) => (i32::MIN, i32::MAX),
+    };
+    let mut values = vec![min, max, 0];
+    for (_, operation, value) in conditions.iter().filter_map(|c| comparison(c)) {
+        values.extend([value.saturating_sub(1), value, value.saturating_add(1)]);
+        if operation == 6 {
+            values.extend((0..32).map(|bit| 1 << bit).filter(|bit| value & bit != 0));
+        }
+    }
+    values.retain(|value| (min..=max).contains(value));
+    values
+}
+
+/// Whether some value of their common target satisfies all conditions, None when unsure
+fn satisfiable(target: XrefTarget, conditions: &[&Condition]) -> Option<bool> {
+    let mut state = GameState::default();
+    for value in candidates(target, conditions) {
+        state.set_value(target, value);
+        if conditions.iter().all(|c| state.evaluate(c) != Some(false)) {
+            return Some(true);
+        }
+    }
+    // Values satisfying a mask together with a range may lie between the candidates
+    let has_mask = conditions.iter().filter_map(|c| comparison(c)).any(|(_, operation, _)| operation == 6);
+    if has_mask {
+        None
+    } else {
+        Some(false)
+    }
+}
+
+/// True if every value satisfying `a` also satisfies `b`
+fn implies(a: &Condition, b: &Condition) -> bool {
+    if a == b {
+        return true;
+    }
+    match (comparison(a), comparison(b)) {
+        (Some((target_a, op_a, _)), Some((target_b, op_b, _))) if target_a == target_b && op_a != 6 && op_b != 6 => {
+            let mut state = GameState::default();
+            candidates(target_a, &[a, b]).into_iter().all(|value| {
+                state.set_value(target_a, value);
+                state.evaluate(a) != Some(true) || state.evaluate(b) == Some(true)
+            })
+        }
+        _ => false,
+    }
+}
+
+/// True if `condition` cannot hold while all `required` conditions do
+fn excluded_by(required: &[Condition], condition: &Condition) -> bool {
+    let Some((target, ..)) = comparison(condition) else {
+        return false;
+    };
+    let mut conditions: Vec<&Condition> = required.iter().filter(|r| same_target(r, target)).collect();
+    conditions.push(condition);
+    satisfiable(target, &conditions) == Some(false)
+}
+
+fn same_target(condition: &Condition, target: XrefTarget) -> bool {
+    comparison(condition).map_or(false, |(t, ..)| t == target)
+}
+
+/// Drops duplicates and conditions implied by another one, keeping the first of equal ones
+fn remove_implied(conditions: &[Condition]) -> Vec<Condition> {
+    let mut kept: Vec<Condition> = Vec::new();
+    for condition in conditions {
+        if kept.iter().any(|k| implies(k, condition)) {
+            continue;
+        }
+        kept.retain(|k| !implies(condition, k));
+        kept.push(condition.clone());
+    }
+    kept
+}
+
+/// Describes why the conditions can never all hold, if they cannot
+fn find_contradiction(conditions: &[Condition]) -> Option<String> {
+    let mut targets: Vec<XrefTarget> = Vec::new();
+    for (target, ..) in conditions.iter().filter_map(comparison) {
+        if !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+    for target in targets {
+        let on_target: Vec<&Condition> = conditions.iter().filter(|c| same_target(c, target)).collect();
+        for (index, a) in on_target.iter().enumerate() {
+            for b in &on_target[index + 1..] {
+                if satisfiable(target, &[a, b]) == Some(false) {
+                    return Some(format!("{} contradicts {}", a, b));
+                }
+            }
+        }
+        if satisfiable(target, &on_target) == Some(false) {
+            let parts: Vec<String> = on_target.iter().map(|c| c.to_string()).collect();
+            return Some(format!("{} cannot all hold", parts.join(" && ")));
+        }
+    }
+    None
+}
+
+impl fmt::Display for PathCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ref reason) = self.contradiction {
+            return write!(f, "never: {}", reason);
+        }
+        let parts: Vec<String> = self
+            .required
+            .iter()
+            .map(|c| c.to_string())
+            .chain(self.alternatives.iter().map(|c| c.to_string()))
+            .collect();
+        if parts.is_empty() {
+            write!(f, "always")
+        } else {
+            write!(f, "{}", parts.join(" && "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rooms::{load, room};
+
+    /// Reachability of a room with an empty init script and the given main functions
+    fn reach(main: &[&str]) -> Reachability {
+        let data = load(&room(&["EvtEnd()"], main));
+        Reachability::new(&data.init_functions, &data.main_functions)
+    }
+
+    fn bit(bit_array: i32, bit_number: i32, value: i32) -> Condition {
+        Condition::Bit { bit_array, bit_number, value }
+    }
+
+    #[test]
+    fn same_bit_required_both_clear_and_set_is_a_contradiction() {
+        let reach = reach(&["IfStart(0, 0)
+CheckBit(1, 5, 0)
+IfStart(0, 0)
+CheckBit(1, 5, 1)
+SetBit(1, 0, 1)
+EndIf()
+EndIf()
+EvtEnd()"]);
+        let inner = &reach.main[0][4];
+        assert!(!inner.is_reachable());
+        assert_eq!(inner.contradiction.as_deref(), Some("bit[1][5] == 0 contradicts bit[1][5] == 1"));
+        assert!(reach.main[0][2].is_reachable());
+    }
+
+    #[test]
+    fn else_branch_of_one_condition_requires_its_negation() {
+        let reach = reach(&["IfStart(0, 0)
+CheckBit(1, 5, 1)
+SetBit(1, 0, 1)
+ElseStart(0, 0)
+SetBit(1, 1, 1)
+EndIf()
+EvtEnd()"]);
+        assert_eq!(reach.main[0][2].required, [bit(1, 5, 1)]);
+        let else_branch = &reach.main[0][4];
+        assert_eq!(else_branch.required, [bit(1, 5, 0)]);
+        assert!(else_branch.alternatives.is_empty());
+        assert_eq!(else_branch.to_string(), "bit[1][5] == 0");
+    }
+
+    #[test]
+    fn else_branch_of_implied_conditions_is_unreachable() {
+        let reach = reach(&["IfStart(0, 0)
+CheckBit(1, 5, 1)
+Compare(0, 3, 0, 2)
+IfStart(0, 0)
+CheckBit(1, 5, 1)
+Compare(0, 3, 2, 1)
+SetBit(1, 0, 1)
+ElseStart(0, 0)
+SetBit(1, 1, 1)
+EndIf()
+EndIf()
+EvtEnd()"]);
+        assert!(reach.main[0][6].is_reachable());
+        let else_branch = &reach.main[0][8];
+        assert!(!else_branch.is_reachable());
+        assert!(else_branch.contradiction.as_deref().unwrap().starts_with("!(bit[1][5] == 1 && var[3] >= 1) contradicts"));
+    }
+
+    #[test]
+    fn else_branch_with_one_open_condition_requires_its_negation() {
+        let reach = reach(&["IfStart(0, 0)
+CheckBit(1, 5, 1)
+IfStart(0, 0)
+CheckBit(1, 5, 1)
+Compare(0, 3, 0, 2)
+SetBit(1, 0, 1)
+ElseStart(0, 0)
+SetBit(1, 1, 1)
+EndIf()
+EndIf()
+EvtEnd()"]);
+        let else_branch = &reach.main[0][7];
+        let not_two = Condition::Compare { var_id: 3, operation: 5, value: 2 };
+        assert_eq!(else_branch.required, [bit(1, 5, 1), not_two]);
+        assert!(else_branch.alternatives.is_empty());
+    }
+
+    #[test]
+    fn single_caller_passes_its_guard_to_the_callee() {
+        let caller = "IfStart(0, 0)\nCheckBit(1, 5, 1)\nGoSub(2)\nEndIf()\nEvtEnd()";
+        let callee = "SetBit(1, 0, 1)\nEvtEnd()";
+        let single = reach(&[caller, "EvtEnd()", callee]);
+        let entered = &single.main[2][0];
+        assert_eq!(entered.required, [bit(1, 5, 1)]);
+        assert_eq!(entered.caller, Some(ScriptLocation { script: ScriptKind::Main, function: 0, instruction: 2 }));
+
+        // With a second call site the callee is taken as always entered
+        let shared = reach(&[caller, "GoSub(2)\nEvtEnd()", callee]);
+        assert_eq!(shared.main[2][0], PathCondition::default());
+    }
+}
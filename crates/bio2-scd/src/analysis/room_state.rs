@@ -0,0 +1,387 @@
+use crate::file_handler::RdtFileData;
+use crate::fileio::name_tables::{enemy_name, NameTables};
+use crate::fileio::opcode_data::Opcode;
+use crate::fileio::rdt_scd::ScdInstruction;
+use crate::fileio::room_id::RoomId;
+use crate::fileio::room_layout::{
+    aot_type_name, AotShape, DoorMarker, EnemyMarker, ItemMarker, LayoutShape, ScriptLocation,
+};
+use crate::vm::effects::Effect;
+use crate::vm::machine::Vm;
+use crate::vm::state::GameState;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Frames the init script may take before the room state is read anyway
+pub const INIT_FRAME_LIMIT: u32 = 600;
+
+/// What occupies an AOT slot
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub enum AotSlot {
+    Area(AotShape),
+    Door(DoorMarker),
+    Item(ItemMarker),
+}
+
+/// A LightPosSet or LightKidoSet applied by the init script
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct LightSetting {
+    pub source: ScriptLocation,
+    pub instruction: String,
+    pub params: Vec<i32>,
+}
+
+/// A room as the init script leaves it for one flag state
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct RoomState {
+    /// Flags and variables the init script started from
+    pub initial: GameState,
+    /// Flags, variables and camera once the init script ended
+    pub state: GameState,
+    /// AOT slots, the last instruction writing a slot wins
+    pub aots: BTreeMap<i32, AotSlot>,
+    /// Entity slots filled by SceEmSet
+    pub entities: BTreeMap<i32, EnemyMarker>,
+    /// Light settings keyed by instruction, light index and, for LightPosSet, axis
+    pub lights: BTreeMap<String, LightSetting>,
+    /// The init script was still running after `INIT_FRAME_LIMIT` frames
+    pub incomplete: bool,
+}
+
+/// One table of a room state, rows keyed so they can be matched between two states
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct StateTable {
+    pub name: &'static str,
+    pub headers: &'static [&'static str],
+    pub rows: Vec<(String, Vec<String>)>,
+}
+
+/// How a row differs between two room states
+#[derive(Clone, Debug, serde::Serialize)]
+pub enum RowChange {
+    Added(Vec<String>),
+    Removed(Vec<String>),
+    Changed { before: Vec<String>, after: Vec<String> },
+}
+
+/// Changed rows of one table
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct TableDiff {
+    pub name: &'static str,
+    pub headers: &'static [&'static str],
+    pub changes: Vec<(String, RowChange)>,
+}
+
+impl AotSlot {
+    pub fn area(&self) -> &AotShape {
+        match self {
+            AotSlot::Area(area) => area,
+            AotSlot::Door(door) => &door.area,
+            AotSlot::Item(item) => &item.area,
+        }
+    }
+}
+
+impl RoomState {
+    /// Runs the init script of a room from the given flags and collects what it set up
+    pub fn evaluate(data: &RdtFileData, initial: GameState, names: &NameTables) -> Self {
+        let mut vm = Vm::new(data, initial.clone(), names.clone());
+        let incomplete = !vm.run_init(INIT_FRAME_LIMIT);
+
+        let mut room = RoomState {
+            initial,
+            incomplete,
+            ..Default::default()
+        };
+        for event in &vm.log {
+            let source = event.location;
+            match event.effect {
+                Effect::AotSet { .. } | Effect::Door { .. } | Effect::Item { .. } | Effect::EntitySpawn { .. } => {
+                    match LayoutShape::from_instruction(source, data.instruction(source)) {
+                        Some(LayoutShape::Aot(aot)) => {
+                            room.aots.insert(aot.aot, AotSlot::Area(aot));
+                        }
+                        Some(LayoutShape::Door(door)) => {
+                            room.aots.insert(door.area.aot, AotSlot::Door(door));
+                        }
+                        Some(LayoutShape::Item(item)) => {
+                            room.aots.insert(item.area.aot, AotSlot::Item(item));
+                        }
+                        Some(LayoutShape::Enemy(enemy)) => {
+                            room.entities.insert(enemy.em_no, enemy);
+                        }
+                        None => {}
+                    }
+                }
+                Effect::Other => {
+                    let instruction = data.instruction(source);
+                    if let Some(key) = light_key(instruction) {
+                        room.lights.insert(
+                            key,
+                            LightSetting {
+                                source,
+                                instruction: instruction.name.clone(),
+                                params: instruction.params.clone(),
+                            },
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+        room.state = vm.state;
+        room
+    }
+
+    pub fn doors(&self) -> impl Iterator<Item = &DoorMarker> {
+        self.aots.values().filter_map(|slot| match slot {
+            AotSlot::Door(door) => Some(door),
+            _ => None,
+        })
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = &ItemMarker> {
+        self.aots.values().filter_map(|slot| match slot {
+            AotSlot::Item(item) => Some(item),
+            _ => None,
+        })
+    }
+
+    /// The room state as tables of AOT slots, entity slots, doors, items, lights and camera
+    pub fn tables(&self, names: &NameTables) -> Vec<StateTable> {
+        let aots = self.aots.iter().map(|(slot, contents)| {
+            let area = contents.area();
+            let kind = match contents {
+                AotSlot::Area(_) => "Area",
+                AotSlot::Door(_) => "Door",
+                AotSlot::Item(_) => "Item",
+            };
+            let points: Vec<String> = area.points.iter().map(|(x, z)| format!("({}, {})", x, z)).collect();
+            (
+                slot.to_string(),
+                vec![
+                    slot.to_string(),
+                    kind.to_string(),
+                    aot_type_name(area.aot_type),
+                    area.floor.to_string(),
+                    points.join(" "),
+                    location_label(area.source),
+                ],
+            )
+        });
+        let entities = self.entities.iter().map(|(slot, e)| {
+            (
+                slot.to_string(),
+                vec![
+                    slot.to_string(),
+                    enemy_name(e.id),
+                    e.enemy_type.to_string(),
+                    e.status.to_string(),
+                    format!("({}, {}, {})", e.x, e.y, e.z),
+                    e.dir.to_string(),
+                    e.motion.to_string(),
+                    e.em_set_flag.to_string(),
+                    location_label(e.source),
+                ],
+            )
+        });
+        let doors = self.doors().map(|d| {
+            let key_type = names
+                .lookup("DoorAotSet", "key_type", d.key_type)
+                .map_or(d.key_type.to_string(), str::to_string);
+            (
+                d.area.aot.to_string(),
+                vec![
+                    d.area.aot.to_string(),
+                    names.room_label(&RoomId::from_door(d.next_stage, d.next_room, 0).to_string()),
+                    d.next_camera.to_string(),
+                    format!("({}, {}, {})", d.next_pos.0, d.next_pos.1, d.next_pos.2),
+                    d.next_dir.to_string(),
+                    d.key_id.to_string(),
+                    key_type,
+                    location_label(d.area.source),
+                ],
+            )
+        });
+        let items = self.items().map(|i| {
+            let item = names
+                .lookup("ItemAotSet", "item_id", i.item_id)
+                .map_or(i.item_id.to_string(), str::to_string);
+            (
+                i.area.aot.to_string(),
+                vec![
+                    i.area.aot.to_string(),
+                    item,
+                    i.amount.to_string(),
+                    i.item_picked_index.to_string(),
+                    location_label(i.area.source),
+                ],
+            )
+        });
+        let lights = self.lights.iter().map(|(key, light)| {
+            let params: Vec<String> = light.params.iter().map(|p| p.to_string()).collect();
+            (
+                key.clone(),
+                vec![light.instruction.clone(), params.join(", "), location_label(light.source)],
+            )
+        });
+        let camera = self.state.camera.map(|camera| ("camera".to_string(), vec![camera.to_string()]));
+
+        vec![
+            StateTable {
+                name: "AOT slots",
+                headers: &["Slot", "Kind", "Type", "Floor", "Area", "Set by"],
+                rows: aots.collect(),
+            },
+            StateTable {
+                name: "Entity slots",
+                headers: &["Slot", "Enemy", "Type", "Status", "Position", "Direction", "Motion", "Set flag", "Set by"],
+                rows: entities.collect(),
+            },
+            StateTable {
+                name: "Doors",
+                headers: &["AOT", "Destination", "Camera", "Position", "Direction", "Key", "Key type", "Set by"],
+                rows: doors.collect(),
+            },
+            StateTable {
+                name: "Items",
+                headers: &["AOT", "Item", "Amount", "Picked index", "Set by"],
+                rows: items.collect(),
+            },
+            StateTable {
+                name: "Lights",
+                headers: &["Instruction", "Parameters", "Set by"],
+                rows: lights.collect(),
+            },
+            StateTable {
+                name: "Camera",
+                headers: &["Camera"],
+                rows: camera.into_iter().collect(),
+            },
+        ]
+    }
+}
+
+/// Compares the tables of two room states row by row, listing only tables with changes
+pub fn diff_tables(before: &[StateTable], after: &[StateTable]) -> Vec<TableDiff> {
+    let mut diffs = Vec::new();
+    for (old, new) in before.iter().zip(after) {
+        let old_rows: BTreeMap<&String, &Vec<String>> = old.rows.iter().map(|(k, v)| (k, v)).collect();
+        let new_rows: BTreeMap<&String, &Vec<String>> = new.rows.iter().map(|(k, v)| (k, v)).collect();
+        let mut changes = Vec::new();
+        for (key, cells) in &old_rows {
+            match new_rows.get(key) {
+                None => changes.push(((*key).clone(), RowChange::Removed((*cells).clone()))),
+                Some(after) if after != cells => changes.push((
+                    (*key).clone(),
+                    RowChange::Changed {
+                        before: (*cells).clone(),
+                        after: (*after).clone(),
+                    },
+                )),
+                Some(_) => {}
+            }
+        }
+        for (key, cells) in &new_rows {
+            if !old_rows.contains_key(key) {
+                changes.push(((*key).clone(), RowChange::Added((*cells).clone())));
+            }
+        }
+        if !changes.is_empty() {
+            diffs.push(TableDiff {
+                name: old.name,
+                headers: old.headers,
+                changes,
+            });
+        }
+    }
+    diffs
+}
+
+impl fmt::Display for StateTable {
+    /// Aligned text columns under a heading
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} ({})", self.name, self.rows.len())?;
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for (_, cells) in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(cells) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let headers: Vec<String> = self.headers.iter().map(|h| h.to_string()).collect();
+        for cells in std::iter::once(&headers).chain(self.rows.iter().map(|(_, cells)| cells)) {
+            let padded: Vec<String> = cells.iter().zip(&widths).map(|(c, w)| format!("{:w$}", c, w = w)).collect();
+            writeln!(f, "  {}", padded.join("  ").trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TableDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.name)?;
+        for (_, change) in &self.changes {
+            match change {
+                RowChange::Added(cells) => writeln!(f, "+ {}", cells.join(", "))?,
+                RowChange::Removed(cells) => writeln!(f, "- {}", cells.join(", "))?,
+                RowChange::Changed { before, after } => {
+                    let fields: Vec<String> = self
+                        .headers
+                        .iter()
+                        .zip(before.iter().zip(after))
+                        .filter(|(_, (old, new))| old != new)
+                        .map(|(header, (old, new))| format!("{}: {} -> {}", header, old, new))
+                        .collect();
+                    writeln!(f, "~ {} {}: {}", self.headers[0], before[0], fields.join(", "))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The light field an instruction writes, so only settings of the same field replace each other
+///
+/// LightPosSet(dummy, light, axis, position low, position high) sets one coordinate of a light,
+/// LightKidoSet(light, brightness low, brightness high) its brightness.
+fn light_key(instruction: &ScdInstruction) -> Option<String> {
+    let p = &instruction.params;
+    match num::FromPrimitive::from_u8(instruction.opcode) {
+        Some(Opcode::LightPosSet) if p.len() >= 3 => Some(format!("{} {} axis {}", instruction.name, p[1], p[2])),
+        Some(Opcode::LightKidoSet) if !p.is_empty() => Some(format!("{} {}", instruction.name, p[0])),
+        _ => None,
+    }
+}
+
+/// Short form of the instruction that set a slot, e.g. "init 0:3"
+fn location_label(location: ScriptLocation) -> String {
+    format!("{} {}:{}", location.script.name(), location.function, location.instruction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rooms::{load, room};
+
+    #[test]
+    fn lights_are_keyed_by_light_and_axis() {
+        let init = "LightPosSet(0, 1, 0, 16, 0)
+LightPosSet(0, 1, 2, 32, 0)
+LightPosSet(0, 2, 0, 48, 0)
+LightPosSet(0, 1, 0, 64, 0)
+LightKidoSet(1, 200, 0)
+LightKidoSet(2, 100, 0)
+EvtEnd()";
+        let data = load(&room(&[init], &["EvtEnd()"]));
+        let room = RoomState::evaluate(&data, GameState::default(), &NameTables::builtin());
+
+        let lights: Vec<(&str, usize)> = room.lights.iter().map(|(key, light)| (key.as_str(), light.source.instruction)).collect();
+        assert_eq!(lights, [
+            ("LightKidoSet 1", 4),
+            ("LightKidoSet 2", 5),
+            ("LightPosSet 1 axis 0", 3),
+            ("LightPosSet 1 axis 2", 1),
+            ("LightPosSet 2 axis 0", 2),
+        ]);
+    }
+}
@@ -0,0 +1,251 @@
+use crate::file_handler::RdtFileData;
+use crate::fileio::name_tables::{NameTables, NamedParam};
+use crate::fileio::opcode_data::Opcode;
+use crate::fileio::rdt_scd::{ScdFunction, ScdInstruction};
+use crate::fileio::room_layout::ScriptKind;
+use crate::vm::structure::{has_block_length, PARAM_BLOCK_LENGTH};
+use std::fmt;
+
+/// How a line of the aligned functions differs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum LineChange {
+    Same,
+    Inserted,
+    Removed,
+    /// Same instruction with different parameters
+    Changed,
+}
+
+/// How a function differs between the two rooms
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum FunctionStatus {
+    Unchanged,
+    Changed,
+    Added,
+    Removed,
+}
+
+/// A parameter that differs between two aligned instructions
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct ParamChange {
+    pub old: NamedParam,
+    pub new: NamedParam,
+}
+
+/// One row of the side by side view, pointing at an instruction of either or both functions
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct DiffLine {
+    pub change: LineChange,
+    /// Instruction index in the old function
+    pub old: Option<usize>,
+    /// Instruction index in the new function
+    pub new: Option<usize>,
+    pub old_code: Option<String>,
+    pub new_code: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<ParamChange>,
+}
+
+/// The aligned instructions of a function with the same index in both rooms
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct FunctionDiff {
+    pub script: ScriptKind,
+    pub function: usize,
+    pub status: FunctionStatus,
+    pub lines: Vec<DiffLine>,
+}
+
+/// Structural difference between the scripts of two rooms
+///
+/// Functions are matched by index, since scripts call them by index, and their instructions are
+/// aligned on opcode and parameters. Offsets and block lengths, which only follow from the
+/// size of the code, are not compared.
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct RoomDiff {
+    pub functions: Vec<FunctionDiff>,
+}
+
+impl RoomDiff {
+    pub fn new(old: &RdtFileData, new: &RdtFileData, names: &NameTables, scripts: &[ScriptKind]) -> Self {
+        let mut functions = Vec::new();
+        for &script in scripts {
+            let (old_functions, new_functions) = (old.functions(script), new.functions(script));
+            for index in 0..old_functions.len().max(new_functions.len()) {
+                functions.push(diff_functions(script, index, old_functions.get(index), new_functions.get(index), names));
+            }
+        }
+        Self { functions }
+    }
+
+    /// Functions that are not the same in both rooms
+    pub fn changed(&self) -> impl Iterator<Item = &FunctionDiff> {
+        self.functions.iter().filter(|f| f.status != FunctionStatus::Unchanged)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changed().next().is_none()
+    }
+
+    /// Counts of inserted, removed and changed instructions
+    pub fn counts(&self) -> (usize, usize, usize) {
+        let count = |change| self.functions.iter().flat_map(|f| &f.lines).filter(|l| l.change == change).count();
+        (count(LineChange::Inserted), count(LineChange::Removed), count(LineChange::Changed))
+    }
+}
+
+impl FunctionDiff {
+    /// Lines other than Same
+    pub fn changes(&self) -> impl Iterator<Item = &DiffLine> {
+        self.lines.iter().filter(|line| line.change != LineChange::Same)
+    }
+}
+
+/// Opcode and parameters without the block length, or the raw bytes of undecoded instructions
+pub(crate) fn comparable(instruction: &ScdInstruction) -> (bool, u8, Vec<i32>) {
+    if instruction.error.is_some() {
+        return (true, instruction.opcode, instruction.raw.iter().map(|&b| b as i32).collect());
+    }
+    let mut params = instruction.params.clone();
+    let opcode: Option<Opcode> = num::FromPrimitive::from_u8(instruction.opcode);
+    if opcode.map_or(false, has_block_length) {
+        if let Some(length) = params.get_mut(PARAM_BLOCK_LENGTH) {
+            *length = 0;
+        }
+    }
+    (false, instruction.opcode, params)
+}
+
+fn diff_functions(
+    script: ScriptKind,
+    function: usize,
+    old: Option<&ScdFunction>,
+    new: Option<&ScdFunction>,
+    names: &NameTables,
+) -> FunctionDiff {
+    let old_instructions = old.map(|f| f.instructions.as_slice()).unwrap_or_default();
+    let new_instructions = new.map(|f| f.instructions.as_slice()).unwrap_or_default();
+    let line = |change, old: Option<usize>, new: Option<usize>, params| DiffLine {
+        change,
+        old,
+        new,
+        old_code: old.map(|i| names.code_line(&old_instructions[i])),
+        new_code: new.map(|i| names.code_line(&new_instructions[i])),
+        params,
+    };
+
+    let old_keys: Vec<_> = old_instructions.iter().map(comparable).collect();
+    let new_keys: Vec<_> = new_instructions.iter().map(comparable).collect();
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for (match_i, match_j) in common_subsequence(&old_keys, &new_keys).into_iter().chain([(old_keys.len(), new_keys.len())]) {
+        // Instructions between two matches are changed when an old and a new one share an opcode
+        let mut next_new = j;
+        for old_index in i..match_i {
+            let paired = (next_new..match_j).find(|&new_index| {
+                let (old_error, old_opcode, old_params) = &old_keys[old_index];
+                let (new_error, new_opcode, new_params) = &new_keys[new_index];
+                !old_error && !new_error && old_opcode == new_opcode && old_params.len() == new_params.len()
+            });
+            match paired {
+                Some(new_index) => {
+                    for inserted in next_new..new_index {
+                        lines.push(line(LineChange::Inserted, None, Some(inserted), Vec::new()));
+                    }
+                    let params = param_changes(&old_instructions[old_index], &new_instructions[new_index], names);
+                    lines.push(line(LineChange::Changed, Some(old_index), Some(new_index), params));
+                    next_new = new_index + 1;
+                }
+                None => lines.push(line(LineChange::Removed, Some(old_index), None, Vec::new())),
+            }
+        }
+        for inserted in next_new..match_j {
+            lines.push(line(LineChange::Inserted, None, Some(inserted), Vec::new()));
+        }
+        if match_i < old_keys.len() {
+            lines.push(line(LineChange::Same, Some(match_i), Some(match_j), Vec::new()));
+        }
+        (i, j) = (match_i + 1, match_j + 1);
+    }
+
+    let status = match (old, new) {
+        (None, _) => FunctionStatus::Added,
+        (_, None) => FunctionStatus::Removed,
+        _ if lines.iter().all(|l| l.change == LineChange::Same) => FunctionStatus::Unchanged,
+        _ => FunctionStatus::Changed,
+    };
+    FunctionDiff { script, function, status, lines }
+}
+
+/// Named parameters that differ, leaving out the block length
+fn param_changes(old: &ScdInstruction, new: &ScdInstruction, names: &NameTables) -> Vec<ParamChange> {
+    let old_key = comparable(old).2;
+    let new_key = comparable(new).2;
+    names
+        .named_params(old)
+        .into_iter()
+        .zip(names.named_params(new))
+        .enumerate()
+        .filter(|(index, _)| old_key[*index] != new_key[*index])
+        .map(|(_, (old, new))| ParamChange { old, new })
+        .collect()
+}
+
+/// Index pairs of a longest common subsequence, in order
+pub(crate) fn common_subsequence<T: PartialEq>(old: &[T], new: &[T]) -> Vec<(usize, usize)> {
+    // Equal ends are matched directly, which keeps the table small for typical edits
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_mid, new_mid) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    // lengths[a][b] is the length of the common subsequence of old_mid[a..] and new_mid[b..]
+    let (n, m) = (old_mid.len(), new_mid.len());
+    let mut lengths = vec![vec![0u32; m + 1]; n + 1];
+    for a in (0..n).rev() {
+        for b in (0..m).rev() {
+            lengths[a][b] = match old_mid[a] == new_mid[b] {
+                true => lengths[a + 1][b + 1] + 1,
+                false => lengths[a + 1][b].max(lengths[a][b + 1]),
+            };
+        }
+    }
+
+    let mut pairs: Vec<(usize, usize)> = (0..prefix).map(|k| (k, k)).collect();
+    let (mut a, mut b) = (0, 0);
+    while a < n && b < m {
+        if old_mid[a] == new_mid[b] {
+            pairs.push((prefix + a, prefix + b));
+            a += 1;
+            b += 1;
+        } else if lengths[a + 1][b] >= lengths[a][b + 1] {
+            a += 1;
+        } else {
+            b += 1;
+        }
+    }
+    pairs.extend((0..suffix).map(|k| (old.len() - suffix + k, new.len() - suffix + k)));
+    pairs
+}
+
+impl fmt::Display for ParamChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = |param: &NamedParam| param.symbol.clone().unwrap_or_else(|| param.value.to_string());
+        write!(f, "{}: {} → {}", self.old.field, value(&self.old), value(&self.new))
+    }
+}
+
+impl fmt::Display for FunctionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FunctionStatus::Unchanged => "unchanged",
+            FunctionStatus::Changed => "changed",
+            FunctionStatus::Added => "added",
+            FunctionStatus::Removed => "removed",
+        };
+        f.pad(name)
+    }
+}
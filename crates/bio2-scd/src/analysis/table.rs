@@ -0,0 +1,51 @@
+use crate::fileio::room_layout::ScriptLocation;
+
+/// A row of a game-wide placement table
+pub trait PlacementRow: serde::Serialize {
+    /// Column names, in the order of `table_row`
+    const HEADERS: &'static [&'static str];
+
+    fn room(&self) -> &str;
+
+    /// The instruction the row was built from
+    fn source(&self) -> ScriptLocation;
+
+    /// Cell values for the GUI table and CSV export
+    fn table_row(&self) -> Vec<String>;
+
+    /// One-line description of the whole table
+    fn summary(rows: &[Self]) -> String
+    where
+        Self: Sized,
+    {
+        format!("{} rows", rows.len())
+    }
+}
+
+/// Export as CSV with a header line
+pub fn rows_to_csv<T: PlacementRow>(rows: &[T]) -> String {
+    let headers: Vec<String> = T::HEADERS.iter().map(|h| h.to_string()).collect();
+    let mut lines = vec![csv_row(&headers)];
+    lines.extend(rows.iter().map(|row| csv_row(&row.table_row())));
+    lines.join("\n") + "\n"
+}
+
+/// Export as pretty-printed JSON
+pub fn rows_to_json<T: PlacementRow>(rows: &[T]) -> Result<String, String> {
+    serde_json::to_string_pretty(rows).map_err(|e| format!("JSON error: {}", e))
+}
+
+/// Joins fields into one CSV line, quoting fields that need it
+pub fn csv_row(fields: &[String]) -> String {
+    let quoted: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            if field.contains(',') || field.contains('"') || field.contains('\n') {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect();
+    quoted.join(",")
+}
@@ -0,0 +1,332 @@
+use crate::analysis::door_graph::DoorEdge;
+use crate::analysis::lint::Severity;
+use crate::analysis::xref::{Access, XrefTarget};
+use crate::fileio::room_id::RoomId;
+use crate::fileio::room_layout::ScriptLocation;
+use crate::fileio::symbols::{SymbolDictionary, SymbolKind};
+use crate::game_data::GameData;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// An instruction in a room of the game
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct RoomLocation {
+    pub room: String,
+    pub source: ScriptLocation,
+}
+
+/// An item_picked_index shared by placements of different items
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct ReusedPickedIndex {
+    pub item_picked_index: i32,
+    pub items: Vec<PickedItem>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct PickedItem {
+    pub location: RoomLocation,
+    pub item_id: i32,
+}
+
+/// A DoorAotSet whose destination is missing or outside the target room
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct DoorIssue {
+    pub location: RoomLocation,
+    pub aot: i32,
+    pub target: String,
+    pub next_camera: i32,
+    pub next_pos: (i32, i32, i32),
+    pub reason: String,
+}
+
+/// A flag some script checks that no script sets
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct UnsetFlag {
+    pub bit_array: i32,
+    pub bit_number: i32,
+    /// Name from the symbol dictionary, if it has one
+    pub name: Option<String>,
+    pub checked_at: Vec<RoomLocation>,
+    /// Info when an item pickup or enemy kill may set the flag, Warning otherwise
+    pub severity: Severity,
+    /// Placements whose item_picked_index or em_set_flag is the bit number of the flag
+    pub engine_writes: Vec<EngineWrite>,
+}
+
+/// An ItemAotSet or SceEmSet whose pickup or kill the game records in a flag without a SetBit
+///
+/// The room files do not say which bit array the game writes, so placements are matched to
+/// flags by bit number alone.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct EngineWrite {
+    pub location: RoomLocation,
+    /// e.g. "item 0x13 picked up" or "enemy 0x10 killed"
+    pub event: String,
+}
+
+/// A room file whose name disagrees with its contents, see [`RoomId::check`]
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct RoomNameIssue {
+    pub room: String,
+    pub problem: String,
+}
+
+/// Game-wide consistency problems of a mod's RDT directory
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct ValidationReport {
+    pub rooms_checked: usize,
+    /// RDT files that could not be loaded
+    pub load_errors: Vec<String>,
+    pub reused_picked_indices: Vec<ReusedPickedIndex>,
+    pub doors_outside_collision: Vec<DoorIssue>,
+    pub missing_door_targets: Vec<DoorIssue>,
+    pub flags_never_set: Vec<UnsetFlag>,
+    pub misnamed_rooms: Vec<RoomNameIssue>,
+}
+
+impl ValidationReport {
+    /// Runs every game-wide check over the loaded rooms
+    pub fn build(game: &GameData, symbols: &SymbolDictionary) -> Self {
+        let mut report = ValidationReport {
+            rooms_checked: game.rooms.len(),
+            load_errors: game.load_errors.clone(),
+            reused_picked_indices: reused_picked_indices(game),
+            flags_never_set: flags_never_set(game, symbols),
+            misnamed_rooms: misnamed_rooms(game),
+            ..Default::default()
+        };
+        for edge in &game.door_graph.edges {
+            match door_issue(game, edge) {
+                Some(DoorCheck::Missing(issue)) => report.missing_door_targets.push(issue),
+                Some(DoorCheck::OutsideCollision(issue)) => report.doors_outside_collision.push(issue),
+                None => {}
+            }
+        }
+        report
+    }
+
+    pub fn issue_count(&self) -> usize {
+        self.load_errors.len()
+            + self.reused_picked_indices.len()
+            + self.doors_outside_collision.len()
+            + self.missing_door_targets.len()
+            + self.unset_flags(Severity::Warning)
+            + self.misnamed_rooms.len()
+    }
+
+    /// Flags checked but never set that are reported with the given severity
+    pub fn unset_flags(&self, severity: Severity) -> usize {
+        self.flags_never_set.iter().filter(|flag| flag.severity == severity).count()
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| format!("JSON error: {}", e))
+    }
+
+    /// One line per kind of problem, e.g. for a console summary
+    pub fn summary(&self) -> String {
+        format!(
+            "{} rooms checked: {} load errors, {} reused item_picked_index values, {} doors outside the target collision, {} doors to missing targets, {} flags checked but never set (and {} possibly set by item pickups or enemy kills), {} misnamed rooms",
+            self.rooms_checked,
+            self.load_errors.len(),
+            self.reused_picked_indices.len(),
+            self.doors_outside_collision.len(),
+            self.missing_door_targets.len(),
+            self.unset_flags(Severity::Warning),
+            self.unset_flags(Severity::Info),
+            self.misnamed_rooms.len()
+        )
+    }
+}
+
+/// Picked indices used by more than one item
+///
+/// Placements of the same item in the same room share an index, e.g. the same pickup in both
+/// branches of a condition or in the Leon and Claire versions of a room.
+fn reused_picked_indices(game: &GameData) -> Vec<ReusedPickedIndex> {
+    let mut by_index: BTreeMap<i32, Vec<PickedItem>> = BTreeMap::new();
+    for item in &game.items {
+        by_index.entry(item.item_picked_index).or_default().push(PickedItem {
+            location: RoomLocation {
+                room: item.room.clone(),
+                source: item.source,
+            },
+            item_id: item.item_id,
+        });
+    }
+    by_index
+        .into_iter()
+        .filter(|(_, items)| {
+            let distinct: BTreeSet<(Option<i32>, i32)> = items
+                .iter()
+                .map(|item| (RoomId::parse(&item.location.room).map(|id| id.key()), item.item_id))
+                .collect();
+            distinct.len() > 1
+        })
+        .map(|(item_picked_index, items)| ReusedPickedIndex { item_picked_index, items })
+        .collect()
+}
+
+/// Rooms whose file name is not a room id or disagrees with the file
+fn misnamed_rooms(game: &GameData) -> Vec<RoomNameIssue> {
+    let mut issues = Vec::new();
+    for room in &game.rooms {
+        let problems = match RoomId::parse(&room.name) {
+            Some(id) => id.check(&room.data),
+            None => vec!["not a room name such as ROOM1000".to_string()],
+        };
+        issues.extend(problems.into_iter().map(|problem| RoomNameIssue {
+            room: room.name.clone(),
+            problem,
+        }));
+    }
+    issues
+}
+
+enum DoorCheck {
+    Missing(DoorIssue),
+    OutsideCollision(DoorIssue),
+}
+
+/// Checks the destination stage, room, camera and position of a door
+fn door_issue(game: &GameData, edge: &DoorEdge) -> Option<DoorCheck> {
+    let issue = |reason: String| DoorIssue {
+        location: RoomLocation {
+            room: edge.from.clone(),
+            source: edge.source,
+        },
+        aot: edge.aot,
+        target: edge.to.clone(),
+        next_camera: edge.next_camera,
+        next_pos: edge.next_pos,
+        reason,
+    };
+
+    let Some(target) = game.rooms.iter().find(|room| room.name == edge.to) else {
+        let stage = RoomId::parse(&edge.to).map(|id| id.stage);
+        let stage_exists = game.rooms.iter().any(|room| RoomId::parse(&room.name).map(|id| id.stage) == stage);
+        let reason = match (stage, stage_exists) {
+            (Some(stage), false) => format!("stage {} has no rooms", stage),
+            _ => format!("room {} does not exist", edge.to),
+        };
+        return Some(DoorCheck::Missing(issue(reason)));
+    };
+
+    let cameras = target.data.header.num_cameras as i32;
+    if edge.next_camera >= cameras {
+        let reason = format!("camera {} does not exist, {} has {} cameras", edge.next_camera, edge.to, cameras);
+        return Some(DoorCheck::Missing(issue(reason)));
+    }
+
+    // The collision boxes include the walls around the room, so their extent bounds where the player can stand
+    let collision = &target.data.room_layout.collision;
+    if collision.is_empty() {
+        return None;
+    }
+    let min_x = collision.iter().map(|b| b.x as i32).min()?;
+    let min_z = collision.iter().map(|b| b.z as i32).min()?;
+    let max_x = collision.iter().map(|b| b.x as i32 + b.width as i32).max()?;
+    let max_z = collision.iter().map(|b| b.z as i32 + b.depth as i32).max()?;
+    let (x, _, z) = edge.next_pos;
+    if x < min_x || x > max_x || z < min_z || z > max_z {
+        let reason = format!(
+            "({}, {}) is outside the collision of {}, which spans ({}, {}) to ({}, {})",
+            x, z, edge.to, min_x, min_z, max_x, max_z
+        );
+        return Some(DoorCheck::OutsideCollision(issue(reason)));
+    }
+    None
+}
+
+/// Flags read by CheckBit somewhere but written by no SetBit in any room
+///
+/// Picking up an item and killing an enemy set flags in the game itself, numbered by the
+/// item_picked_index of the ItemAotSet and the em_set_flag of the SceEmSet. Flags with such a
+/// bit number are reported as Info, since the engine may be what sets them.
+fn flags_never_set(game: &GameData, symbols: &SymbolDictionary) -> Vec<UnsetFlag> {
+    let mut written = BTreeSet::new();
+    let mut checked: BTreeMap<(i32, i32), Vec<RoomLocation>> = BTreeMap::new();
+    for reference in &game.xref.references {
+        let XrefTarget::Flag(bit_array, bit_number) = reference.target else {
+            continue;
+        };
+        match reference.access {
+            Access::Write => {
+                written.insert((bit_array, bit_number));
+            }
+            Access::Read => checked.entry((bit_array, bit_number)).or_default().push(RoomLocation {
+                room: reference.room.clone(),
+                source: reference.source,
+            }),
+        }
+    }
+    checked
+        .into_iter()
+        .filter(|(flag, _)| !written.contains(flag))
+        .map(|((bit_array, bit_number), checked_at)| {
+            let engine_writes = engine_writes(game, bit_number);
+            UnsetFlag {
+                bit_array,
+                bit_number,
+                name: symbols.name(SymbolKind::Flag(bit_array, bit_number)).map(str::to_string),
+                checked_at,
+                severity: if engine_writes.is_empty() { Severity::Warning } else { Severity::Info },
+                engine_writes,
+            }
+        })
+        .collect()
+}
+
+/// Item pickups and enemy kills the game records with the given bit number
+fn engine_writes(game: &GameData, bit_number: i32) -> Vec<EngineWrite> {
+    let pickups = game.items.iter().filter(|item| item.item_picked_index == bit_number).map(|item| EngineWrite {
+        location: RoomLocation {
+            room: item.room.clone(),
+            source: item.source,
+        },
+        event: format!("item 0x{:02x} picked up", item.item_id),
+    });
+    let kills = game.enemies.iter().filter(|enemy| enemy.em_set_flag == bit_number).map(|enemy| EngineWrite {
+        location: RoomLocation {
+            room: enemy.room.clone(),
+            source: enemy.source,
+        },
+        event: format!("enemy 0x{:02x} killed", enemy.id),
+    });
+    pickups.chain(kills).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fileio::utils::write_file;
+    use crate::test_rooms::room;
+
+    #[test]
+    fn flags_an_item_pickup_or_enemy_kill_may_set_are_info() {
+        let init = "CheckBit(9, 5, 1)
+CheckBit(9, 6, 1)
+CheckBit(9, 7, 1)
+ItemAotSet(1, 2, 0, 0, 0, 0, 0, 500, 500, 19, 1, 5, 0, 0)
+SceEmSet(0, 0, 16, 0, 0, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0)
+EvtEnd()";
+        let dir = std::env::temp_dir().join(format!("bio2-scd-{}-validate", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        write_file(&dir.join("pl0/ROOM1000.RDT"), &room(&[init], &["EvtEnd()"])).unwrap();
+        let game = GameData::load(&dir);
+        let _ = std::fs::remove_dir_all(&dir);
+        let report = ValidationReport::build(&game.unwrap(), &SymbolDictionary::default());
+
+        let flags: Vec<(i32, Severity, Vec<&str>)> = report
+            .flags_never_set
+            .iter()
+            .map(|flag| (flag.bit_number, flag.severity, flag.engine_writes.iter().map(|write| write.event.as_str()).collect()))
+            .collect();
+        assert_eq!(flags, [
+            (5, Severity::Info, vec!["item 0x13 picked up"]),
+            (6, Severity::Info, vec!["enemy 0x10 killed"]),
+            (7, Severity::Warning, vec![]),
+        ]);
+        assert_eq!(report.unset_flags(Severity::Warning), 1);
+        assert_eq!(report.issue_count(), 1);
+    }
+}
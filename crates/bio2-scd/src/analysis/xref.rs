@@ -0,0 +1,199 @@
+use crate::analysis::table::PlacementRow;
+use crate::file_handler::LoadedRoom;
+use crate::fileio::name_tables::NameTables;
+use crate::fileio::opcode_data::Opcode;
+use crate::fileio::rdt_scd::ScdInstruction;
+use crate::fileio::room_layout::{ScriptKind, ScriptLocation};
+use crate::fileio::symbols::{parse_number, SymbolDictionary, SymbolKind};
+use std::fmt;
+
+/// Game state that script instructions read or write
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum XrefTarget {
+    Flag(i32, i32),
+    Variable(i32),
+    /// A member of the current work entity, used by MemberCmp
+    Member(i32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// One instruction that reads or writes a flag or variable
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Reference {
+    pub room: String,
+    pub source: ScriptLocation,
+    /// Absolute offset of the instruction in the RDT file
+    pub offset: usize,
+    pub target: XrefTarget,
+    pub access: Access,
+    pub code: String,
+}
+
+/// Every flag and variable access of a game directory
+#[derive(Default)]
+pub struct XrefIndex {
+    pub references: Vec<Reference>,
+}
+
+impl XrefTarget {
+    /// The target a named flag or variable refers to; bit arrays as a whole are not indexed
+    pub fn from_symbol(kind: SymbolKind) -> Option<Self> {
+        match kind {
+            SymbolKind::Flag(array, bit) => Some(XrefTarget::Flag(array, bit)),
+            SymbolKind::Variable(var_id) => Some(XrefTarget::Variable(var_id)),
+            SymbolKind::BitArray(_) => None,
+        }
+    }
+
+    /// Parses "flag:A:B", "var:N" or "member:N", where each number may also be a
+    /// name from the symbol dictionary
+    pub fn parse(text: &str, symbols: &SymbolDictionary) -> Result<Self, String> {
+        let invalid = || format!("Invalid reference target '{}', expected flag:ARRAY:BIT, var:ID or member:ID", text);
+        let (kind, rest) = text.split_once(':').ok_or_else(invalid)?;
+        match kind {
+            "flag" => {
+                if let Some(((array, bit), _)) = symbols.flags.iter().find(|(_, name)| *name == rest) {
+                    return Ok(XrefTarget::Flag(*array, *bit));
+                }
+                let (array, bit) = rest.split_once(':').ok_or_else(invalid)?;
+                let array = parse_number(array)
+                    .or_else(|| symbols.bit_arrays.iter().find(|(_, name)| *name == array).map(|(a, _)| *a))
+                    .ok_or_else(invalid)?;
+                Ok(XrefTarget::Flag(array, parse_number(bit).ok_or_else(invalid)?))
+            }
+            "var" => parse_number(rest)
+                .or_else(|| symbols.variables.iter().find(|(_, name)| *name == rest).map(|(v, _)| *v))
+                .map(XrefTarget::Variable)
+                .ok_or_else(invalid),
+            "member" => parse_number(rest).map(XrefTarget::Member).ok_or_else(invalid),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Name from the symbol dictionary, or the plain notation used by conditions
+    pub fn label(&self, symbols: &SymbolDictionary) -> String {
+        let name = match *self {
+            XrefTarget::Flag(array, bit) => symbols.name(SymbolKind::Flag(array, bit)),
+            XrefTarget::Variable(var_id) => symbols.name(SymbolKind::Variable(var_id)),
+            XrefTarget::Member(_) => None,
+        };
+        match name {
+            Some(name) => format!("{} ({})", name, self),
+            None => self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for XrefTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XrefTarget::Flag(array, bit) => write!(f, "bit[{}][{}]", array, bit),
+            XrefTarget::Variable(var_id) => write!(f, "var[{}]", var_id),
+            XrefTarget::Member(member) => write!(f, "member[{}]", member),
+        }
+    }
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Access::Read => write!(f, "read"),
+            Access::Write => write!(f, "write"),
+        }
+    }
+}
+
+impl PlacementRow for Reference {
+    const HEADERS: &'static [&'static str] = &["Room", "Script", "Function", "Offset", "Target", "Access", "Instruction"];
+
+    fn room(&self) -> &str {
+        &self.room
+    }
+
+    fn source(&self) -> ScriptLocation {
+        self.source
+    }
+
+    fn table_row(&self) -> Vec<String> {
+        vec![
+            self.room.clone(),
+            self.source.script.name().to_string(),
+            self.source.function.to_string(),
+            format!("0x{:x}", self.offset),
+            self.target.to_string(),
+            self.access.to_string(),
+            self.code.clone(),
+        ]
+    }
+
+    fn summary(rows: &[Self]) -> String {
+        let writes = rows.iter().filter(|r| r.access == Access::Write).count();
+        format!("{} references, {} reads, {} writes", rows.len(), rows.len() - writes, writes)
+    }
+}
+
+/// Flags and variables accessed by an instruction
+///
+/// Reads: CheckBit, Compare, MemberCmp and the source of Copy.
+/// Writes: SetBit, Save, Calc and the destination of Copy.
+pub fn instruction_accesses(instruction: &ScdInstruction) -> Vec<(XrefTarget, Access)> {
+    if instruction.error.is_some() {
+        return Vec::new();
+    }
+    let p = &instruction.params;
+    match num::FromPrimitive::from_u8(instruction.opcode) {
+        Some(Opcode::CheckBit) => vec![(XrefTarget::Flag(p[0], p[1]), Access::Read)],
+        Some(Opcode::SetBit) => vec![(XrefTarget::Flag(p[0], p[1]), Access::Write)],
+        Some(Opcode::Compare) => vec![(XrefTarget::Variable(p[1]), Access::Read)],
+        Some(Opcode::MemberCmp) => vec![(XrefTarget::Member(p[1]), Access::Read)],
+        Some(Opcode::Save) => vec![(XrefTarget::Variable(p[0]), Access::Write)],
+        Some(Opcode::Calc) => vec![(XrefTarget::Variable(p[2]), Access::Write)],
+        Some(Opcode::Copy) => vec![
+            (XrefTarget::Variable(p[1]), Access::Read),
+            (XrefTarget::Variable(p[0]), Access::Write),
+        ],
+        _ => Vec::new(),
+    }
+}
+
+impl XrefIndex {
+    /// Indexes the init and main scripts of every room, formatting instructions with `names`
+    pub fn build(rooms: &[LoadedRoom], names: &NameTables) -> Self {
+        let mut references = Vec::new();
+        for room in rooms {
+            for script in [ScriptKind::Init, ScriptKind::Main] {
+                for function in room.data.functions(script) {
+                    for (index, instruction) in function.instructions.iter().enumerate() {
+                        for (target, access) in instruction_accesses(instruction) {
+                            references.push(Reference {
+                                room: room.name.clone(),
+                                source: ScriptLocation {
+                                    script,
+                                    function: function.index,
+                                    instruction: index,
+                                },
+                                offset: instruction.offset,
+                                target,
+                                access,
+                                code: names.code_line(instruction),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        Self { references }
+    }
+
+    /// References to one flag or variable, writes first
+    pub fn find(&self, target: XrefTarget) -> Vec<Reference> {
+        let mut found: Vec<Reference> = self.references.iter().filter(|r| r.target == target).cloned().collect();
+        found.sort_by_key(|r| r.access == Access::Read);
+        found
+    }
+}
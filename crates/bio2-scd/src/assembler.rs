@@ -0,0 +1,270 @@
+use crate::fileio::name_tables::NameTables;
+use crate::fileio::opcode_data::{init_opcode_info_map, OPCODE_SLEEP};
+use crate::fileio::rdt_scd::{encode_instruction, parse_function_body, ScdFunction, ScdInstruction};
+use std::collections::HashMap;
+
+/// Name of the pseudo instruction that holds bytes which are not a decodable instruction
+pub const RAW_BYTES: &str = "Raw";
+
+/// Only the Sleep opcode is emitted, so a count the Sleeping line does not repeat would be lost
+const SLEEP_MISMATCH: &str = "Sleep must be followed by a Sleeping line with the same count";
+
+/// A line the assembler could not encode
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct AssemblyError {
+    /// Zero-based line number
+    pub line: usize,
+    pub message: String,
+}
+
+/// Encodes the pseudocode written by the disassembler back into bytecode
+///
+/// Lines have the form `Name(field: value, ...)`, where values are decimal or 0x hex numbers or
+/// names from the name tables and symbol dictionary. Field names may be left out, in which case
+/// parameters are taken in order. `Raw(22 01 05)` emits bytes as they are. Empty lines and lines
+/// starting with `;` are ignored.
+pub struct Assembler<'a> {
+    names: &'a NameTables,
+    opcodes: HashMap<String, u8>,
+}
+
+impl<'a> Assembler<'a> {
+    pub fn new(names: &'a NameTables) -> Self {
+        let opcodes = init_opcode_info_map()
+            .into_iter()
+            .map(|(opcode, info)| (info.name, opcode as u8))
+            .collect();
+        Self { names, opcodes }
+    }
+
+    /// Encodes one line, or returns None for empty and comment lines
+    pub fn assemble_line(&self, line: &str) -> Result<Option<Vec<u8>>, String> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') {
+            return Ok(None);
+        }
+        let (name, rest) = line.split_once('(').ok_or("Expected Name(parameters)")?;
+        let args = rest.trim_end().strip_suffix(')').ok_or("Missing closing parenthesis")?;
+        let name = name.trim();
+
+        if name == RAW_BYTES {
+            return args
+                .split_whitespace()
+                .map(|byte| u8::from_str_radix(byte, 16).map_err(|_| format!("Invalid hex byte '{}'", byte)))
+                .collect::<Result<Vec<u8>, String>>()
+                .map(Some);
+        }
+        let opcode = *self.opcodes.get(name).ok_or(format!("Unknown instruction '{}'", name))?;
+
+        let args: Vec<(Option<&str>, &str)> = match args.trim() {
+            "" => Vec::new(),
+            args => args
+                .split(',')
+                .map(|arg| match arg.split_once(':') {
+                    Some((field, value)) => (Some(field.trim()), value.trim()),
+                    None => (None, arg.trim()),
+                })
+                .collect(),
+        };
+        // Same naming as NameTables::named_params, so disassembled lines always match
+        let expected: Vec<String> = match self.names.param_names(name).filter(|names| names.len() == args.len()) {
+            Some(names) => names.to_vec(),
+            None => (1..=args.len()).map(|index| format!("param{}", index)).collect(),
+        };
+
+        let mut fields: Vec<(String, i32)> = Vec::with_capacity(args.len());
+        for ((field, text), expected) in args.iter().zip(expected) {
+            if let Some(field) = field.filter(|field| *field != expected) {
+                return Err(format!("Expected parameter '{}' but found '{}'", expected, field));
+            }
+            let value = self
+                .names
+                .parse_value(name, &expected, text, &fields)
+                .ok_or(format!("Unknown value '{}' for {}", text, expected))?;
+            fields.push((expected, value));
+        }
+        let params: Vec<i32> = fields.iter().map(|(_, value)| *value).collect();
+        encode_instruction(opcode, &params).map(Some)
+    }
+
+    /// Encodes a whole function, reporting every line that fails
+    pub fn assemble(&self, text: &str) -> Result<Vec<u8>, Vec<AssemblyError>> {
+        let mut bytes = Vec::new();
+        let mut errors = Vec::new();
+        // Line of the last Sleep and the Sleeping bytes it holds, until the next line is seen
+        let mut sleep: Option<(usize, Vec<u8>)> = None;
+        for (line, code) in text.lines().enumerate() {
+            match self.function_line(code) {
+                Ok((encoded, None)) if encoded.is_empty() => {}
+                Ok((encoded, sleeping)) => {
+                    if let Some((sleep_line, expected)) = sleep.take() {
+                        if encoded != expected {
+                            errors.push(AssemblyError {
+                                line: sleep_line,
+                                message: SLEEP_MISMATCH.to_string(),
+                            });
+                        }
+                    }
+                    sleep = sleeping.map(|sleeping| (line, sleeping));
+                    bytes.extend(encoded);
+                }
+                Err(message) => {
+                    sleep = None;
+                    errors.push(AssemblyError { line, message });
+                }
+            }
+        }
+        if let Some((line, _)) = sleep {
+            errors.push(AssemblyError {
+                line,
+                message: SLEEP_MISMATCH.to_string(),
+            });
+        }
+        if errors.is_empty() {
+            Ok(bytes)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Encodes a whole function and decodes it again, rejecting code after the EvtEnd that ends it
+    pub fn assemble_function(&self, text: &str) -> Result<ScdFunction, Vec<AssemblyError>> {
+        let bytes = self.assemble(text)?;
+        let function = parse_function_body(&bytes);
+        let end = function.size();
+        if end < bytes.len() {
+            let mut offset = 0;
+            let line = text
+                .lines()
+                .position(|code| {
+                    offset += self.function_line(code).map_or(0, |(encoded, _)| encoded.len());
+                    offset > end
+                })
+                .unwrap_or_default();
+            return Err(vec![AssemblyError {
+                line,
+                message: "The code continues after EvtEnd".to_string(),
+            }]);
+        }
+        Ok(function)
+    }
+
+    /// Bytes a line adds to a function, and for Sleep the bytes its Sleeping line must have
+    fn function_line(&self, code: &str) -> Result<(Vec<u8>, Option<Vec<u8>>), String> {
+        // The decoder reads the Sleeping instruction inside Sleep on its own, so only the
+        // Sleep opcode is emitted and the following Sleeping line supplies the rest
+        let is_sleep = code.split_once('(').map_or(false, |(name, _)| name.trim() == OPCODE_SLEEP);
+        match self.assemble_line(code)? {
+            Some(encoded) if is_sleep => Ok((encoded[..1].to_vec(), Some(encoded[1..].to_vec()))),
+            Some(encoded) => Ok((encoded, None)),
+            None => Ok((Vec::new(), None)),
+        }
+    }
+
+    /// The line to write for an instruction so that it assembles to the same bytes
+    ///
+    /// Falls back to plain numbers when a symbolic name is ambiguous, and to raw bytes for
+    /// instructions that did not decode
+    pub fn disassemble(&self, instruction: &ScdInstruction) -> String {
+        if instruction.error.is_none() {
+            let line = self.names.code_line(instruction);
+            if self.assemble_line(&line).ok().flatten().as_ref() == Some(&instruction.raw) {
+                return line;
+            }
+            let params: Vec<String> = self
+                .names
+                .named_params(instruction)
+                .iter()
+                .map(|param| format!("{}: {}", param.field, param.value))
+                .collect();
+            let line = format!("{}({})", instruction.name, params.join(", "));
+            if self.assemble_line(&line).ok().flatten().as_ref() == Some(&instruction.raw) {
+                return line;
+            }
+        }
+        raw_line(&instruction.raw)
+    }
+}
+
+/// A `Raw(...)` line holding the given bytes
+pub fn raw_line(bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}({})", RAW_BYTES, hex.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// IfStart with a CheckBit, EndIf, a Sleep and EvtEnd
+    const FUNCTION: &[u8] = &[
+        0x06, 0x00, 0x06, 0x00, // IfStart(dummy: 0, block_length: 6)
+        0x21, 0x01, 0x05, 0x00, // CheckBit(bit_array: 1, bit_number: 5, value: 0)
+        0x08, // EndIf
+        0x09, 0x0a, 0x1e, 0x00, // Sleep and the Sleeping it holds
+        0x01, // EvtEnd
+    ];
+
+    #[test]
+    fn disassembled_lines_assemble_to_their_bytes() {
+        let names = NameTables::builtin();
+        let assembler = Assembler::new(&names);
+        let function = parse_function_body(FUNCTION);
+        let names: Vec<&str> = function.instructions.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, ["IfStart", "CheckBit", "EndIf", "Sleep", "Sleeping", "EvtEnd"]);
+
+        for instruction in &function.instructions {
+            let line = assembler.disassemble(instruction);
+            assert!(!line.starts_with(RAW_BYTES), "{} fell back to raw bytes", instruction.name);
+            assert_eq!(assembler.assemble_line(&line).unwrap(), Some(instruction.raw.clone()), "{}", line);
+        }
+    }
+
+    #[test]
+    fn sleep_and_sleeping_assemble_once() {
+        let names = NameTables::builtin();
+        let assembler = Assembler::new(&names);
+        let function = parse_function_body(FUNCTION);
+        let text: Vec<String> = function.instructions.iter().map(|i| assembler.disassemble(i)).collect();
+
+        assert_eq!(assembler.assemble(&text.join("\n")).unwrap(), FUNCTION);
+        let assembled = assembler.assemble_function(&text.join("\n")).unwrap();
+        assert_eq!(assembled.size(), FUNCTION.len());
+    }
+
+    #[test]
+    fn sleep_count_must_match_sleeping() {
+        let names = NameTables::builtin();
+        let assembler = Assembler::new(&names);
+        let mismatch = |line| AssemblyError {
+            line,
+            message: SLEEP_MISMATCH.to_string(),
+        };
+        assert_eq!(assembler.assemble("Sleep(10, 30)\n; wait\nSleeping(30, 0)\nEvtEnd()").unwrap(), [
+            0x09, 0x0a, 0x1e, 0x00, 0x01
+        ]);
+        assert_eq!(assembler.assemble("Sleep(10, 60)\nSleeping(30, 0)\nEvtEnd()").unwrap_err(), [mismatch(0)]);
+        assert_eq!(assembler.assemble("Sleep(10, 30)\nEvtEnd()").unwrap_err(), [mismatch(0)]);
+        assert_eq!(assembler.assemble("EvtEnd()\nSleep(10, 30)").unwrap_err(), [mismatch(1)]);
+    }
+
+    #[test]
+    fn code_after_evt_end_is_rejected() {
+        let names = NameTables::builtin();
+        let assembler = Assembler::new(&names);
+        let errors = assembler.assemble_function("EvtEnd()\nEndIf()").unwrap_err();
+        assert_eq!(errors, [AssemblyError {
+            line: 1,
+            message: "The code continues after EvtEnd".to_string(),
+        }]);
+    }
+
+    #[test]
+    fn raw_lines_and_comments() {
+        let names = NameTables::builtin();
+        let assembler = Assembler::new(&names);
+        assert_eq!(assembler.assemble_line("; comment").unwrap(), None);
+        assert_eq!(assembler.assemble_line(&raw_line(&[0xfe, 0x01])).unwrap(), Some(vec![0xfe, 0x01]));
+        assert!(assembler.assemble_line("Unknown(1)").is_err());
+    }
+}
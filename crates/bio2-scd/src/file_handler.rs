@@ -0,0 +1,172 @@
+use crate::fileio::rdt::{
+    RDTHeader, RDT_OFFSET_CAMERA_SWITCHES, RDT_OFFSET_COLLISION,
+    RDT_OFFSET_INIT_SCRIPT, RDT_OFFSET_MAIN_SCRIPT,
+};
+use crate::fileio::rdt_rvd::parse_rdt_rvd;
+use crate::fileio::rdt_sca::parse_rdt_sca;
+use crate::fileio::room_id::{scenario_from_path, Scenario};
+use crate::fileio::name_tables::NameTables;
+use crate::fileio::rdt_scd::{format_scd_functions, parse_rdt_scd_functions, ScdFunction, ScdInstruction};
+use crate::fileio::room_layout::{RoomLayout, ScriptKind, ScriptLocation};
+use crate::fileio::opcode_data::init_opcode_documentation;
+use crate::fileio::utils::read_file;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Contains all data loaded from an RDT file
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct RdtFileData {
+    pub init_script: Vec<String>,
+    pub main_script: Vec<String>,
+    pub init_raw: String,
+    pub main_raw: String,
+    pub opcode_docs: HashMap<String, String>,
+    pub init_functions: Vec<ScdFunction>,
+    pub main_functions: Vec<ScdFunction>,
+    pub room_layout: RoomLayout,
+    pub header: RDTHeader,
+    /// Size of the RDT file in bytes
+    pub file_size: usize,
+    /// Scenario of the pl0 or pl1 directory the file was loaded from
+    #[serde(default)]
+    pub scenario: Option<Scenario>,
+}
+
+impl RdtFileData {
+    /// Decoded functions of the init or main script
+    pub fn functions(&self, script: ScriptKind) -> &[ScdFunction] {
+        match script {
+            ScriptKind::Init => &self.init_functions,
+            ScriptKind::Main => &self.main_functions,
+        }
+    }
+
+    /// The instruction at a script location
+    pub fn instruction(&self, location: ScriptLocation) -> &ScdInstruction {
+        &self.functions(location.script)[location.function].instructions[location.instruction]
+    }
+
+    /// Formats the scripts again, e.g. after the name tables changed
+    pub fn refresh_scripts(&mut self, names: &NameTables) {
+        // Scripts whose function offsets could not be read keep their error text
+        if !self.init_functions.is_empty() {
+            (self.init_script, self.init_raw) = format_scd_functions(&self.init_functions, names);
+        }
+        if !self.main_functions.is_empty() {
+            (self.main_script, self.main_raw) = format_scd_functions(&self.main_functions, names);
+        }
+    }
+}
+
+/// An RDT file loaded as part of a game directory
+pub struct LoadedRoom {
+    pub path: PathBuf,
+    /// File stem, e.g. ROOM1000
+    pub name: String,
+    pub data: RdtFileData,
+}
+
+/// Handles file loading and parsing operations
+pub struct FileHandler;
+
+impl FileHandler {
+    /// Loads and parses an RDT file, returning the parsed data
+    pub fn load_rdt_file(file_path: &Path) -> Result<RdtFileData, String> {
+        let filename = file_path.display().to_string();
+        
+        let contents = read_file(filename)
+            .map_err(|e| format!("File read error: {}", e))?;
+        Self::parse_rdt_file(file_path, &contents)
+    }
+
+    /// Parses the contents of an RDT file, e.g. one edited in memory
+    ///
+    /// The path only serves to tell the scenario from its pl0 or pl1 directory
+    pub fn parse_rdt_file(file_path: &Path, contents: &[u8]) -> Result<RdtFileData, String> {
+        let header = RDTHeader::from(contents)
+            .map_err(|e| format!("Invalid RDT file format: {}", e))?;
+        
+        let init_script_offset = header.offsets[RDT_OFFSET_INIT_SCRIPT];
+        let exec_script_offset = header.offsets[RDT_OFFSET_MAIN_SCRIPT];
+
+        let opcode_docs = init_opcode_documentation();
+        let names = NameTables::builtin();
+        let (init_functions, init_script, init_raw) = decode_script(contents, init_script_offset, &names);
+        let (main_functions, main_script, main_raw) = decode_script(contents, exec_script_offset, &names);
+
+        // Collision and camera switches are optional, so a room without them still loads
+        let collision = parse_rdt_sca(contents, header.offsets[RDT_OFFSET_COLLISION]).unwrap_or_default();
+        let camera_switches = parse_rdt_rvd(contents, header.offsets[RDT_OFFSET_CAMERA_SWITCHES]).unwrap_or_default();
+        let room_layout = RoomLayout::new(collision, camera_switches, &init_functions, &main_functions);
+
+        Ok(RdtFileData {
+            init_script,
+            main_script,
+            init_raw,
+            main_raw,
+            opcode_docs,
+            init_functions,
+            main_functions,
+            room_layout,
+            header,
+            file_size: contents.len(),
+            scenario: scenario_from_path(file_path),
+        })
+    }
+
+    /// Loads every RDT file below a directory, sorted by name
+    ///
+    /// Files that fail to load are reported in the returned error list instead of aborting the scan
+    pub fn load_rdt_directory(dir: &Path) -> Result<(Vec<LoadedRoom>, Vec<String>), String> {
+        let paths = Self::rdt_paths(dir)?;
+        let mut rooms = Vec::new();
+        let mut errors = Vec::new();
+        for path in paths {
+            let name = path.file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default()
+                .to_uppercase();
+            match Self::load_rdt_file(&path) {
+                Ok(data) => rooms.push(LoadedRoom { path, name, data }),
+                Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+        Ok((rooms, errors))
+    }
+
+    /// Paths of every RDT file below a directory, sorted by name
+    pub fn rdt_paths(dir: &Path) -> Result<Vec<PathBuf>, String> {
+        let mut paths = Vec::new();
+        collect_rdt_paths(dir, &mut paths)?;
+        paths.sort();
+        Ok(paths)
+    }
+}
+
+/// Recursively finds files with the .rdt extension
+fn collect_rdt_paths(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| format!("Unable to read directory '{}': {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_rdt_paths(&path, paths)?;
+        } else if path.extension().map_or(false, |ext| ext.eq_ignore_ascii_case("rdt")) {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Decodes a script section once into its functions and their pseudocode and raw hex listings
+///
+/// A section whose function offsets cannot be read has no functions and an error text as listing
+fn decode_script(contents: &[u8], offset: u32, names: &NameTables) -> (Vec<ScdFunction>, Vec<String>, String) {
+    match parse_rdt_scd_functions(contents, offset) {
+        Ok(functions) => {
+            let (script, raw) = format_scd_functions(&functions, names);
+            (functions, script, raw)
+        }
+        Err(_) => (Vec::new(), Vec::new(), "Error reading function offsets".to_string()),
+    }
+}
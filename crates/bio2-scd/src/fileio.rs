@@ -0,0 +1,10 @@
+pub mod name_tables;
+pub mod rdt;
+pub mod rdt_rvd;
+pub mod rdt_sca;
+pub mod rdt_scd;
+pub mod room_id;
+pub mod room_layout;
+pub mod symbols;
+pub mod utils;
+pub mod opcode_data;
@@ -0,0 +1,427 @@
+use crate::fileio::opcode_data::init_opcode_param_names;
+use crate::fileio::rdt_scd::ScdInstruction;
+use crate::fileio::room_id::RoomId;
+use crate::fileio::room_layout::AOT_TYPE_NAMES;
+use crate::fileio::symbols::{
+    parse_number, SymbolDictionary, SymbolKind, BIT_ARRAY_FIELDS, BIT_NUMBER_FIELDS, VARIABLE_FIELDS,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+/// Table of room names, keyed by stage and room number as in 0x100 for ROOM1000 and ROOM1001
+pub const ROOM_TABLE: &str = "room";
+
+/// RE2 item ids
+const ITEM_NAMES: &[(i32, &str)] = &[
+    (0x00, "NONE"),
+    (0x01, "KNIFE"),
+    (0x02, "HANDGUN_LEON"),
+    (0x03, "HANDGUN_CLAIRE"),
+    (0x04, "CUSTOM_HANDGUN"),
+    (0x05, "MAGNUM"),
+    (0x06, "CUSTOM_MAGNUM"),
+    (0x07, "SHOTGUN"),
+    (0x08, "CUSTOM_SHOTGUN"),
+    (0x09, "GRENADE_LAUNCHER"),
+    (0x0a, "GRENADE_LAUNCHER_FLAME"),
+    (0x0b, "GRENADE_LAUNCHER_ACID"),
+    (0x0c, "BOWGUN"),
+    (0x0d, "COLT_SAA"),
+    (0x0e, "SPARKSHOT"),
+    (0x0f, "SUB_MACHINEGUN"),
+    (0x10, "FLAMETHROWER"),
+    (0x11, "ROCKET_LAUNCHER"),
+    (0x12, "GATLING_GUN"),
+    (0x13, "BERETTA"),
+    (0x14, "HANDGUN_AMMO"),
+    (0x15, "SHOTGUN_SHELLS"),
+    (0x16, "MAGNUM_ROUNDS"),
+    (0x17, "FUEL"),
+    (0x18, "GRENADE_ROUNDS"),
+    (0x19, "FLAME_ROUNDS"),
+    (0x1a, "ACID_ROUNDS"),
+    (0x1b, "MACHINEGUN_AMMO"),
+    (0x1c, "SPARKSHOT_AMMO"),
+    (0x1d, "BOWGUN_BOLTS"),
+    (0x1e, "INK_RIBBON"),
+    (0x1f, "SMALL_KEY"),
+    (0x20, "HANDGUN_PARTS"),
+    (0x21, "MAGNUM_PARTS"),
+    (0x22, "SHOTGUN_PARTS"),
+    (0x23, "FIRST_AID_SPRAY"),
+    (0x24, "ANTIVIRUS_BOMB"),
+    (0x25, "CHEMICAL_ACW_32"),
+    (0x26, "GREEN_HERB"),
+    (0x27, "RED_HERB"),
+    (0x28, "BLUE_HERB"),
+    (0x29, "MIXED_HERB_GG"),
+    (0x2a, "MIXED_HERB_GR"),
+    (0x2b, "MIXED_HERB_GB"),
+    (0x2c, "MIXED_HERB_GGG"),
+    (0x2d, "MIXED_HERB_GGB"),
+    (0x2e, "MIXED_HERB_GRB"),
+    (0x2f, "LIGHTER"),
+    (0x30, "LOCK_PICK"),
+    (0x31, "PHOTO_SHERRY"),
+    (0x32, "VALVE_HANDLE"),
+    (0x33, "RED_JEWEL"),
+    (0x34, "RED_CARD_KEY"),
+    (0x35, "BLUE_CARD_KEY"),
+    (0x36, "SERPENT_STONE"),
+    (0x37, "JAGUAR_STONE"),
+    (0x38, "JAGUAR_STONE_L"),
+    (0x39, "JAGUAR_STONE_R"),
+    (0x3a, "EAGLE_STONE"),
+    (0x3b, "ROOK_PLUG"),
+    (0x3c, "KING_PLUG"),
+    (0x3d, "BISHOP_PLUG"),
+    (0x3e, "KNIGHT_PLUG"),
+    (0x3f, "PAWN_PLUG"),
+    (0x40, "QUEEN_PLUG"),
+];
+
+/// RE2 entity ids used by SceEmSet
+const ENEMY_NAMES: &[(i32, &str)] = &[
+    (0x10, "ZOMBIE_COP"),
+    (0x11, "ZOMBIE_BRAD"),
+    (0x12, "ZOMBIE_GUY1"),
+    (0x13, "ZOMBIE_GIRL"),
+    (0x15, "ZOMBIE_TEST"),
+    (0x16, "ZOMBIE_SCIENTIST"),
+    (0x17, "ZOMBIE_GUY2"),
+    (0x18, "ZOMBIE_GUY3"),
+    (0x1e, "ZOMBIE_NAKED"),
+    (0x1f, "ZOMBIE_GUY4"),
+    (0x20, "ZOMBIE_DOG"),
+    (0x21, "CROW"),
+    (0x22, "LICKER_RED"),
+    (0x23, "ALLIGATOR"),
+    (0x24, "LICKER_GREY"),
+    (0x25, "SPIDER"),
+    (0x26, "BABY_SPIDER"),
+    (0x27, "G_EMBRYO"),
+    (0x28, "G_ADULT"),
+    (0x29, "COCKROACH"),
+    (0x2a, "TYRANT1"),
+    (0x2b, "TYRANT2"),
+    (0x2d, "ZOMBIE_ARMS"),
+    (0x2e, "IVY"),
+    (0x2f, "VINES"),
+    (0x30, "BIRKIN1"),
+    (0x31, "BIRKIN2"),
+    (0x32, "BIRKIN3"),
+    (0x33, "BIRKIN4"),
+    (0x34, "BIRKIN5"),
+    (0x39, "IVY_PURPLE"),
+    (0x3a, "GIANT_MOTH"),
+    (0x3b, "MAGGOTS"),
+    (0x40, "CHIEF_IRONS1"),
+    (0x41, "ADA1"),
+    (0x42, "CHIEF_IRONS2"),
+    (0x43, "ADA2"),
+    (0x44, "BEN1"),
+    (0x45, "SHERRY_VEST"),
+    (0x46, "BEN2"),
+    (0x47, "ANNETTE1"),
+    (0x48, "KENDO"),
+    (0x49, "ANNETTE2"),
+    (0x4a, "MARVIN"),
+    (0x4b, "MAYORS_DAUGHTER"),
+    (0x4f, "SHERRY_JACKET"),
+    (0x50, "LEON"),
+    (0x51, "CLAIRE"),
+    (0x54, "LEON_BANDAGED"),
+    (0x55, "CLAIRE_NO_JACKET"),
+    (0x58, "LEON_TANK_TOP"),
+    (0x59, "CLAIRE_BIKER"),
+];
+
+/// Music slots addressed by SceBgmControl
+const BGM_NAMES: &[(i32, &str)] = &[(0, "MAIN"), (1, "SUB0"), (2, "SUB1")];
+
+/// SceBgmControl operations
+const BGM_OPERATION_NAMES: &[(i32, &str)] = &[
+    (0, "NOP"),
+    (1, "START"),
+    (2, "STOP"),
+    (3, "RESTART"),
+    (4, "PAUSE"),
+    (5, "FADEOUT"),
+];
+
+/// Special DoorAotSet key types; the others are the item id of the key
+const KEY_TYPE_NAMES: &[(i32, &str)] = &[(0, "UNLOCKED"), (0xff, "LOCKED")];
+
+/// Which table names the values of an instruction field, as ("Opcode.field", table)
+const FIELD_TABLES: &[(&str, &str)] = &[
+    ("AotSet.type", "aot_type"),
+    ("AotSet4p.type", "aot_type"),
+    ("DoorAotSet.type", "aot_type"),
+    ("DoorAotSet.door_type", "door_type"),
+    ("DoorAotSet.key_type", "key_type"),
+    ("ItemAotSet.type", "aot_type"),
+    ("ItemAotSet.item_id", "item"),
+    ("SceEmSet.id", "enemy"),
+    ("SceBgmControl.bgm", "bgm"),
+    ("SceBgmControl.operation", "bgm_operation"),
+];
+
+/// Display name of an ItemAotSet item id
+pub fn item_name(id: i32) -> String {
+    match ITEM_NAMES.iter().find(|(known, _)| *known == id) {
+        Some((_, name)) => name.to_string(),
+        None => format!("ITEM_{:02X}", id),
+    }
+}
+
+/// Display name of a SceEmSet entity id
+pub fn enemy_name(id: i32) -> String {
+    match ENEMY_NAMES.iter().find(|(known, _)| *known == id) {
+        Some((_, name)) => name.to_string(),
+        None => format!("EM_{:02X}", id),
+    }
+}
+
+/// Layout of a name table file
+///
+/// Values are keyed by decimal or 0x hex strings so the file stays readable, e.g.
+/// `{"tables": {"item": {"0x50": "SPADE_KEY"}}, "fields": {"ItemAotSet.item_id": "item"}}`
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct NameTablesFile {
+    tables: BTreeMap<String, BTreeMap<String, String>>,
+    fields: BTreeMap<String, String>,
+}
+
+/// Enumeration tables that give parameter values symbolic names
+///
+/// Tables are attached to instruction fields by "Opcode.field", using the parameter
+/// names of the opcode documentation
+#[derive(Clone, Debug)]
+pub struct NameTables {
+    tables: BTreeMap<String, BTreeMap<i32, String>>,
+    fields: BTreeMap<String, String>,
+    param_names: HashMap<String, Vec<String>>,
+    /// Names of flags and work variables
+    pub symbols: SymbolDictionary,
+}
+
+/// A decoded parameter with its field name and symbolic value, if any
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct NamedParam {
+    pub field: String,
+    pub value: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// The flag or variable the field refers to, which can be named in the symbol dictionary
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol_kind: Option<SymbolKind>,
+}
+
+impl fmt::Display for NamedParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.symbol {
+            Some(symbol) => write!(f, "{}: {}", self.field, symbol),
+            None => write!(f, "{}: {}", self.field, self.value),
+        }
+    }
+}
+
+impl Default for NameTables {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl NameTables {
+    /// The RE2 tables shipped with the IDE
+    ///
+    /// `door_type` starts empty because the door models differ between releases, and `room`,
+    /// keyed by [`RoomId::key`], starts empty until a name table file names the rooms
+    pub fn builtin() -> Self {
+        let to_table = |entries: &[(i32, &str)]| -> BTreeMap<i32, String> {
+            entries.iter().map(|&(value, name)| (value, name.to_string())).collect()
+        };
+        let aot_types = AOT_TYPE_NAMES
+            .iter()
+            .enumerate()
+            .map(|(value, name)| (value as i32, to_symbol(name)))
+            .collect();
+        // Keys are items, so unknown key types fall back to item names
+        let mut key_types = to_table(ITEM_NAMES);
+        key_types.extend(to_table(KEY_TYPE_NAMES));
+
+        let tables = BTreeMap::from([
+            ("aot_type".to_string(), aot_types),
+            ("item".to_string(), to_table(ITEM_NAMES)),
+            ("enemy".to_string(), to_table(ENEMY_NAMES)),
+            ("bgm".to_string(), to_table(BGM_NAMES)),
+            ("bgm_operation".to_string(), to_table(BGM_OPERATION_NAMES)),
+            ("door_type".to_string(), BTreeMap::new()),
+            (ROOM_TABLE.to_string(), BTreeMap::new()),
+            ("key_type".to_string(), key_types),
+        ]);
+        let fields = FIELD_TABLES
+            .iter()
+            .map(|&(field, table)| (field.to_string(), table.to_string()))
+            .collect();
+
+        Self {
+            tables,
+            fields,
+            param_names: init_opcode_param_names(),
+            symbols: SymbolDictionary::default(),
+        }
+    }
+
+    /// Built-in tables extended with the entries of a JSON name table file
+    pub fn load_file(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read '{}': {}", path.display(), e))?;
+        let file: NameTablesFile = serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid name table file '{}': {}", path.display(), e))?;
+
+        let mut names = Self::builtin();
+        for (table, entries) in file.tables {
+            let target = names.tables.entry(table.clone()).or_default();
+            for (key, name) in entries {
+                let value = parse_number(&key).ok_or(format!("Invalid value '{}' in table '{}'", key, table))?;
+                target.insert(value, name);
+            }
+        }
+        for (field, table) in file.fields {
+            if !names.tables.contains_key(&table) {
+                return Err(format!("Field '{}' refers to unknown table '{}'", field, table));
+            }
+            names.fields.insert(field, table);
+        }
+        Ok(names)
+    }
+
+    /// Every table and field binding as a name table file, to use as a starting point
+    pub fn to_json(&self) -> Result<String, String> {
+        let file = NameTablesFile {
+            tables: self
+                .tables
+                .iter()
+                .map(|(table, entries)| {
+                    let entries = entries.iter().map(|(value, name)| (format!("0x{:02x}", value), name.clone()));
+                    (table.clone(), entries.collect())
+                })
+                .collect(),
+            fields: self.fields.clone(),
+        };
+        serde_json::to_string_pretty(&file).map_err(|e| format!("JSON error: {}", e))
+    }
+
+    /// Symbolic name of a field value, if the field has a table that knows the value
+    pub fn lookup(&self, opcode: &str, field: &str, value: i32) -> Option<&str> {
+        let table = self.fields.get(&format!("{}.{}", opcode, field))?;
+        self.tables.get(table)?.get(&value).map(|s| s.as_str())
+    }
+
+    /// Human-readable name of a room, e.g. "RPD Main Hall"
+    pub fn room_name(&self, id: RoomId) -> Option<&str> {
+        self.tables.get(ROOM_TABLE)?.get(&id.key()).map(|s| s.as_str())
+    }
+
+    /// A room file stem followed by the room's name when it has one
+    pub fn room_label(&self, room: &str) -> String {
+        match RoomId::parse(room).and_then(|id| self.room_name(id)) {
+            Some(name) => format!("{} {}", room, name),
+            None => room.to_string(),
+        }
+    }
+
+    /// Parameters of an instruction with their field names and symbols
+    ///
+    /// Parameters of undocumented opcodes are named by position
+    pub fn named_params(&self, instruction: &ScdInstruction) -> Vec<NamedParam> {
+        let names = self
+            .param_names
+            .get(&instruction.name)
+            .filter(|names| names.len() == instruction.params.len());
+        let fields: Vec<(String, i32)> = instruction
+            .params
+            .iter()
+            .enumerate()
+            .map(|(index, &value)| match names {
+                Some(names) => (names[index].clone(), value),
+                None => (format!("param{}", index + 1), value),
+            })
+            .collect();
+
+        fields
+            .iter()
+            .map(|(field, value)| {
+                let symbol_kind = SymbolDictionary::symbol_kind(&instruction.name, field, &fields);
+                let symbol = self
+                    .lookup(&instruction.name, field, *value)
+                    .or_else(|| self.symbols.name(symbol_kind?))
+                    .map(|s| s.to_string());
+                NamedParam {
+                    field: field.clone(),
+                    value: *value,
+                    symbol,
+                    symbol_kind,
+                }
+            })
+            .collect()
+    }
+
+    /// Documented parameter names of an opcode, one per decoded parameter
+    pub fn param_names(&self, opcode: &str) -> Option<&[String]> {
+        self.param_names.get(opcode).map(|names| names.as_slice())
+    }
+
+    /// Value of a number or symbolic name written for a field, the inverse of `named_params`
+    ///
+    /// `previous` holds the fields before this one, which give the bit array of a flag name
+    pub fn parse_value(&self, opcode: &str, field: &str, text: &str, previous: &[(String, i32)]) -> Option<i32> {
+        if let Some(value) = parse_number(text) {
+            return Some(value);
+        }
+        let find = |names: &BTreeMap<i32, String>| names.iter().find(|(_, name)| *name == text).map(|(value, _)| *value);
+        if let Some(table) = self.fields.get(&format!("{}.{}", opcode, field)) {
+            return find(self.tables.get(table)?);
+        }
+        if BIT_ARRAY_FIELDS.contains(&(opcode, field)) {
+            return find(&self.symbols.bit_arrays);
+        }
+        if BIT_NUMBER_FIELDS.contains(&(opcode, field)) {
+            let array = previous.iter().find(|(name, _)| name == "bit_array")?.1;
+            return self
+                .symbols
+                .flags
+                .iter()
+                .find(|((a, _), name)| *a == array && *name == text)
+                .map(|((_, bit), _)| *bit);
+        }
+        if VARIABLE_FIELDS.contains(&(opcode, field)) {
+            return find(&self.symbols.variables);
+        }
+        None
+    }
+
+    /// Formats an instruction as pseudocode with named parameters
+    pub fn code_line(&self, instruction: &ScdInstruction) -> String {
+        if instruction.error.is_some() || instruction.params.is_empty() {
+            return instruction.code_line();
+        }
+        let params: Vec<String> = self.named_params(instruction).iter().map(|p| p.to_string()).collect();
+        format!("{}({})", instruction.name, params.join(", "))
+    }
+}
+
+/// Turns a display name such as "FlagChange" into a symbol such as "FLAG_CHANGE"
+fn to_symbol(name: &str) -> String {
+    let mut symbol = String::new();
+    for (index, c) in name.chars().enumerate() {
+        if index > 0 && c.is_uppercase() {
+            symbol.push('_');
+        }
+        symbol.push(c.to_ascii_uppercase());
+    }
+    symbol
+}
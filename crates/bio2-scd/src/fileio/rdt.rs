@@ -0,0 +1,156 @@
+// Indices into the RDT offset table
+pub const RDT_OFFSET_COLLISION: usize = 6;
+pub const RDT_OFFSET_CAMERA_SWITCHES: usize = 8;
+pub const RDT_OFFSET_INIT_SCRIPT: usize = 16;
+pub const RDT_OFFSET_MAIN_SCRIPT: usize = 17;
+
+/// Size of the counts and the offset table at the start of every RDT file
+pub const RDT_HEADER_SIZE: usize = 100;
+
+/// What each entry of the RDT offset table points to
+pub const RDT_SECTION_NAMES: [&str; 23] = [
+    "Sound attributes (EDT)",
+    "Sound header (VH)",
+    "Sound bank (VB)",
+    "Unknown",
+    "Unknown",
+    "Unknown",
+    "Collision (SCA)",
+    "Camera positions (RID)",
+    "Camera switches (RVD)",
+    "Lights (LIT)",
+    "Models (MD1)",
+    "Floor sounds (FLR)",
+    "Blocks (BLK)",
+    "Messages, Japanese (MSG)",
+    "Messages, other language (MSG)",
+    "Scrolling background (TIM)",
+    "Init script (SCD)",
+    "Main script (SCD)",
+    "Sprite ids (ESP)",
+    "Sprite data (EFF)",
+    "Sprite textures (TIM)",
+    "Animations (RBJ)",
+    "Unknown",
+];
+
+/// A non-empty entry of the RDT offset table
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct RdtSection {
+    /// Index into the offset table
+    pub index: usize,
+    pub name: String,
+    pub offset: u32,
+    /// Bytes up to the next section or the end of the file
+    pub size: u32,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct RDTHeader {
+    pub num_sprites: u8,
+    pub num_cameras: u8,
+    pub num_models: u8,
+    pub num_items: u8,
+    pub num_doors: u8,
+    pub num_rooms: u8,
+    pub num_reverb: u8, // related to sound
+    pub sprite_max: u8, // max number of .pri sprites used by one of the room's cameras
+    pub offsets: [u32; 23],
+}
+
+impl RDTHeader {
+    pub fn from(v: &[u8]) -> Result<RDTHeader, &'static str> {
+        const HEADER_SIZE: usize = 8;
+        const OFFSET_COUNT: usize = 23;
+        const OFFSET_SIZE: usize = 4;
+        const TOTAL_OFFSET_SIZE: usize = OFFSET_COUNT * OFFSET_SIZE;
+        const MIN_FILE_SIZE: usize = HEADER_SIZE + TOTAL_OFFSET_SIZE;
+
+        if v.len() < MIN_FILE_SIZE {
+            return Err("File too small: expected at least 100 bytes for RDT header");
+        }
+
+        let header = &v[0..HEADER_SIZE];
+        let rdt_offsets_bytes = &v[HEADER_SIZE..HEADER_SIZE + TOTAL_OFFSET_SIZE];
+
+        let mut rdt_offsets = [0; OFFSET_COUNT];
+        for (i, chunk) in rdt_offsets_bytes.chunks(OFFSET_SIZE).enumerate() {
+            if chunk.len() != OFFSET_SIZE {
+                return Err("Invalid offset data: incomplete offset entry");
+            }
+            rdt_offsets[i] = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+
+        let num_sprites = header[0];
+        let num_cameras = header[1];
+        let num_models = header[2];
+        let num_items = header[3];
+        let num_doors = header[4];
+        let num_rooms = header[5];
+        let num_reverb = header[6];
+        let sprite_max = header[7];
+
+        Ok(RDTHeader {
+            num_sprites,
+            num_cameras,
+            num_models,
+            num_items,
+            num_doors,
+            num_rooms,
+            num_reverb,
+            sprite_max,
+            offsets: rdt_offsets,
+        })
+    }
+
+    /// The header as stored at the start of an RDT file
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![
+            self.num_sprites,
+            self.num_cameras,
+            self.num_models,
+            self.num_items,
+            self.num_doors,
+            self.num_rooms,
+            self.num_reverb,
+            self.sprite_max,
+        ];
+        for offset in self.offsets {
+            bytes.extend_from_slice(&offset.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// The sections present in a file of the given length, sorted by offset
+    ///
+    /// The header has no sizes, so each section is assumed to end where the next one starts
+    pub fn sections(&self, file_len: usize) -> Vec<RdtSection> {
+        let mut starts: Vec<u32> = self.offsets.iter().copied().filter(|&offset| offset != 0).collect();
+        starts.sort_unstable();
+        starts.dedup();
+
+        let mut sections: Vec<RdtSection> = self
+            .offsets
+            .iter()
+            .enumerate()
+            .filter(|(_, &offset)| offset != 0)
+            .map(|(index, &offset)| {
+                let end = starts
+                    .iter()
+                    .copied()
+                    .find(|&start| start > offset)
+                    .unwrap_or(file_len as u32)
+                    .max(offset);
+                RdtSection {
+                    index,
+                    name: RDT_SECTION_NAMES[index].to_string(),
+                    offset,
+                    size: end - offset,
+                }
+            })
+            .collect();
+        sections.sort_by_key(|section| (section.offset, section.index));
+        sections
+    }
+}
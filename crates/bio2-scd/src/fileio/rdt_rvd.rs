@@ -0,0 +1,53 @@
+use crate::fileio::utils::{read_i16, read_u32};
+
+const RVD_ELEMENT_SIZE: usize = 20;
+const RVD_END_MARKER: u32 = 0xFFFF_FFFF;
+
+/// A quad on the floor that switches the camera when the player walks into it
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct CameraSwitch {
+    pub flag: u8,
+    pub floor: u8,
+    pub camera_from: u8,
+    pub camera_to: u8,
+    pub points: [(i16, i16); 4],
+}
+
+/// Parse the camera switch zones of a room
+///
+/// Each element is [flag: u8, floor: u8, cam0: u8, cam1: u8, 4 * (x: i16, z: i16)]
+/// and the list ends with 0xFFFFFFFF
+pub fn parse_rdt_rvd(file_contents: &[u8], start_offset: u32) -> Result<Vec<CameraSwitch>, &'static str> {
+    let mut pos = start_offset as usize;
+    if pos == 0 {
+        return Err("Room has no camera switches");
+    }
+
+    let mut switches = Vec::new();
+    loop {
+        if file_contents.len() < pos + 4 {
+            return Err("File too short to read camera switches");
+        }
+        if read_u32(file_contents, pos) == RVD_END_MARKER {
+            break;
+        }
+        if file_contents.len() < pos + RVD_ELEMENT_SIZE {
+            return Err("File too short to read camera switches");
+        }
+
+        let mut points = [(0, 0); 4];
+        for (i, point) in points.iter_mut().enumerate() {
+            let point_pos = pos + 4 + i * 4;
+            *point = (read_i16(file_contents, point_pos), read_i16(file_contents, point_pos + 2));
+        }
+        switches.push(CameraSwitch {
+            flag: file_contents[pos],
+            floor: file_contents[pos + 1],
+            camera_from: file_contents[pos + 2],
+            camera_to: file_contents[pos + 3],
+            points,
+        });
+        pos += RVD_ELEMENT_SIZE;
+    }
+    Ok(switches)
+}
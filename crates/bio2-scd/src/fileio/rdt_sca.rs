@@ -0,0 +1,47 @@
+use crate::fileio::utils::{read_i16, read_u16, read_u32};
+
+const SCA_HEADER_SIZE: usize = 32;
+const SCA_ELEMENT_SIZE: usize = 16;
+
+/// A single collision shape from the room's SCA data
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct CollisionBox {
+    pub x: i16,
+    pub z: i16,
+    pub width: u16,
+    pub depth: u16,
+    pub id: u16,
+    pub shape_type: u16,
+    pub floor: u32,
+}
+
+/// Parse the collision boundaries of a room
+///
+/// The SCA header is [cx: u16, cz: u16, counts: 5u32, ceiling: i32, dummy: u32]
+/// and is followed by `counts[0] - 1` elements of
+/// [x: i16, z: i16, w: u16, d: u16, id: u16, type: u16, floor: u32]
+pub fn parse_rdt_sca(file_contents: &[u8], start_offset: u32) -> Result<Vec<CollisionBox>, &'static str> {
+    let start = start_offset as usize;
+    if start == 0 || file_contents.len() < start + SCA_HEADER_SIZE {
+        return Err("File too short to read collision header");
+    }
+
+    let element_count = read_u32(file_contents, start + 4).saturating_sub(1) as usize;
+    let mut boxes = Vec::with_capacity(element_count);
+    for i in 0..element_count {
+        let pos = start + SCA_HEADER_SIZE + i * SCA_ELEMENT_SIZE;
+        if file_contents.len() < pos + SCA_ELEMENT_SIZE {
+            return Err("File too short to read all collision elements");
+        }
+        boxes.push(CollisionBox {
+            x: read_i16(file_contents, pos),
+            z: read_i16(file_contents, pos + 2),
+            width: read_u16(file_contents, pos + 4),
+            depth: read_u16(file_contents, pos + 6),
+            id: read_u16(file_contents, pos + 8),
+            shape_type: read_u16(file_contents, pos + 10),
+            floor: read_u32(file_contents, pos + 12),
+        });
+    }
+    Ok(boxes)
+}
@@ -0,0 +1,379 @@
+use num;
+use std::collections::HashMap;
+use crate::fileio::name_tables::NameTables;
+use crate::fileio::opcode_data::*;
+
+/// Type sizes in bytes for parameter parsing
+const TYPE_SIZES: &[(&str, usize)] = &[
+    ("u8", 1), ("i8", 1), ("u16", 2), 
+    ("i16", 2), ("u32", 4), ("i32", 4)
+];
+
+/// Parse type information from a format term
+/// 
+/// Examples:
+/// - "4u16" -> ("u16", 8)  // 4 elements × 2 bytes = 8 bytes
+/// - "2i32" -> ("i32", 8)  // 2 elements × 4 bytes = 8 bytes  
+/// - "1u8"  -> ("u8", 1)   // 1 element × 1 byte = 1 byte
+fn parse_type_info(term: &str) -> Result<(&str, usize), String> {
+    for &(suffix, bytes_per_element) in TYPE_SIZES {
+        if term.ends_with(suffix) {
+            let qty: usize = term.strip_suffix(suffix)
+                .ok_or("Invalid format string")?
+                .parse()
+                .map_err(|_| "Invalid number format")?;
+            return Ok((suffix, qty * bytes_per_element));
+        }
+    }
+    Err(format!("Unknown type suffix in: {}", term))
+}
+
+/// Parse bytes based on type suffix
+fn parse_bytes_by_type(data: &[u8], type_suffix: &str) -> Result<Vec<i32>, String> {
+    match type_suffix {
+        "u8" => Ok(data.iter().map(|&x| x as i32).collect()),
+        "i8" => Ok(data.iter().map(|&x| (x as i8) as i32).collect()),
+        "u16" => {
+            Ok(data.chunks_exact(2)
+                .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]) as i32)
+                .collect())
+        },
+        "i16" => {
+            Ok(data.chunks_exact(2)
+                .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]) as i32)
+                .collect())
+        },
+        "u32" => {
+            Ok(data.chunks_exact(4)
+                .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) as i32)
+                .collect())
+        },
+        "i32" => {
+            Ok(data.chunks_exact(4)
+                .map(|chunk| i32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                .collect())
+        },
+        _ => Err(format!("Unknown type suffix: {}", type_suffix)),
+    }
+}
+
+fn parse_function_params(raw_function_params: &[u8], params_format: String) -> Result<Vec<i32>, String> {
+    if params_format.is_empty() {
+        if !raw_function_params.is_empty() {
+            return Err("Expected empty params but got data".to_string());
+        }
+        return Ok(Vec::new());
+    }
+
+    let mut result = Vec::new();
+    let mut offset = 0;
+    
+    for term in params_format.split(',') {
+        let (type_suffix, bytes_needed) = parse_type_info(term)?;
+        
+        let data_slice = &raw_function_params[offset..offset + bytes_needed];
+        let parsed_values = parse_bytes_by_type(data_slice, type_suffix)?;
+        result.extend(parsed_values);
+        
+        offset += bytes_needed;
+    }
+    
+    if raw_function_params.len() != offset {
+        return Err("Mismatch between expected and actual parameter length".to_string());
+    }
+    
+    Ok(result)
+}
+
+/// Encode parameters according to a format string, the inverse of `parse_function_params`
+fn encode_function_params(params: &[i32], params_format: &str) -> Result<Vec<u8>, String> {
+    let mut result = Vec::new();
+    let mut remaining = params;
+
+    for term in params_format.split(',').filter(|term| !term.is_empty()) {
+        let (type_suffix, bytes_needed) = parse_type_info(term)?;
+        let element_size = if type_suffix.ends_with('8') { 1 } else if type_suffix.ends_with("16") { 2 } else { 4 };
+        let count = bytes_needed / element_size;
+        if remaining.len() < count {
+            return Err(format!("Expected more parameters for {}", term));
+        }
+        let (values, rest) = remaining.split_at(count);
+        for &value in values {
+            // Values are stored as read, so truncating restores the original bytes
+            result.extend_from_slice(&value.to_le_bytes()[..element_size]);
+        }
+        remaining = rest;
+    }
+
+    if !remaining.is_empty() {
+        return Err(format!("{} parameters too many", remaining.len()));
+    }
+    Ok(result)
+}
+
+/// Encode an instruction from its opcode byte and decoded parameters
+///
+/// This is the inverse of decoding, so the bytes of any instruction that decoded without
+/// errors are reproduced exactly
+pub fn encode_instruction(opcode: u8, params: &[i32]) -> Result<Vec<u8>, String> {
+    let opcode_info_map = init_opcode_info_map();
+    let info = num::FromPrimitive::from_u8(opcode)
+        .and_then(|opcode: Opcode| opcode_info_map.get(&opcode))
+        .ok_or(format!("Unknown opcode {}", opcode))?;
+
+    let mut bytes = vec![opcode];
+    bytes.extend(encode_function_params(params, &info.function_params)?);
+    if bytes.len() != info.instruction_size as usize {
+        return Err(format!(
+            "{} encodes to {} bytes, expected {}",
+            info.name,
+            bytes.len(),
+            info.instruction_size
+        ));
+    }
+    Ok(bytes)
+}
+
+/// Reasons an instruction could not be fully decoded
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum ScdDecodeError {
+    UnknownOpcode,
+    InvalidParameters(String),
+}
+
+/// A single decoded script instruction
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ScdInstruction {
+    /// Absolute offset of the opcode byte within the RDT file
+    pub offset: usize,
+    pub opcode: u8,
+    pub name: String,
+    pub params: Vec<i32>,
+    pub raw: Vec<u8>,
+    pub error: Option<ScdDecodeError>,
+}
+
+impl ScdInstruction {
+    /// Formats the instruction as a line of pseudocode
+    pub fn code_line(&self) -> String {
+        match &self.error {
+            Some(ScdDecodeError::UnknownOpcode) => format!("Unknown opcode {}", self.opcode),
+            Some(ScdDecodeError::InvalidParameters(e)) => format!("Error parsing parameters: {}", e),
+            None if self.params.is_empty() => format!("{}()", self.name),
+            None => format!("{}({:?})", self.name, self.params),
+        }
+    }
+
+    /// The bytes of the instruction, encoded again from its parameters
+    ///
+    /// Instructions that failed to decode keep their original bytes
+    pub fn encode(&self) -> Result<Vec<u8>, String> {
+        match self.error {
+            Some(_) => Ok(self.raw.clone()),
+            None => encode_instruction(self.opcode, &self.params),
+        }
+    }
+
+    /// Formats the instruction bytes as hex
+    pub fn raw_line(&self) -> String {
+        match self.error {
+            Some(ScdDecodeError::UnknownOpcode) => format!("{:02x}", self.opcode),
+            _ => format!("{:02x?}", self.raw),
+        }
+    }
+}
+
+/// A script function, which runs until its EvtEnd instruction
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ScdFunction {
+    pub index: usize,
+    pub instructions: Vec<ScdInstruction>,
+}
+
+impl ScdFunction {
+    /// Bytes the decoded instructions take, counting a Sleep as its opcode only since the
+    /// Sleeping instruction after it holds the rest
+    pub fn size(&self) -> usize {
+        self.instructions
+            .iter()
+            .map(|instruction| match instruction.error {
+                None if instruction.opcode == Opcode::Sleep as u8 => 1,
+                _ => instruction.raw.len(),
+            })
+            .sum()
+    }
+}
+
+/// Decode the opcode at the given offset
+fn decode_instruction(
+    file_contents: &[u8],
+    offset: usize,
+    opcode_info_map: &HashMap<Opcode, OpcodeInfo>,
+) -> ScdInstruction {
+    let opcode_byte = file_contents[offset];
+    let opcode: Option<Opcode> = num::FromPrimitive::from_u8(opcode_byte);
+
+    let info = match opcode.as_ref().and_then(|x| opcode_info_map.get(x)) {
+        Some(info) => info,
+        None => {
+            return ScdInstruction {
+                offset,
+                opcode: opcode_byte,
+                name: String::new(),
+                params: Vec::new(),
+                raw: vec![opcode_byte],
+                error: Some(ScdDecodeError::UnknownOpcode),
+            };
+        }
+    };
+
+    let end = (offset + info.instruction_size as usize).min(file_contents.len());
+    let raw = file_contents[offset..end].to_vec();
+    let (params, error) = if end - offset != info.instruction_size as usize {
+        (Vec::new(), Some(ScdDecodeError::InvalidParameters("Instruction extends past end of file".to_string())))
+    } else {
+        match parse_function_params(&raw[1..], info.function_params.clone()) {
+            Ok(params) => (params, None),
+            Err(e) => (Vec::new(), Some(ScdDecodeError::InvalidParameters(e))),
+        }
+    };
+
+    ScdInstruction {
+        offset,
+        opcode: opcode_byte,
+        name: info.name.clone(),
+        params,
+        raw,
+        error,
+    }
+}
+
+fn parse_single_function(
+    file_contents: &[u8],
+    start_offset: u32,
+    function_offset: u16,
+    function_length: usize,
+    function_index: usize,
+    opcode_info_map: &HashMap<Opcode, OpcodeInfo>,
+) -> ScdFunction {
+    let mut instructions = Vec::new();
+    let mut function_cur_offset = start_offset as usize + function_offset as usize;
+
+    for _line_num in 0..function_length {
+        if function_cur_offset >= file_contents.len() {
+            break;
+        }
+
+        let instruction = decode_instruction(file_contents, function_cur_offset, opcode_info_map);
+        function_cur_offset += instruction.raw.len();
+
+        // Sleep contains sleep and sleeping commands
+        // The sleep command is [0x9 0xa u8 u8], where 0x9 is the sleep command and 0xa is the sleeping command
+        if instruction.opcode == Opcode::Sleep as u8 && instruction.error.is_none() {
+            function_cur_offset -= instruction.raw.len() - 1;
+        }
+
+        let is_end = instruction.opcode == Opcode::EvtEnd as u8;
+        instructions.push(instruction);
+
+        if is_end {
+            break;
+        }
+    }
+
+    ScdFunction {
+        index: function_index,
+        instructions,
+    }
+}
+
+fn read_function_offsets(file_contents: &[u8], start_offset: u32) -> Result<Vec<u16>, &'static str> {
+    // Check if we have enough data to read the first offset
+    if file_contents.len() < (start_offset + 2) as usize {
+        return Err("File too short to read function offsets");
+    }
+    
+    let mut function_offsets = Vec::new();
+    
+    // Read the first offset
+    let first_offset = u16::from_le_bytes([
+        file_contents[start_offset as usize],
+        file_contents[(start_offset + 1) as usize],
+    ]);
+    function_offsets.push(first_offset);
+    
+    // Read remaining offsets
+    for i in (2..first_offset).step_by(2) {
+        let offset_pos = start_offset as usize + i as usize;
+        if file_contents.len() < offset_pos + 2 {
+            return Err("File too short to read all function offsets");
+        }
+        
+        let next_offset = u16::from_le_bytes([
+            file_contents[offset_pos],
+            file_contents[offset_pos + 1],
+        ]);
+        function_offsets.push(next_offset);
+    }
+    
+    Ok(function_offsets)
+}
+
+/// Decode every function of the SCD stream starting at `start_offset`
+pub fn parse_rdt_scd_functions(file_contents: &[u8], start_offset: u32) -> Result<Vec<ScdFunction>, &'static str> {
+    let opcode_info_map = init_opcode_info_map();
+    let function_offsets = read_function_offsets(file_contents, start_offset)?;
+    let function_offsets_count = function_offsets.len();
+
+    let mut functions = Vec::new();
+    for i in 0..function_offsets_count {
+        let function_length = match i {
+            i if i == function_offsets_count - 1 => file_contents.len() - start_offset as usize,
+            _ => function_offsets[i + 1].saturating_sub(function_offsets[i]).into(),
+        };
+
+        functions.push(parse_single_function(
+            file_contents,
+            start_offset,
+            function_offsets[i],
+            function_length,
+            i,
+            &opcode_info_map,
+        ));
+    }
+    Ok(functions)
+}
+
+/// Decode a function body on its own, such as freshly assembled bytes
+pub fn parse_function_body(bytes: &[u8]) -> ScdFunction {
+    parse_single_function(bytes, 0, 0, bytes.len(), 0, &init_opcode_info_map())
+}
+
+/// Format decoded functions as pseudocode lines and raw hex lines
+pub fn format_scd_functions(functions: &[ScdFunction], names: &NameTables) -> (Vec<String>, String) {
+    let mut code_lines = Vec::new();
+    let mut raw_code_lines = Vec::new();
+
+    for function in functions {
+        code_lines.push(format!("Start Function {}:", function.index));
+        raw_code_lines.push(format!("Start Function {}:", function.index));
+
+        for instruction in &function.instructions {
+            code_lines.push(names.code_line(instruction));
+            raw_code_lines.push(instruction.raw_line());
+        }
+
+        code_lines.push(format!("End Function {}\n", function.index));
+        raw_code_lines.push(format!("End Function {}\n", function.index));
+    }
+    (code_lines, raw_code_lines.join("\n"))
+}
+
+// SCD file is within RDT
+pub fn parse_rdt_scd_stream(file_contents: &[u8], start_offset: u32) -> (Vec<String>, String) {
+    match parse_rdt_scd_functions(file_contents, start_offset) {
+        Ok(functions) => format_scd_functions(&functions, &NameTables::builtin()),
+        // If we can't read offsets, return empty results
+        Err(_) => (Vec::new(), "Error reading function offsets".to_string()),
+    }
+}
@@ -0,0 +1,150 @@
+use crate::file_handler::RdtFileData;
+use std::fmt;
+use std::path::Path;
+
+/// Stages of the game, numbered from 1 in file names
+pub const STAGE_COUNT: u8 = 7;
+
+/// The scenario a room file belongs to, by the pl0 or pl1 directory it ships in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Scenario {
+    #[serde(rename = "pl0")]
+    Leon,
+    #[serde(rename = "pl1")]
+    Claire,
+}
+
+/// pl0 or pl1 among the directories of a path
+pub fn scenario_from_path(path: &Path) -> Option<Scenario> {
+    path.components().rev().find_map(|component| {
+        let name = component.as_os_str().to_string_lossy();
+        if name.eq_ignore_ascii_case("pl0") {
+            Some(Scenario::Leon)
+        } else if name.eq_ignore_ascii_case("pl1") {
+            Some(Scenario::Claire)
+        } else {
+            None
+        }
+    })
+}
+
+/// Stage, room and player of a room file, as encoded in file names such as ROOM1000.RDT
+///
+/// The stage is the one of the file name, which starts at 1, while DoorAotSet counts stages from
+/// 0. The room number is hex and the last digit is the player, 0 for Leon and 1 for Claire.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Deserialize, serde::Serialize)]
+pub struct RoomId {
+    pub stage: u8,
+    pub room: u8,
+    pub player: u8,
+}
+
+impl RoomId {
+    /// Parses a file name or stem such as ROOM1000.RDT or room10a1
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.to_uppercase();
+        let name = name.strip_suffix(".RDT").unwrap_or(&name);
+        let digits = name.strip_prefix("ROOM")?;
+        if digits.len() != 4 || !digits.is_ascii() {
+            return None;
+        }
+        Some(Self {
+            stage: u8::from_str_radix(&digits[0..1], 16).ok()?,
+            room: u8::from_str_radix(&digits[1..3], 16).ok()?,
+            player: digits[3..4].parse().ok()?,
+        })
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::parse(&path.file_name()?.to_string_lossy())
+    }
+
+    /// The room a DoorAotSet leads to, in the files of the given player
+    pub fn from_door(next_stage: i32, next_room: i32, player: u8) -> Self {
+        Self {
+            stage: (next_stage + 1) as u8,
+            room: next_room as u8,
+            player,
+        }
+    }
+
+    /// Key of the room in the "room" name table, the same for both players, e.g. 0x100 for ROOM1000
+    pub fn key(&self) -> i32 {
+        (self.stage as i32) << 8 | self.room as i32
+    }
+
+    pub fn player_name(&self) -> Option<&'static str> {
+        match self.scenario()? {
+            Scenario::Leon => Some("Leon"),
+            Scenario::Claire => Some("Claire"),
+        }
+    }
+
+    /// The scenario whose files hold the room
+    pub fn scenario(&self) -> Option<Scenario> {
+        match self.player {
+            0 => Some(Scenario::Leon),
+            1 => Some(Scenario::Claire),
+            _ => None,
+        }
+    }
+
+    /// Where the name disagrees with the loaded file
+    ///
+    /// The header holds no room number or player, so the name is checked against the doors of
+    /// the room: none may lead back into the room itself, and a room whose doors all lead into
+    /// other stages most likely belongs to one of those. The player is checked against the
+    /// pl0/pl1 scenario the file was loaded from
+    pub fn check(&self, data: &RdtFileData) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.stage == 0 || self.stage > STAGE_COUNT {
+            problems.push(format!("stage {} is outside stages 1 to {}", self.stage, STAGE_COUNT));
+        }
+        match (self.scenario(), data.scenario) {
+            (None, _) => problems.push(format!("player {} is neither 0 (Leon) nor 1 (Claire)", self.player)),
+            (Some(named), Some(stored)) if named != stored => {
+                problems.push(format!("named for {} but the file belongs to {}", self.player_name().unwrap_or_default(), stored))
+            }
+            _ => {}
+        }
+
+        let doors = &data.room_layout.doors;
+        let targets: Vec<RoomId> = doors
+            .iter()
+            .map(|door| Self::from_door(door.next_stage, door.next_room, self.player))
+            .collect();
+        for (door, target) in doors.iter().zip(&targets) {
+            if *target == *self {
+                problems.push(format!("door AOT {} leads back into {}", door.area.aot, self));
+            }
+        }
+        if !targets.is_empty() && targets.iter().all(|target| target.stage != self.stage) {
+            let mut stages: Vec<u8> = targets.iter().map(|target| target.stage).collect();
+            stages.sort_unstable();
+            stages.dedup();
+            let stages: Vec<String> = stages.iter().map(|stage| stage.to_string()).collect();
+            problems.push(format!(
+                "named for stage {} but its doors only lead into stage {}",
+                self.stage,
+                stages.join(", ")
+            ));
+        }
+        problems
+    }
+}
+
+impl fmt::Display for Scenario {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Scenario::Leon => "pl0 (Leon)",
+            Scenario::Claire => "pl1 (Claire)",
+        };
+        f.pad(name)
+    }
+}
+
+impl fmt::Display for RoomId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ROOM{:X}{:02X}{}", self.stage, self.room, self.player)
+    }
+}
@@ -0,0 +1,318 @@
+use crate::fileio::opcode_data::Opcode;
+use crate::fileio::rdt_rvd::CameraSwitch;
+use crate::fileio::rdt_sca::CollisionBox;
+use crate::fileio::rdt_scd::{ScdFunction, ScdInstruction};
+
+// Parameter positions shared by AotSet, AotSet4p, DoorAotSet and ItemAotSet
+pub const PARAM_AOT: usize = 0;
+pub const PARAM_AOT_TYPE: usize = 2;
+pub const PARAM_AOT_FLOOR: usize = 3;
+pub const PARAM_AOT_X: usize = 5;
+pub const PARAM_AOT_Z: usize = 6;
+pub const PARAM_AOT_WIDTH: usize = 7;
+pub const PARAM_AOT_DEPTH: usize = 8;
+
+// DoorAotSet parameter positions
+pub const PARAM_DOOR_NEXT_X: usize = 9;
+pub const PARAM_DOOR_NEXT_Y: usize = 10;
+pub const PARAM_DOOR_NEXT_Z: usize = 11;
+pub const PARAM_DOOR_NEXT_DIR: usize = 12;
+pub const PARAM_DOOR_NEXT_STAGE: usize = 13;
+pub const PARAM_DOOR_NEXT_ROOM: usize = 14;
+pub const PARAM_DOOR_NEXT_CAMERA: usize = 15;
+pub const PARAM_DOOR_KEY_ID: usize = 20;
+pub const PARAM_DOOR_KEY_TYPE: usize = 21;
+
+// ItemAotSet parameter positions
+pub const PARAM_ITEM_ID: usize = 9;
+pub const PARAM_ITEM_AMOUNT: usize = 10;
+pub const PARAM_ITEM_PICKED_INDEX: usize = 11;
+
+// SceEmSet parameter positions
+pub const PARAM_EM_NO: usize = 1;
+pub const PARAM_EM_ID: usize = 2;
+pub const PARAM_EM_TYPE: usize = 3;
+pub const PARAM_EM_STATUS: usize = 4;
+pub const PARAM_EM_SET_FLAG: usize = 8;
+pub const PARAM_EM_X: usize = 9;
+pub const PARAM_EM_Y: usize = 10;
+pub const PARAM_EM_Z: usize = 11;
+pub const PARAM_EM_DIR: usize = 12;
+pub const PARAM_EM_MOTION: usize = 13;
+
+/// Number of angle units in a full turn
+pub const FULL_TURN: i32 = 4096;
+
+/// Names of the AOT trigger types, indexed by the AOT type field
+pub const AOT_TYPE_NAMES: &[&str] = &[
+    "Auto", "Door", "Item", "Normal", "Message", "Event", "FlagChange", "Water",
+    "Move", "Save", "ItemBox", "Damage", "Status", "Hikidashi", "Windows",
+];
+
+/// Which script of the room an instruction belongs to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum ScriptKind {
+    Init,
+    Main,
+}
+
+impl ScriptKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScriptKind::Init => "init",
+            ScriptKind::Main => "main",
+        }
+    }
+}
+
+/// Points back to the instruction that created a map shape
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub struct ScriptLocation {
+    pub script: ScriptKind,
+    pub function: usize,
+    pub instruction: usize,
+}
+
+/// A trigger area created by AotSet or AotSet4p
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct AotShape {
+    pub source: ScriptLocation,
+    pub aot: i32,
+    pub aot_type: i32,
+    pub floor: i32,
+    pub points: Vec<(i32, i32)>,
+}
+
+/// A door trigger area and where it leads
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct DoorMarker {
+    pub area: AotShape,
+    pub next_stage: i32,
+    pub next_room: i32,
+    pub next_camera: i32,
+    pub next_pos: (i32, i32, i32),
+    pub next_dir: i32,
+    pub key_id: i32,
+    pub key_type: i32,
+}
+
+/// An item pickup trigger area
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct ItemMarker {
+    pub area: AotShape,
+    pub item_id: i32,
+    pub amount: i32,
+    pub item_picked_index: i32,
+}
+
+/// An entity placed with SceEmSet
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct EnemyMarker {
+    pub source: ScriptLocation,
+    /// Entity slot the enemy is loaded into
+    pub em_no: i32,
+    pub id: i32,
+    pub enemy_type: i32,
+    pub status: i32,
+    pub em_set_flag: i32,
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub dir: i32,
+    pub motion: i32,
+}
+
+/// A map shape created by a single instruction
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub enum LayoutShape {
+    Aot(AotShape),
+    Door(DoorMarker),
+    Item(ItemMarker),
+    Enemy(EnemyMarker),
+}
+
+/// Everything that can be drawn on a top-down map of a room
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct RoomLayout {
+    pub collision: Vec<CollisionBox>,
+    pub camera_switches: Vec<CameraSwitch>,
+    pub aots: Vec<AotShape>,
+    pub doors: Vec<DoorMarker>,
+    pub items: Vec<ItemMarker>,
+    pub enemies: Vec<EnemyMarker>,
+}
+
+impl AotShape {
+    /// Average of the corner points
+    pub fn center(&self) -> (i32, i32) {
+        let count = self.points.len().max(1) as i32;
+        let sum = self.points.iter().fold((0, 0), |acc, p| (acc.0 + p.0, acc.1 + p.1));
+        (sum.0 / count, sum.1 / count)
+    }
+}
+
+impl RoomLayout {
+    /// Collects shapes from the decoded init and main scripts
+    pub fn new(
+        collision: Vec<CollisionBox>,
+        camera_switches: Vec<CameraSwitch>,
+        init_functions: &[ScdFunction],
+        main_functions: &[ScdFunction],
+    ) -> Self {
+        let mut layout = RoomLayout {
+            collision,
+            camera_switches,
+            ..Default::default()
+        };
+        layout.add_script(ScriptKind::Init, init_functions);
+        layout.add_script(ScriptKind::Main, main_functions);
+        layout
+    }
+
+    /// Returns the (min_x, min_z, max_x, max_z) extent of every shape
+    pub fn bounds(&self) -> Option<(i32, i32, i32, i32)> {
+        let mut points: Vec<(i32, i32)> = Vec::new();
+        for b in &self.collision {
+            points.push((b.x as i32, b.z as i32));
+            points.push((b.x as i32 + b.width as i32, b.z as i32 + b.depth as i32));
+        }
+        for switch in &self.camera_switches {
+            points.extend(switch.points.iter().map(|&(x, z)| (x as i32, z as i32)));
+        }
+        for aot in self.all_areas() {
+            points.extend(aot.points.iter().copied());
+        }
+        points.extend(self.enemies.iter().map(|e| (e.x, e.z)));
+
+        let first = *points.first()?;
+        Some(points.iter().fold((first.0, first.1, first.0, first.1), |b, p| {
+            (b.0.min(p.0), b.1.min(p.1), b.2.max(p.0), b.3.max(p.1))
+        }))
+    }
+
+    /// Iterates over the trigger areas of plain AOTs, doors and items
+    pub fn all_areas(&self) -> impl Iterator<Item = &AotShape> {
+        self.aots
+            .iter()
+            .chain(self.doors.iter().map(|d| &d.area))
+            .chain(self.items.iter().map(|i| &i.area))
+    }
+
+    fn add_script(&mut self, script: ScriptKind, functions: &[ScdFunction]) {
+        for function in functions {
+            for (index, instruction) in function.instructions.iter().enumerate() {
+                let source = ScriptLocation {
+                    script,
+                    function: function.index,
+                    instruction: index,
+                };
+                self.add_instruction(source, instruction);
+            }
+        }
+    }
+
+    /// Adds the shape created by an AOT or SceEmSet instruction, other instructions are ignored
+    pub fn add_instruction(&mut self, source: ScriptLocation, instruction: &ScdInstruction) {
+        match LayoutShape::from_instruction(source, instruction) {
+            Some(LayoutShape::Aot(aot)) => self.aots.push(aot),
+            Some(LayoutShape::Door(door)) => self.doors.push(door),
+            Some(LayoutShape::Item(item)) => self.items.push(item),
+            Some(LayoutShape::Enemy(enemy)) => self.enemies.push(enemy),
+            None => {}
+        }
+    }
+}
+
+impl LayoutShape {
+    /// The shape created by an AOT or SceEmSet instruction
+    pub fn from_instruction(source: ScriptLocation, instruction: &ScdInstruction) -> Option<Self> {
+        if instruction.error.is_some() {
+            return None;
+        }
+        let p = &instruction.params;
+
+        let shape = match num::FromPrimitive::from_u8(instruction.opcode)? {
+            Opcode::AotSet => LayoutShape::Aot(rect_aot(source, p)),
+            Opcode::AotSet4p => LayoutShape::Aot(AotShape {
+                source,
+                aot: p[PARAM_AOT],
+                aot_type: p[PARAM_AOT_TYPE],
+                floor: p[PARAM_AOT_FLOOR],
+                points: p[PARAM_AOT_X..PARAM_AOT_X + 8]
+                    .chunks_exact(2)
+                    .map(|c| (c[0], c[1]))
+                    .collect(),
+            }),
+            Opcode::DoorAotSet => LayoutShape::Door(DoorMarker {
+                area: rect_aot(source, p),
+                next_stage: p[PARAM_DOOR_NEXT_STAGE],
+                next_room: p[PARAM_DOOR_NEXT_ROOM],
+                next_camera: p[PARAM_DOOR_NEXT_CAMERA],
+                next_pos: (p[PARAM_DOOR_NEXT_X], p[PARAM_DOOR_NEXT_Y], p[PARAM_DOOR_NEXT_Z]),
+                next_dir: p[PARAM_DOOR_NEXT_DIR],
+                key_id: p[PARAM_DOOR_KEY_ID],
+                key_type: p[PARAM_DOOR_KEY_TYPE],
+            }),
+            Opcode::ItemAotSet => LayoutShape::Item(ItemMarker {
+                area: rect_aot(source, p),
+                item_id: p[PARAM_ITEM_ID],
+                amount: p[PARAM_ITEM_AMOUNT],
+                item_picked_index: p[PARAM_ITEM_PICKED_INDEX],
+            }),
+            Opcode::SceEmSet => LayoutShape::Enemy(EnemyMarker {
+                source,
+                em_no: p[PARAM_EM_NO],
+                id: p[PARAM_EM_ID],
+                enemy_type: p[PARAM_EM_TYPE],
+                status: p[PARAM_EM_STATUS],
+                em_set_flag: p[PARAM_EM_SET_FLAG],
+                x: p[PARAM_EM_X],
+                y: p[PARAM_EM_Y],
+                z: p[PARAM_EM_Z],
+                dir: p[PARAM_EM_DIR],
+                motion: p[PARAM_EM_MOTION],
+            }),
+            _ => return None,
+        };
+        Some(shape)
+    }
+}
+
+/// Builds the rectangle of AotSet, DoorAotSet and ItemAotSet
+fn rect_aot(source: ScriptLocation, p: &[i32]) -> AotShape {
+    let (x, z) = (p[PARAM_AOT_X], p[PARAM_AOT_Z]);
+    let (w, d) = (p[PARAM_AOT_WIDTH], p[PARAM_AOT_DEPTH]);
+    AotShape {
+        source,
+        aot: p[PARAM_AOT],
+        aot_type: p[PARAM_AOT_TYPE],
+        floor: p[PARAM_AOT_FLOOR],
+        points: vec![(x, z), (x + w, z), (x + w, z + d), (x, z + d)],
+    }
+}
+
+/// Display name of an AOT type
+pub fn aot_type_name(aot_type: i32) -> String {
+    match AOT_TYPE_NAMES.get(aot_type as usize) {
+        Some(name) => name.to_string(),
+        None => format!("Type{}", aot_type),
+    }
+}
+
+/// Colour used to draw an AOT of the given type, as RGB
+pub fn aot_type_color(aot_type: i32) -> (u8, u8, u8) {
+    match aot_type {
+        0 => (120, 120, 255),
+        1 => (230, 160, 60),
+        2 => (80, 220, 120),
+        3 => (180, 180, 180),
+        4 => (240, 240, 110),
+        5 => (230, 90, 200),
+        6 => (90, 200, 230),
+        7 => (60, 110, 230),
+        9 => (255, 255, 255),
+        10 => (150, 110, 60),
+        11 => (230, 60, 60),
+        _ => (160, 120, 220),
+    }
+}
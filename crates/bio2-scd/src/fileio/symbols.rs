@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+
+/// Instruction fields holding a bit array index, as (opcode, field)
+pub const BIT_ARRAY_FIELDS: &[(&str, &str)] = &[("CheckBit", "bit_array"), ("SetBit", "bit_array")];
+
+/// Instruction fields holding a bit number within the bit array of the same instruction
+pub const BIT_NUMBER_FIELDS: &[(&str, &str)] = &[("CheckBit", "bit_number"), ("SetBit", "bit_number")];
+
+/// Instruction fields holding a work variable id
+pub const VARIABLE_FIELDS: &[(&str, &str)] = &[
+    ("Compare", "var_id"),
+    ("Save", "var_id"),
+    ("Calc", "var_id"),
+    ("Copy", "dest_var_id"),
+    ("Copy", "source_var_id"),
+    ("Switch", "var_id"),
+];
+
+/// What a symbol names
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum SymbolKind {
+    BitArray(i32),
+    Flag(i32, i32),
+    Variable(i32),
+}
+
+/// Game-wide names for bit arrays, individual flags and work variables
+///
+/// Stored as JSON with decimal or 0x hex keys, flags keyed by "array:bit", e.g.
+/// `{"bit_arrays": {"1": "SCENARIO"}, "flags": {"1:5": "MET_ADA"}, "variables": {"3": "ROOM_STATE"}}`
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(into = "SymbolFile", try_from = "SymbolFile")]
+pub struct SymbolDictionary {
+    pub bit_arrays: BTreeMap<i32, String>,
+    pub flags: BTreeMap<(i32, i32), String>,
+    pub variables: BTreeMap<i32, String>,
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct SymbolFile {
+    bit_arrays: BTreeMap<String, String>,
+    flags: BTreeMap<String, String>,
+    variables: BTreeMap<String, String>,
+}
+
+impl SymbolDictionary {
+    /// Reads a dictionary from a JSON file
+    pub fn load_file(path: &std::path::Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read '{}': {}", path.display(), e))?;
+        serde_json::from_str(&contents).map_err(|e| format!("Invalid symbol dictionary '{}': {}", path.display(), e))
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| format!("JSON error: {}", e))
+    }
+
+    /// Adds every name of another dictionary, replacing names of the same symbol
+    pub fn merge(&mut self, other: SymbolDictionary) {
+        self.bit_arrays.extend(other.bit_arrays);
+        self.flags.extend(other.flags);
+        self.variables.extend(other.variables);
+    }
+
+    pub fn name(&self, kind: SymbolKind) -> Option<&str> {
+        match kind {
+            SymbolKind::BitArray(array) => self.bit_arrays.get(&array),
+            SymbolKind::Flag(array, bit) => self.flags.get(&(array, bit)),
+            SymbolKind::Variable(var_id) => self.variables.get(&var_id),
+        }
+        .map(|s| s.as_str())
+    }
+
+    /// Sets or, with an empty name, removes the name of a symbol
+    pub fn set_name(&mut self, kind: SymbolKind, name: String) {
+        let name = name.trim().to_string();
+        match kind {
+            SymbolKind::BitArray(array) => update_name(&mut self.bit_arrays, array, name),
+            SymbolKind::Flag(array, bit) => update_name(&mut self.flags, (array, bit), name),
+            SymbolKind::Variable(var_id) => update_name(&mut self.variables, var_id, name),
+        }
+    }
+
+    /// The symbol referred to by an instruction field, given every field of the instruction
+    pub fn symbol_kind(opcode: &str, field: &str, fields: &[(String, i32)]) -> Option<SymbolKind> {
+        let value = fields.iter().find(|(name, _)| name == field)?.1;
+        if BIT_ARRAY_FIELDS.contains(&(opcode, field)) {
+            return Some(SymbolKind::BitArray(value));
+        }
+        if BIT_NUMBER_FIELDS.contains(&(opcode, field)) {
+            let array = fields.iter().find(|(name, _)| name == "bit_array")?.1;
+            return Some(SymbolKind::Flag(array, value));
+        }
+        VARIABLE_FIELDS
+            .contains(&(opcode, field))
+            .then_some(SymbolKind::Variable(value))
+    }
+}
+
+impl From<SymbolDictionary> for SymbolFile {
+    fn from(dictionary: SymbolDictionary) -> Self {
+        Self {
+            bit_arrays: dictionary.bit_arrays.into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+            flags: dictionary
+                .flags
+                .into_iter()
+                .map(|((array, bit), v)| (format!("{}:{}", array, bit), v))
+                .collect(),
+            variables: dictionary.variables.into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+        }
+    }
+}
+
+impl TryFrom<SymbolFile> for SymbolDictionary {
+    type Error = String;
+
+    fn try_from(file: SymbolFile) -> Result<Self, Self::Error> {
+        let number = |key: &str| parse_number(key).ok_or(format!("Invalid symbol key '{}'", key));
+        let mut dictionary = SymbolDictionary::default();
+        for (key, name) in file.bit_arrays {
+            dictionary.bit_arrays.insert(number(&key)?, name);
+        }
+        for (key, name) in file.flags {
+            let (array, bit) = key.split_once(':').ok_or(format!("Flag key '{}' is not array:bit", key))?;
+            dictionary.flags.insert((number(array)?, number(bit)?), name);
+        }
+        for (key, name) in file.variables {
+            dictionary.variables.insert(number(&key)?, name);
+        }
+        Ok(dictionary)
+    }
+}
+
+fn update_name<K: Ord>(names: &mut BTreeMap<K, String>, key: K, name: String) {
+    if name.is_empty() {
+        names.remove(&key);
+    } else {
+        names.insert(key, name);
+    }
+}
+
+/// Parses a decimal or 0x hex number
+pub fn parse_number(text: &str) -> Option<i32> {
+    let text = text.trim();
+    match text.strip_prefix("0x") {
+        Some(hex) => i32::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
@@ -0,0 +1,107 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+// general function for any file type
+pub fn read_file(file_name: String) -> Result<Vec<u8>, String> {
+    if file_name.is_empty() {
+        return Err("No file specified".to_string());
+    }
+
+    let path = Path::new(&file_name);
+    if !path.exists() {
+        return Err(format!("File not found: {}", file_name));
+    }
+    
+    let mut file_content = Vec::new();
+    let mut file = File::open(&file_name)
+        .map_err(|e| format!("Unable to open file '{}': {}", file_name, e))?;
+    file.read_to_end(&mut file_content)
+        .map_err(|e| format!("Unable to read file '{}': {}", file_name, e))?;
+    Ok(file_content)
+}
+
+/// Write a file, creating its parent directories
+pub fn write_file(path: &Path, contents: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Unable to create '{}': {}", parent.display(), e))?;
+    }
+    std::fs::write(path, contents).map_err(|e| format!("Unable to write '{}': {}", path.display(), e))
+}
+
+/// Relative path with forward slashes, so manifests work on every platform
+pub fn portable_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// CRC32 as used by BPS, zip and PNG
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc = CRC32_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { 0xedb8_8320 ^ (crc >> 1) } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// Read a little-endian u16 at the given offset
+pub fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+/// Read a little-endian i16 at the given offset
+pub fn read_i16(data: &[u8], offset: usize) -> i16 {
+    i16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+/// Read a little-endian u32 at the given offset
+pub fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+/// Joins entries after a table of u16 offsets to each of them, as used by scripts and messages
+pub fn with_offset_table(entries: Vec<Vec<u8>>) -> Result<Vec<u8>, String> {
+    let mut table = Vec::new();
+    let mut offset = entries.len() * 2;
+    for entry in &entries {
+        let value = u16::try_from(offset).map_err(|_| "Section is larger than 64 KiB".to_string())?;
+        table.extend_from_slice(&value.to_le_bytes());
+        offset += entry.len();
+    }
+    table.extend(entries.into_iter().flatten());
+    Ok(table)
+}
+
+/// Format bytes as hex dump lines of 16 bytes with their offset and ASCII text
+pub fn hexdump(data: &[u8], start_offset: usize) -> String {
+    let mut text = String::new();
+    for (i, chunk) in data.chunks(16).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+            .collect();
+        text.push_str(&format!("{:08x}  {:<47}  |{}|\n", start_offset + i * 16, hex.join(" "), ascii));
+    }
+    text
+}
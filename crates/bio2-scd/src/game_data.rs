@@ -0,0 +1,46 @@
+use crate::analysis::door_graph::DoorGraph;
+use crate::analysis::enemy_table::{collect_enemies, EnemyPlacement};
+use crate::analysis::item_table::{collect_items, ItemPlacement};
+use crate::analysis::xref::XrefIndex;
+use crate::fileio::name_tables::NameTables;
+use crate::file_handler::{FileHandler, LoadedRoom};
+use std::path::{Path, PathBuf};
+
+/// Every room of a game directory and the analyses built from them
+pub struct GameData {
+    pub dir: PathBuf,
+    pub rooms: Vec<LoadedRoom>,
+    pub load_errors: Vec<String>,
+    pub door_graph: DoorGraph,
+    pub items: Vec<ItemPlacement>,
+    pub enemies: Vec<EnemyPlacement>,
+    pub xref: XrefIndex,
+}
+
+impl GameData {
+    /// Loads all RDT files below `dir` and runs the game-wide analyses
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let (rooms, load_errors) = FileHandler::load_rdt_directory(dir)?;
+        if rooms.is_empty() {
+            return Err(format!("No RDT files found in '{}'", dir.display()));
+        }
+        let door_graph = DoorGraph::build(&rooms);
+        let items = collect_items(&rooms);
+        let enemies = collect_enemies(&rooms);
+        let xref = XrefIndex::build(&rooms, &NameTables::builtin());
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            rooms,
+            load_errors,
+            door_graph,
+            items,
+            enemies,
+            xref,
+        })
+    }
+
+    /// Path of a loaded room by file stem
+    pub fn room_path(&self, name: &str) -> Option<&Path> {
+        self.rooms.iter().find(|r| r.name == name).map(|r| r.path.as_path())
+    }
+}
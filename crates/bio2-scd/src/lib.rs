@@ -0,0 +1,64 @@
+//! Reading, decoding and analysing the room files (.RDT) of Resident Evil 2 / Biohazard 2
+//!
+//! An RDT file starts with a header of object counts and a table of section offsets
+//! ([`fileio::rdt::RDTHeader`]). Two of the sections are SCD scripts: the init script, which
+//! runs once when the room loads, and the main script, which runs its event tasks every frame.
+//! Each script is a table of function offsets followed by bytecode, decoded into
+//! [`fileio::rdt_scd::ScdFunction`]s of [`fileio::rdt_scd::ScdInstruction`]s using the opcode
+//! tables in [`fileio::opcode_data`].
+//!
+//! [`FileHandler`] loads a whole room at once, and the [`analysis`] and [`vm`] modules work on
+//! the loaded rooms without any GUI:
+//!
+//! ```no_run
+//! use bio2_scd::FileHandler;
+//! use std::path::Path;
+//!
+//! let room = FileHandler::load_rdt_file(Path::new("ROOM1000.RDT"))?;
+//! println!("{} cameras", room.header.num_cameras);
+//! for function in &room.main_functions {
+//!     for instruction in &function.instructions {
+//!         println!("{:06x} {}", instruction.offset, instruction.code_line());
+//!     }
+//! }
+//! # Ok::<(), String>(())
+//! ```
+//!
+//! [`schema::RoomDocument`] turns a loaded room into a versioned JSON document with the header,
+//! section map, named parameters and diagnostics, reads such documents back and writes their
+//! scripts into the room.
+
+#![warn(clippy::all, rust_2018_idioms)]
+
+/// Encoding pseudocode lines back into SCD bytecode
+pub mod assembler;
+/// Game-wide and per-room analyses: door graph, placement tables, cross-references, path
+/// conditions, room state, linting, validation and script diffs
+pub mod analysis;
+/// Loading whole RDT files and directories of them
+pub mod file_handler;
+/// Parsers for the RDT header and sections, the opcode tables and the name tables used to
+/// format scripts
+pub mod fileio;
+/// Every room of a game directory with the game-wide analyses built from them
+pub mod game_data;
+/// Three-way merge of the script edits of two mods against the original room
+pub mod merge;
+/// Script edits described in TOML manifests, applied to the rooms of an unmodified game
+pub mod mod_manifest;
+/// IPS and BPS patches between original and modified RDT files
+pub mod patch;
+/// Versioned JSON documents of decoded rooms for other tools
+pub mod schema;
+#[cfg(test)]
+mod test_rooms;
+/// Unpacking rooms into a tree of text and binary files that packs back into the same RDT files
+pub mod text_tree;
+/// Script virtual machine and debugger
+pub mod vm;
+/// Writing edited scripts back into RDT files with recomputed block lengths and offsets
+pub mod writer;
+
+pub use file_handler::{FileHandler, LoadedRoom, RdtFileData};
+pub use fileio::rdt::RDTHeader;
+pub use fileio::rdt_scd::{encode_instruction, parse_rdt_scd_functions, parse_rdt_scd_stream, ScdFunction, ScdInstruction};
@@ -0,0 +1,321 @@
+use crate::analysis::script_diff::{common_subsequence, comparable};
+use crate::file_handler::RdtFileData;
+use crate::fileio::room_layout::ScriptKind;
+use crate::writer::{placed_instructions, write_scripts, PlacedInstruction};
+use std::fmt;
+
+/// Which side of a conflict ends up in the merged room
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Resolution {
+    Ours,
+    Theirs,
+    /// Our instructions followed by theirs
+    Both,
+    /// Neither edit, the original instructions
+    Base,
+}
+
+pub const RESOLUTIONS: [Resolution; 4] = [Resolution::Ours, Resolution::Theirs, Resolution::Both, Resolution::Base];
+
+/// Instructions that both mods changed in different ways
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct MergeConflict {
+    pub script: ScriptKind,
+    pub function: usize,
+    /// Index of the first conflicting instruction in the original function
+    pub base_start: usize,
+    pub base: Vec<PlacedInstruction>,
+    pub ours: Vec<PlacedInstruction>,
+    pub theirs: Vec<PlacedInstruction>,
+    pub resolution: Option<Resolution>,
+}
+
+/// A run of merged instructions or a conflict to resolve
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+enum MergePart {
+    Merged(Vec<PlacedInstruction>),
+    /// Index into `RoomMerge::conflicts`
+    Conflict(usize),
+}
+
+/// A script function merged from the functions with the same index in the three rooms
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct MergedFunction {
+    pub script: ScriptKind,
+    pub index: usize,
+    /// Whether the result differs from the original function
+    pub changed: bool,
+    parts: Vec<MergePart>,
+}
+
+/// Three-way merge of the scripts of two mods of the same room
+///
+/// Functions are matched by index and their instructions aligned with the original as in
+/// [`crate::analysis::script_diff`]. Where only one mod changed a run of instructions its version
+/// is taken; where both changed it differently the run becomes a conflict.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct RoomMerge {
+    pub functions: Vec<MergedFunction>,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl RoomMerge {
+    pub fn new(base: &RdtFileData, ours: &RdtFileData, theirs: &RdtFileData) -> Self {
+        let mut merge = RoomMerge {
+            functions: Vec::new(),
+            conflicts: Vec::new(),
+        };
+        for script in [ScriptKind::Init, ScriptKind::Main] {
+            let placed = |data: &RdtFileData| -> Vec<Vec<PlacedInstruction>> {
+                data.functions(script).iter().map(placed_instructions).collect()
+            };
+            let (base, ours, theirs) = (placed(base), placed(ours), placed(theirs));
+            let count = base.len().max(ours.len()).max(theirs.len());
+            for index in 0..count {
+                // A function missing from one room merges like an empty one
+                let function = |functions: &[Vec<PlacedInstruction>]| functions.get(index).cloned().unwrap_or_default();
+                merge.merge_function(script, index, &function(&base), &function(&ours), &function(&theirs));
+            }
+        }
+        merge
+    }
+
+    /// Number of conflicts without a resolution
+    pub fn unresolved(&self) -> usize {
+        self.conflicts.iter().filter(|conflict| conflict.resolution.is_none()).count()
+    }
+
+    /// Functions merged without any conflict that differ from the original
+    pub fn merged_automatically(&self) -> usize {
+        self.functions
+            .iter()
+            .filter(|function| function.changed && function.parts.iter().all(|part| matches!(part, MergePart::Merged(_))))
+            .count()
+    }
+
+    /// Resolves every open conflict the same way
+    pub fn resolve_all(&mut self, resolution: Resolution) {
+        for conflict in self.conflicts.iter_mut().filter(|conflict| conflict.resolution.is_none()) {
+            conflict.resolution = Some(resolution);
+        }
+    }
+
+    /// Instructions of a merged function with its conflicts resolved
+    pub fn instructions(&self, function: &MergedFunction) -> Result<Vec<PlacedInstruction>, String> {
+        let mut instructions = Vec::new();
+        for part in &function.parts {
+            match part {
+                MergePart::Merged(merged) => instructions.extend(merged.iter().cloned()),
+                MergePart::Conflict(index) => {
+                    let conflict = &self.conflicts[*index];
+                    match conflict.resolution {
+                        Some(Resolution::Ours) => instructions.extend(conflict.ours.iter().cloned()),
+                        Some(Resolution::Theirs) => instructions.extend(conflict.theirs.iter().cloned()),
+                        Some(Resolution::Both) => {
+                            instructions.extend(conflict.ours.iter().cloned());
+                            instructions.extend(conflict.theirs.iter().cloned());
+                        }
+                        Some(Resolution::Base) => instructions.extend(conflict.base.iter().cloned()),
+                        None => return Err(format!("{} is not resolved", conflict)),
+                    }
+                }
+            }
+        }
+        Ok(instructions)
+    }
+
+    /// Writes the merged scripts into the original RDT file, see [`write_scripts`]
+    pub fn write(&self, base_contents: &[u8]) -> Result<(Vec<u8>, Vec<String>), String> {
+        let mut scripts = Vec::new();
+        for script in [ScriptKind::Init, ScriptKind::Main] {
+            let mut functions = Vec::new();
+            for function in self.functions.iter().filter(|f| f.script == script) {
+                functions.push(match function.changed {
+                    true => Some(self.instructions(function)?),
+                    false => None,
+                });
+            }
+            scripts.push((script, functions));
+        }
+        write_scripts(base_contents, &scripts)
+    }
+
+    /// Aligns both edits of a function with the original and merges them region by region
+    fn merge_function(
+        &mut self,
+        script: ScriptKind,
+        index: usize,
+        base: &[PlacedInstruction],
+        ours: &[PlacedInstruction],
+        theirs: &[PlacedInstruction],
+    ) {
+        let keys = |instructions: &[PlacedInstruction]| -> Vec<_> {
+            instructions.iter().map(|placed| comparable(&placed.instruction)).collect()
+        };
+        let (base_keys, our_keys, their_keys) = (keys(base), keys(ours), keys(theirs));
+        let matches = |keys: &[_]| {
+            let mut matched = vec![None; base.len()];
+            for (b, other) in common_subsequence(&base_keys, keys) {
+                matched[b] = Some(other);
+            }
+            matched
+        };
+        let (our_match, their_match) = (matches(&our_keys), matches(&their_keys));
+
+        let mut parts = Vec::new();
+        let mut changed = false;
+        let mut merged = Vec::new();
+        let (mut b, mut o, mut t) = (0, 0, 0);
+        loop {
+            // Next original instruction that both mods kept
+            let stable = (b..base.len()).find_map(|next| Some((next, our_match[next]?, their_match[next]?)));
+            let (next_b, next_o, next_t) = stable.unwrap_or((base.len(), ours.len(), theirs.len()));
+
+            if (next_b, next_o, next_t) != (b, o, t) {
+                let (base_run, our_run, their_run) = (&base[b..next_b], &ours[o..next_o], &theirs[t..next_t]);
+                let (base_run_keys, our_run_keys, their_run_keys) =
+                    (&base_keys[b..next_b], &our_keys[o..next_o], &their_keys[t..next_t]);
+                let taken = if our_run_keys == base_run_keys {
+                    Some(their_run)
+                } else if their_run_keys == base_run_keys || our_run_keys == their_run_keys {
+                    Some(our_run)
+                } else {
+                    None
+                };
+                match taken {
+                    Some(run) => {
+                        changed |= keys(run) != base_run_keys;
+                        merged.extend(run.iter().cloned());
+                    }
+                    None => {
+                        if !merged.is_empty() {
+                            parts.push(MergePart::Merged(std::mem::take(&mut merged)));
+                        }
+                        parts.push(MergePart::Conflict(self.conflicts.len()));
+                        self.conflicts.push(MergeConflict {
+                            script,
+                            function: index,
+                            base_start: b,
+                            base: base_run.to_vec(),
+                            ours: our_run.to_vec(),
+                            theirs: their_run.to_vec(),
+                            resolution: None,
+                        });
+                        changed = true;
+                    }
+                }
+            }
+            if stable.is_none() {
+                break;
+            }
+            merged.push(base[next_b].clone());
+            (b, o, t) = (next_b + 1, next_o + 1, next_t + 1);
+        }
+        if !merged.is_empty() {
+            parts.push(MergePart::Merged(merged));
+        }
+        self.functions.push(MergedFunction {
+            script,
+            index,
+            changed,
+            parts,
+        });
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Resolution::Ours => "ours",
+            Resolution::Theirs => "theirs",
+            Resolution::Both => "both",
+            Resolution::Base => "base",
+        };
+        f.pad(name)
+    }
+}
+
+impl std::str::FromStr for Resolution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RESOLUTIONS
+            .into_iter()
+            .find(|resolution| resolution.to_string() == s)
+            .ok_or(format!("Unknown resolution '{}', expected ours, theirs, both or base", s))
+    }
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Conflict in {} function {} at instruction {}", self.script.name(), self.function, self.base_start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rooms::{init_function, load, room};
+
+    const BASE: &str = "IfStart(dummy: 0, block_length: 8)
+CheckBit(bit_array: 1, bit_number: 5, value: 0)
+SetBit(bit_array: 1, bit_number: 3, operation: 1)
+EndIf()
+SetBit(bit_array: 1, bit_number: 1, operation: 1)
+EvtEnd()";
+
+    fn merge(ours: &str, theirs: &str) -> (Vec<u8>, RoomMerge) {
+        let base = room(&[BASE], &["EvtEnd()"]);
+        let merge = RoomMerge::new(&load(&base), &load(&room(&[ours], &["EvtEnd()"])), &load(&room(&[theirs], &["EvtEnd()"])));
+        (base, merge)
+    }
+
+    #[test]
+    fn edits_of_different_instructions_merge_cleanly() {
+        let ours = BASE.replace("bit_number: 5", "bit_number: 6");
+        let theirs = BASE.replace("bit_number: 1, operation: 1", "bit_number: 1, operation: 0");
+        let (base, merge) = merge(&ours, &theirs);
+        assert!(merge.conflicts.is_empty());
+        assert_eq!(merge.merged_automatically(), 1);
+
+        let (bytes, _) = merge.write(&base).unwrap();
+        let expected = BASE
+            .replace("bit_number: 5", "bit_number: 6")
+            .replace("bit_number: 1, operation: 1", "bit_number: 1, operation: 0");
+        assert_eq!(init_function(&load(&bytes), 0), expected);
+    }
+
+    #[test]
+    fn conflicting_edits_are_not_written() {
+        let ours = BASE.replace("bit_number: 3", "bit_number: 4");
+        let theirs = BASE.replace("bit_number: 3", "bit_number: 7");
+        let (base, merge) = merge(&ours, &theirs);
+        assert_eq!(merge.unresolved(), 1);
+        let conflict = &merge.conflicts[0];
+        assert_eq!((conflict.script, conflict.function, conflict.base_start), (ScriptKind::Init, 0, 2));
+        assert!(merge.write(&base).is_err());
+    }
+
+    #[test]
+    fn resolving_both_recomputes_the_enclosing_block_length() {
+        let ours = BASE.replace("bit_number: 3", "bit_number: 4");
+        let theirs = BASE.replace("bit_number: 3", "bit_number: 7");
+        let (base, mut merge) = merge(&ours, &theirs);
+        merge.resolve_all(Resolution::Both);
+
+        let (bytes, _) = merge.write(&base).unwrap();
+        let expected = BASE.replace(
+            "block_length: 8)\nCheckBit(bit_array: 1, bit_number: 5, value: 0)\nSetBit(bit_array: 1, bit_number: 3, operation: 1)",
+            "block_length: 12)\nCheckBit(bit_array: 1, bit_number: 5, value: 0)\n\
+             SetBit(bit_array: 1, bit_number: 4, operation: 1)\nSetBit(bit_array: 1, bit_number: 7, operation: 1)",
+        );
+        assert_eq!(init_function(&load(&bytes), 0), expected);
+    }
+
+    #[test]
+    fn unchanged_functions_keep_their_bytes() {
+        let (base, merge) = merge(BASE, BASE);
+        assert!(merge.functions.iter().all(|function| !function.changed));
+        assert_eq!(merge.write(&base).unwrap().0, base);
+    }
+}
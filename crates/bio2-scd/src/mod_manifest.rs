@@ -0,0 +1,716 @@
+use crate::assembler::Assembler;
+use crate::file_handler::FileHandler;
+use crate::fileio::name_tables::NameTables;
+use crate::fileio::rdt_scd::{parse_rdt_scd_functions, ScdInstruction};
+use crate::fileio::rdt::{RDTHeader, RDT_OFFSET_INIT_SCRIPT, RDT_OFFSET_MAIN_SCRIPT};
+use crate::fileio::room_layout::ScriptKind;
+use crate::fileio::utils::{portable_path, read_file, write_file};
+use crate::writer::{placed_instructions, write_scripts, PlacedInstruction};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A set of script edits to apply to the rooms of an unmodified game, read from TOML
+///
+/// ```toml
+/// name = "Shotgun in the main hall"
+///
+/// [[edit]]
+/// room = "ROOM1000"
+/// script = "init"
+/// function = 0
+/// action = "set"
+/// anchor = { instruction = "ItemAotSet", params = { item_id = "HANDGUN_CLAIRE" } }
+/// set = { item_id = "SHOTGUN", amount = 7 }
+///
+/// [[edit]]
+/// room = "ROOM1000"
+/// script = "main"
+/// function = 0
+/// action = "insert_after"
+/// anchor = { line = "SetBit(bit_array: 1, bit_number: 5, operation: 1)" }
+/// label = "hall_flags"
+/// code = """
+/// SetBit(bit_array: 1, bit_number: 6, operation: 1)
+/// """
+/// ```
+///
+/// Every anchor has to match exactly one instruction of the function, or the one picked by
+/// `occurrence`, so a manifest made for another release or an already modified room fails
+/// instead of editing the wrong instruction.
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModManifest {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, rename = "edit")]
+    pub edits: Vec<ModEdit>,
+}
+
+/// What an edit does at its anchor
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EditAction {
+    /// Replaces `count` instructions from the anchor with the code
+    Replace,
+    InsertBefore,
+    InsertAfter,
+    /// Removes `count` instructions from the anchor
+    Delete,
+    /// Changes parameters of the anchor instruction
+    Set,
+}
+
+/// One edit of a script function
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModEdit {
+    /// File stem such as ROOM1000, or the path of the room relative to the game directory
+    pub room: String,
+    /// "init" or "main"
+    #[serde(default = "default_script")]
+    pub script: String,
+    pub function: usize,
+    pub action: EditAction,
+    pub anchor: Anchor,
+    /// Instructions replaced or deleted from the anchor on
+    #[serde(default = "default_count")]
+    pub count: usize,
+    /// Pseudocode lines to insert or replace with, in the syntax of the assembler
+    #[serde(default)]
+    pub code: String,
+    /// New parameter values for the set action, as numbers or names
+    #[serde(default)]
+    pub set: BTreeMap<String, toml::Value>,
+    /// Name of the first instruction the edit writes, for the anchors of later edits
+    pub label: Option<String>,
+}
+
+/// The instruction an edit applies to
+///
+/// Every given criterion has to hold for an instruction to match
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Anchor {
+    /// Instruction name, e.g. ItemAotSet
+    pub instruction: Option<String>,
+    /// Parameter values the instruction must have, as numbers or names
+    #[serde(default)]
+    pub params: BTreeMap<String, toml::Value>,
+    /// A whole pseudocode line that assembles to the same bytes as the instruction
+    pub line: Option<String>,
+    /// Label given by an earlier edit of this or another manifest
+    pub label: Option<String>,
+    /// Which of several matching instructions, counted from 1
+    pub occurrence: Option<usize>,
+}
+
+/// An instruction of a modded function with the labels edits gave it
+#[derive(Clone, Debug)]
+struct ModLine {
+    placed: PlacedInstruction,
+    labels: Vec<String>,
+}
+
+/// A script function as edited so far
+#[derive(Clone, Debug)]
+struct ModFunction {
+    changed: bool,
+    lines: Vec<ModLine>,
+}
+
+/// A room with the edits applied so far
+#[derive(Clone, Debug)]
+struct ModRoom {
+    contents: Vec<u8>,
+    init: Vec<ModFunction>,
+    main: Vec<ModFunction>,
+}
+
+/// The rooms of a game directory with the edits of one or more manifests applied in order
+///
+/// Later manifests see the rooms as earlier ones left them, so they can anchor on the labels
+/// and code of those. Nothing is written until [`ModdedGame::write`].
+pub struct ModdedGame {
+    game_dir: PathBuf,
+    paths: Vec<PathBuf>,
+    rooms: BTreeMap<PathBuf, ModRoom>,
+}
+
+/// A room written by [`ModdedGame::write`]
+#[derive(Clone, Debug)]
+pub struct ModdedRoom {
+    /// Path relative to the game and output directories
+    pub file: String,
+    /// Sections that moved, see [`crate::writer::replace_sections`]
+    pub warnings: Vec<String>,
+}
+
+fn default_script() -> String {
+    ScriptKind::Main.name().to_string()
+}
+
+fn default_count() -> usize {
+    1
+}
+
+impl ModManifest {
+    pub fn load_file(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Unable to read '{}': {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| format!("Invalid mod manifest '{}': {}", path.display(), e))
+    }
+}
+
+impl ModdedGame {
+    pub fn new(game_dir: &Path) -> Result<Self, String> {
+        Ok(Self {
+            game_dir: game_dir.to_path_buf(),
+            paths: FileHandler::rdt_paths(game_dir)?,
+            rooms: BTreeMap::new(),
+        })
+    }
+
+    /// Number of rooms changed by the manifests applied so far
+    pub fn changed_rooms(&self) -> usize {
+        self.rooms.values().filter(|room| room.is_changed()).count()
+    }
+
+    /// Applies every edit of a manifest, or none of them if one fails
+    pub fn apply(&mut self, manifest: &ModManifest, names: &NameTables) -> Result<(), String> {
+        let assembler = Assembler::new(names);
+        let mut rooms = self.rooms.clone();
+        for (index, edit) in manifest.edits.iter().enumerate() {
+            let context = format!("edit {} ({} {} function {})", index + 1, edit.room, edit.script, edit.function);
+            self.apply_edit(&mut rooms, edit, names, &assembler)
+                .map_err(|e| format!("{}: {}", context, e))?;
+        }
+        self.rooms = rooms;
+        Ok(())
+    }
+
+    /// Writes the changed rooms below the output directory, keeping the layout of the game
+    pub fn write(&self, output_dir: &Path) -> Result<Vec<ModdedRoom>, String> {
+        let mut files = Vec::new();
+        for (path, room) in self.rooms.iter().filter(|(_, room)| room.is_changed()) {
+            let (bytes, warnings) = room.write()?;
+            files.push((path, bytes, warnings));
+        }
+        let mut written = Vec::new();
+        for (path, bytes, warnings) in files {
+            let relative = path.strip_prefix(&self.game_dir).unwrap_or(path);
+            write_file(&output_dir.join(relative), &bytes)?;
+            written.push(ModdedRoom {
+                file: portable_path(relative),
+                warnings,
+            });
+        }
+        Ok(written)
+    }
+
+    fn apply_edit(
+        &self,
+        rooms: &mut BTreeMap<PathBuf, ModRoom>,
+        edit: &ModEdit,
+        names: &NameTables,
+        assembler: &Assembler<'_>,
+    ) -> Result<(), String> {
+        let script = match edit.script.as_str() {
+            "init" => ScriptKind::Init,
+            "main" => ScriptKind::Main,
+            other => return Err(format!("Unknown script '{}', expected init or main", other)),
+        };
+        let path = self.room_path(&edit.room)?;
+        if !rooms.contains_key(&path) {
+            rooms.insert(path.clone(), ModRoom::load(&path)?);
+        }
+        let room = rooms.get_mut(&path).unwrap();
+        let functions = match script {
+            ScriptKind::Init => &mut room.init,
+            ScriptKind::Main => &mut room.main,
+        };
+        let count = functions.len();
+        let function = functions
+            .get_mut(edit.function)
+            .ok_or(format!("The {} script has {} functions", script.name(), count))?;
+
+        let at = find_anchor(&function.lines, &edit.anchor, names, assembler)?;
+        let code = match edit.action {
+            EditAction::Replace | EditAction::InsertBefore | EditAction::InsertAfter => {
+                let code = assemble_code(&edit.code, assembler)?;
+                if code.is_empty() {
+                    return Err("No code to write, use action = \"delete\" to remove instructions".to_string());
+                }
+                code
+            }
+            EditAction::Delete | EditAction::Set => {
+                if !edit.code.trim().is_empty() {
+                    return Err(format!("The {} action takes no code", edit.action));
+                }
+                Vec::new()
+            }
+        };
+        if edit.action != EditAction::Set && !edit.set.is_empty() {
+            return Err("Only the set action takes new parameter values".to_string());
+        }
+        if let Some(label) = &edit.label {
+            if edit.action == EditAction::Delete {
+                return Err("A delete leaves no instruction to label".to_string());
+            }
+            if function.lines.iter().any(|line| line.labels.contains(label)) {
+                return Err(format!("Label '{}' is already defined in this function", label));
+            }
+        }
+
+        let new_lines: Vec<ModLine> = code
+            .into_iter()
+            .map(|placed| ModLine {
+                placed,
+                labels: Vec::new(),
+            })
+            .collect();
+        let first = match edit.action {
+            EditAction::Replace | EditAction::Delete => {
+                if edit.count == 0 || at + edit.count > function.lines.len() {
+                    return Err(format!(
+                        "Cannot remove {} instructions from instruction {} of {}",
+                        edit.count,
+                        at,
+                        function.lines.len()
+                    ));
+                }
+                function.lines.splice(at..at + edit.count, new_lines);
+                at
+            }
+            EditAction::InsertBefore => {
+                function.lines.splice(at..at, new_lines);
+                at
+            }
+            EditAction::InsertAfter => {
+                function.lines.splice(at + 1..at + 1, new_lines);
+                at + 1
+            }
+            EditAction::Set => {
+                set_params(&mut function.lines[at].placed.instruction, &edit.set, names)?;
+                at
+            }
+        };
+        if let Some(label) = &edit.label {
+            function.lines[first].labels.push(label.clone());
+        }
+        function.changed = true;
+        Ok(())
+    }
+
+    /// The RDT file an edit refers to by file stem or relative path
+    fn room_path(&self, room: &str) -> Result<PathBuf, String> {
+        let room = room.replace('\\', "/");
+        let matches: Vec<&PathBuf> = self
+            .paths
+            .iter()
+            .filter(|path| {
+                let relative = portable_path(path.strip_prefix(&self.game_dir).unwrap_or(path));
+                let stem = path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+                relative.eq_ignore_ascii_case(&room) || stem.eq_ignore_ascii_case(&room)
+            })
+            .collect();
+        match matches[..] {
+            [path] => Ok(path.clone()),
+            [] => Err(format!("No room {} in '{}'", room, self.game_dir.display())),
+            _ => Err(format!(
+                "Room {} is ambiguous, use one of: {}",
+                room,
+                matches
+                    .iter()
+                    .map(|path| portable_path(path.strip_prefix(&self.game_dir).unwrap_or(path)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+impl ModRoom {
+    fn load(path: &Path) -> Result<Self, String> {
+        let contents = read_file(path.display().to_string())?;
+        let header = RDTHeader::from(&contents).map_err(|e| format!("Invalid RDT file format: {}", e))?;
+        let functions = |section: usize| -> Vec<ModFunction> {
+            parse_rdt_scd_functions(&contents, header.offsets[section])
+                .unwrap_or_default()
+                .iter()
+                .map(|function| ModFunction {
+                    changed: false,
+                    lines: placed_instructions(function)
+                        .into_iter()
+                        .map(|placed| ModLine {
+                            placed,
+                            labels: Vec::new(),
+                        })
+                        .collect(),
+                })
+                .collect()
+        };
+        Ok(Self {
+            init: functions(RDT_OFFSET_INIT_SCRIPT),
+            main: functions(RDT_OFFSET_MAIN_SCRIPT),
+            contents,
+        })
+    }
+
+    fn is_changed(&self) -> bool {
+        self.init.iter().chain(&self.main).any(|function| function.changed)
+    }
+
+    fn write(&self) -> Result<(Vec<u8>, Vec<String>), String> {
+        let script = |functions: &[ModFunction]| -> Vec<Option<Vec<PlacedInstruction>>> {
+            functions
+                .iter()
+                .map(|function| {
+                    let instructions = function.lines.iter().map(|line| line.placed.clone());
+                    function.changed.then(|| instructions.collect())
+                })
+                .collect()
+        };
+        write_scripts(
+            &self.contents,
+            &[(ScriptKind::Init, script(&self.init)), (ScriptKind::Main, script(&self.main))],
+        )
+    }
+}
+
+/// Index of the instruction an anchor picks
+fn find_anchor(lines: &[ModLine], anchor: &Anchor, names: &NameTables, assembler: &Assembler<'_>) -> Result<usize, String> {
+    if anchor.instruction.is_none() && anchor.line.is_none() && anchor.label.is_none() {
+        return Err("The anchor needs an instruction, line or label".to_string());
+    }
+    if anchor.instruction.is_none() && !anchor.params.is_empty() {
+        return Err("Anchor parameters need an instruction name".to_string());
+    }
+    let line_bytes = match &anchor.line {
+        Some(line) => Some(
+            assembler
+                .assemble_line(line)
+                .map_err(|e| format!("Anchor line '{}': {}", line, e))?
+                .ok_or("The anchor line is empty")?,
+        ),
+        None => None,
+    };
+
+    let mut matches = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let instruction = &line.placed.instruction;
+        if anchor.label.as_ref().map_or(false, |label| !line.labels.contains(label)) {
+            continue;
+        }
+        if anchor.instruction.as_ref().map_or(false, |name| *name != instruction.name) {
+            continue;
+        }
+        if line_bytes.as_ref().map_or(false, |bytes| instruction.encode().as_ref() != Ok(bytes)) {
+            continue;
+        }
+        if matches_params(instruction, &anchor.params, names)? {
+            matches.push(index);
+        }
+    }
+
+    match (anchor.occurrence, &matches[..]) {
+        (_, []) => Err(format!("Anchor {} matches no instruction", anchor)),
+        (None, [index]) => Ok(*index),
+        (None, _) => Err(format!(
+            "Anchor {} matches {} instructions (at {}), set anchor.occurrence to pick one",
+            anchor,
+            matches.len(),
+            matches.iter().map(|index| index.to_string()).collect::<Vec<_>>().join(", ")
+        )),
+        (Some(occurrence), _) => match occurrence.checked_sub(1).and_then(|i| matches.get(i)) {
+            Some(index) => Ok(*index),
+            None => Err(format!(
+                "Anchor {} matches {} instructions, not occurrence {}",
+                anchor,
+                matches.len(),
+                occurrence
+            )),
+        },
+    }
+}
+
+/// Whether the named parameters of an instruction have the given values
+fn matches_params(
+    instruction: &ScdInstruction,
+    params: &BTreeMap<String, toml::Value>,
+    names: &NameTables,
+) -> Result<bool, String> {
+    let named = names.named_params(instruction);
+    for (field, expected) in params {
+        let position = field_position(instruction, field, names)?;
+        let previous: Vec<(String, i32)> = named[..position].iter().map(|p| (p.field.clone(), p.value)).collect();
+        if named[position].value != parse_param(instruction, field, expected, &previous, names)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Changes named parameters of an instruction and encodes it again
+fn set_params(
+    instruction: &mut ScdInstruction,
+    values: &BTreeMap<String, toml::Value>,
+    names: &NameTables,
+) -> Result<(), String> {
+    if values.is_empty() {
+        return Err("The set action needs new parameter values".to_string());
+    }
+    if instruction.error.is_some() {
+        return Err(format!("Instruction {} did not decode", instruction.code_line()));
+    }
+    for (field, value) in values {
+        let position = field_position(instruction, field, names)?;
+        let previous: Vec<(String, i32)> = names.named_params(instruction)[..position]
+            .iter()
+            .map(|p| (p.field.clone(), p.value))
+            .collect();
+        instruction.params[position] = parse_param(instruction, field, value, &previous, names)?;
+    }
+    instruction.raw = instruction.encode()?;
+    Ok(())
+}
+
+/// Index of a named parameter in the decoded parameters of an instruction
+fn field_position(instruction: &ScdInstruction, field: &str, names: &NameTables) -> Result<usize, String> {
+    names
+        .named_params(instruction)
+        .iter()
+        .position(|param| param.field == field)
+        .ok_or(format!("{} has no parameter '{}'", instruction.name, field))
+}
+
+/// A parameter value written in a manifest as a number or a name
+fn parse_param(
+    instruction: &ScdInstruction,
+    field: &str,
+    value: &toml::Value,
+    previous: &[(String, i32)],
+    names: &NameTables,
+) -> Result<i32, String> {
+    match value {
+        toml::Value::Integer(number) => {
+            i32::try_from(*number).map_err(|_| format!("Value {} for {} is out of range", number, field))
+        }
+        toml::Value::String(text) => names
+            .parse_value(&instruction.name, field, text, previous)
+            .ok_or(format!("Unknown value '{}' for {}", text, field)),
+        other => Err(format!("Expected a number or name for {}, found {}", field, other)),
+    }
+}
+
+/// Instructions of a code snippet with the block slack written in it
+fn assemble_code(code: &str, assembler: &Assembler<'_>) -> Result<Vec<PlacedInstruction>, String> {
+    let function = assembler.assemble_function(code).map_err(|errors| {
+        let errors: Vec<String> = errors
+            .iter()
+            .map(|error| format!("code line {}: {}", error.line + 1, error.message))
+            .collect();
+        errors.join("; ")
+    })?;
+    Ok(placed_instructions(&function))
+}
+
+impl fmt::Display for EditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EditAction::Replace => "replace",
+            EditAction::InsertBefore => "insert_before",
+            EditAction::InsertAfter => "insert_after",
+            EditAction::Delete => "delete",
+            EditAction::Set => "set",
+        };
+        f.pad(name)
+    }
+}
+
+impl fmt::Display for Anchor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(label) = &self.label {
+            parts.push(format!("label '{}'", label));
+        }
+        if let Some(line) = &self.line {
+            parts.push(format!("'{}'", line));
+        }
+        if let Some(name) = &self.instruction {
+            let params: Vec<String> = self
+                .params
+                .iter()
+                .map(|(field, value)| match value {
+                    toml::Value::String(text) => format!("{}: {}", field, text),
+                    value => format!("{}: {}", field, value),
+                })
+                .collect();
+            parts.push(format!("{}({})", name, params.join(", ")));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rooms::{init_function, load, room};
+
+    const INIT: &str = "SetBit(bit_array: 1, bit_number: 5, operation: 1)
+CheckBit(bit_array: 1, bit_number: 2, value: 0)
+SetBit(bit_array: 1, bit_number: 5, operation: 1)
+EvtEnd()";
+
+    /// A game directory holding pl0/ROOM1000.RDT, unique to the test
+    fn game(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bio2-scd-{}-{}", std::process::id(), test));
+        let _ = std::fs::remove_dir_all(&dir);
+        write_file(&dir.join("game/pl0/ROOM1000.RDT"), &room(&[INIT], &["EvtEnd()"])).unwrap();
+        dir
+    }
+
+    fn manifest(text: &str) -> ModManifest {
+        toml::from_str(text).unwrap()
+    }
+
+    /// Applies the manifests in order and returns the first init function of the written room
+    fn apply(test: &str, manifests: &[&str]) -> Result<String, String> {
+        let dir = game(test);
+        let names = NameTables::builtin();
+        let mut game = ModdedGame::new(&dir.join("game"))?;
+        for text in manifests {
+            game.apply(&manifest(text), &names)?;
+        }
+        game.write(&dir.join("out"))?;
+        let contents = read_file(dir.join("out/pl0/ROOM1000.RDT").display().to_string())?;
+        let _ = std::fs::remove_dir_all(&dir);
+        Ok(init_function(&load(&contents), 0))
+    }
+
+    #[test]
+    fn manifest_parses_with_defaults() {
+        let manifest = manifest(
+            r#"
+            name = "Flags"
+
+            [[edit]]
+            room = "ROOM1000"
+            function = 0
+            action = "set"
+            anchor = { instruction = "CheckBit", params = { bit_number = 2 } }
+            set = { value = 1 }
+            "#,
+        );
+        assert_eq!(manifest.name, "Flags");
+        let edit = &manifest.edits[0];
+        assert_eq!((edit.script.as_str(), edit.count, edit.action), ("main", 1, EditAction::Set));
+        assert_eq!(edit.anchor.params["bit_number"], toml::Value::Integer(2));
+        assert_eq!(edit.anchor.to_string(), "CheckBit(bit_number: 2)");
+
+        let unknown = toml::from_str::<ModManifest>("[[edit]]\nroom = \"ROOM1000\"\nfunction = 0\naction = \"set\"\nanchor = {}\ntypo = 1");
+        assert!(unknown.is_err());
+    }
+
+    #[test]
+    fn set_changes_the_anchored_instruction() {
+        let init = apply(
+            "set",
+            &[r#"
+            [[edit]]
+            room = "ROOM1000"
+            script = "init"
+            function = 0
+            action = "set"
+            anchor = { instruction = "CheckBit", params = { bit_number = 2 } }
+            set = { value = 1 }
+            "#],
+        );
+        assert_eq!(init.unwrap(), INIT.replace("value: 0", "value: 1"));
+    }
+
+    #[test]
+    fn ambiguous_anchor_needs_an_occurrence() {
+        let edit = r#"
+            [[edit]]
+            room = "ROOM1000"
+            script = "init"
+            function = 0
+            action = "delete"
+            anchor = { line = "SetBit(bit_array: 1, bit_number: 5, operation: 1)" OCCURRENCE }
+            "#;
+        let error = apply("ambiguous", &[&edit.replace(" OCCURRENCE", "")]).unwrap_err();
+        assert!(error.contains("matches 2 instructions (at 0, 2)"), "{}", error);
+        let error = apply("missing", &[&edit.replace(" OCCURRENCE", ", occurrence = 3")]).unwrap_err();
+        assert!(error.contains("not occurrence 3"), "{}", error);
+
+        let init = apply("occurrence", &[&edit.replace(" OCCURRENCE", ", occurrence = 2")]).unwrap();
+        assert_eq!(init, INIT.replace("value: 0)\nSetBit(bit_array: 1, bit_number: 5, operation: 1)", "value: 0)"));
+    }
+
+    #[test]
+    fn later_manifests_anchor_on_labels() {
+        let first = r#"
+            [[edit]]
+            room = "ROOM1000"
+            script = "init"
+            function = 0
+            action = "insert_after"
+            anchor = { instruction = "CheckBit" }
+            label = "flags"
+            code = "SetBit(bit_array: 1, bit_number: 6, operation: 1)"
+            "#;
+        let second = r#"
+            [[edit]]
+            room = "ROOM1000"
+            script = "init"
+            function = 0
+            action = "insert_after"
+            anchor = { label = "flags" }
+            code = "SetBit(bit_array: 1, bit_number: 7, operation: 1)"
+            "#;
+        let init = apply("labels", &[first, second]).unwrap();
+        assert_eq!(
+            init,
+            INIT.replace(
+                "value: 0)",
+                "value: 0)\nSetBit(bit_array: 1, bit_number: 6, operation: 1)\nSetBit(bit_array: 1, bit_number: 7, operation: 1)"
+            )
+        );
+
+        let error = apply("unknown-label", &[second]).unwrap_err();
+        assert!(error.contains("label 'flags' matches no instruction"), "{}", error);
+    }
+
+    #[test]
+    fn failing_manifest_applies_no_edit() {
+        let dir = game("atomic");
+        let original = read_file(dir.join("game/pl0/ROOM1000.RDT").display().to_string()).unwrap();
+        let names = NameTables::builtin();
+        let mut game = ModdedGame::new(&dir.join("game")).unwrap();
+        let failing = manifest(
+            r#"
+            [[edit]]
+            room = "ROOM1000"
+            script = "init"
+            function = 0
+            action = "delete"
+            anchor = { instruction = "CheckBit" }
+
+            [[edit]]
+            room = "ROOM1000"
+            script = "init"
+            function = 0
+            action = "delete"
+            anchor = { instruction = "ItemAotSet" }
+            "#,
+        );
+
+        let error = game.apply(&failing, &names).unwrap_err();
+        assert!(error.starts_with("edit 2 (ROOM1000 init function 0)"), "{}", error);
+        assert_eq!(game.changed_rooms(), 0);
+        assert!(game.write(&dir.join("out")).unwrap().is_empty());
+        assert!(!dir.join("out").exists());
+        assert_eq!(read_file(dir.join("game/pl0/ROOM1000.RDT").display().to_string()).unwrap(), original);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
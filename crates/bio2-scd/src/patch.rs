@@ -0,0 +1,545 @@
+use crate::file_handler::FileHandler;
+use crate::fileio::utils::{crc32, portable_path, read_file, write_file};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Lists the patches of a directory written by `create_patch_set`
+pub const PATCH_MANIFEST_FILE: &str = "patches.json";
+
+const IPS_MAGIC: &[u8] = b"PATCH";
+const IPS_EOF: &[u8] = b"EOF";
+/// Largest offset and record size an IPS patch can address
+const IPS_MAX_OFFSET: usize = 0xff_ffff;
+const IPS_MAX_RECORD: usize = 0xffff;
+/// Unchanged bytes between two changes that are still written as one record
+const IPS_RECORD_GAP: usize = 6;
+
+const BPS_MAGIC: &[u8] = b"BPS1";
+const BPS_SOURCE_READ: u64 = 0;
+const BPS_TARGET_READ: u64 = 1;
+const BPS_SOURCE_COPY: u64 = 2;
+const BPS_TARGET_COPY: u64 = 3;
+/// Shortest run worth an action of its own instead of literal bytes
+const BPS_MIN_READ: usize = 4;
+const BPS_MIN_COPY: usize = 8;
+/// Source positions tried for each match, enough for the repeated padding in RDT files
+const BPS_MAX_CANDIDATES: usize = 32;
+
+/// Binary patch formats for distributing changed files without the originals
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatchFormat {
+    /// Byte records at fixed offsets, without checksums
+    Ips,
+    /// Copies from the source and target with CRC32 checksums, handles moved data well
+    Bps,
+}
+
+/// A file of a patch directory
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct PatchEntry {
+    /// Path of the patched file, relative to the game directory
+    pub file: String,
+    /// Path of the patch, relative to the patch directory
+    pub patch: String,
+    pub source_crc32: u32,
+    pub target_crc32: u32,
+}
+
+/// Patches for every changed file of a game directory
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct PatchManifest {
+    pub format: PatchFormat,
+    pub files: Vec<PatchEntry>,
+}
+
+/// Files handled by `create_patch_set` or `apply_patch_set`
+#[derive(Default)]
+pub struct PatchSetReport {
+    pub patched: usize,
+    pub unchanged: usize,
+    pub warnings: Vec<String>,
+}
+
+impl PatchFormat {
+    /// The format of a patch file, by its extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "ips" => Some(PatchFormat::Ips),
+            "bps" => Some(PatchFormat::Bps),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            PatchFormat::Ips => "ips",
+            PatchFormat::Bps => "bps",
+        }
+    }
+
+    pub fn create(&self, source: &[u8], target: &[u8]) -> Result<Vec<u8>, String> {
+        match self {
+            PatchFormat::Ips => create_ips(source, target),
+            PatchFormat::Bps => Ok(create_bps(source, target)),
+        }
+    }
+
+    /// Applies a patch; BPS patches also check the source, the result and the patch itself
+    pub fn apply(&self, source: &[u8], patch: &[u8]) -> Result<Vec<u8>, String> {
+        match self {
+            PatchFormat::Ips => apply_ips(source, patch),
+            PatchFormat::Bps => apply_bps(source, patch),
+        }
+    }
+}
+
+impl std::str::FromStr for PatchFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ips" => Ok(PatchFormat::Ips),
+            "bps" => Ok(PatchFormat::Bps),
+            other => Err(format!("Unknown patch format '{}', expected ips or bps", other)),
+        }
+    }
+}
+
+/// Writes the bytes that differ as IPS records, with a truncation size when the target is shorter
+pub fn create_ips(source: &[u8], target: &[u8]) -> Result<Vec<u8>, String> {
+    if target.len() > IPS_MAX_OFFSET {
+        return Err(format!("IPS patches cannot address files larger than {} bytes", IPS_MAX_OFFSET));
+    }
+    let differs = |i: usize| source.get(i) != Some(&target[i]);
+    let mut patch = IPS_MAGIC.to_vec();
+    let mut pos = 0;
+    while pos < target.len() {
+        if !differs(pos) {
+            pos += 1;
+            continue;
+        }
+        // "EOF" as an offset would end the patch, so start one byte earlier
+        let start = if pos == 0x45_4f46 { pos - 1 } else { pos };
+        let mut end = pos + 1;
+        let mut last_change = pos;
+        while end < target.len() && end - start < IPS_MAX_RECORD && end - last_change <= IPS_RECORD_GAP {
+            if differs(end) {
+                last_change = end;
+            }
+            end += 1;
+        }
+        let end = last_change + 1;
+        patch.extend_from_slice(&(start as u32).to_be_bytes()[1..]);
+        patch.extend_from_slice(&((end - start) as u16).to_be_bytes());
+        patch.extend_from_slice(&target[start..end]);
+        pos = end;
+    }
+    patch.extend_from_slice(IPS_EOF);
+    if target.len() < source.len() {
+        patch.extend_from_slice(&(target.len() as u32).to_be_bytes()[1..]);
+    }
+    Ok(patch)
+}
+
+pub fn apply_ips(source: &[u8], patch: &[u8]) -> Result<Vec<u8>, String> {
+    let invalid = || "Invalid IPS patch: unexpected end of file".to_string();
+    let body = patch.strip_prefix(IPS_MAGIC).ok_or("Not an IPS patch")?;
+    let mut output = source.to_vec();
+    let mut pos = 0;
+    loop {
+        let offset_bytes = body.get(pos..pos + 3).ok_or_else(invalid)?;
+        if offset_bytes == IPS_EOF {
+            pos += 3;
+            break;
+        }
+        let offset = u32::from_be_bytes([0, offset_bytes[0], offset_bytes[1], offset_bytes[2]]) as usize;
+        let size = u16::from_be_bytes(body.get(pos + 3..pos + 5).ok_or_else(invalid)?.try_into().unwrap()) as usize;
+        pos += 5;
+        let (data, length) = match size {
+            // Run length encoded record: count and the repeated byte
+            0 => {
+                let rle = body.get(pos..pos + 3).ok_or_else(invalid)?;
+                pos += 3;
+                let count = u16::from_be_bytes([rle[0], rle[1]]) as usize;
+                (vec![rle[2]; count], count)
+            }
+            _ => {
+                let data = body.get(pos..pos + size).ok_or_else(invalid)?.to_vec();
+                pos += size;
+                (data, size)
+            }
+        };
+        if output.len() < offset + length {
+            output.resize(offset + length, 0);
+        }
+        output[offset..offset + length].copy_from_slice(&data);
+    }
+    if let Some(size) = body.get(pos..pos + 3) {
+        output.truncate(u32::from_be_bytes([0, size[0], size[1], size[2]]) as usize);
+    }
+    Ok(output)
+}
+
+/// Builds a BPS patch, copying runs found anywhere in the source so moved sections stay small
+pub fn create_bps(source: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut index: HashMap<&[u8], Vec<usize>> = HashMap::new();
+    if source.len() >= BPS_MIN_READ {
+        for start in 0..=source.len() - BPS_MIN_READ {
+            let candidates = index.entry(&source[start..start + BPS_MIN_READ]).or_default();
+            if candidates.len() < BPS_MAX_CANDIDATES {
+                candidates.push(start);
+            }
+        }
+    }
+    let match_length = |from: usize, at: usize| {
+        source[from..].iter().zip(&target[at..]).take_while(|(a, b)| a == b).count()
+    };
+
+    let mut patch = BPS_MAGIC.to_vec();
+    write_number(&mut patch, source.len() as u64);
+    write_number(&mut patch, target.len() as u64);
+    write_number(&mut patch, 0);
+
+    let mut literal_start = None;
+    let mut source_relative = 0i64;
+    let mut pos = 0;
+    while pos < target.len() {
+        let read = if pos < source.len() { match_length(pos, pos) } else { 0 };
+        let copy = target
+            .get(pos..pos + BPS_MIN_READ)
+            .and_then(|key| index.get(key))
+            .into_iter()
+            .flatten()
+            .map(|&from| (from, match_length(from, pos)))
+            .max_by_key(|&(_, length)| length);
+
+        let action = match copy {
+            Some((from, length)) if length >= BPS_MIN_COPY && length > read => Some((BPS_SOURCE_COPY, from, length)),
+            _ if read >= BPS_MIN_READ => Some((BPS_SOURCE_READ, pos, read)),
+            _ => None,
+        };
+        let Some((action, from, length)) = action else {
+            literal_start.get_or_insert(pos);
+            pos += 1;
+            continue;
+        };
+        if let Some(start) = literal_start.take() {
+            write_number(&mut patch, ((pos - start - 1) as u64) << 2 | BPS_TARGET_READ);
+            patch.extend_from_slice(&target[start..pos]);
+        }
+        write_number(&mut patch, ((length - 1) as u64) << 2 | action);
+        if action == BPS_SOURCE_COPY {
+            let offset = from as i64 - source_relative;
+            write_number(&mut patch, (offset.unsigned_abs() << 1) | (offset < 0) as u64);
+            source_relative = (from + length) as i64;
+        }
+        pos += length;
+    }
+    if let Some(start) = literal_start {
+        write_number(&mut patch, ((pos - start - 1) as u64) << 2 | BPS_TARGET_READ);
+        patch.extend_from_slice(&target[start..pos]);
+    }
+
+    patch.extend_from_slice(&crc32(source).to_le_bytes());
+    patch.extend_from_slice(&crc32(target).to_le_bytes());
+    let patch_crc = crc32(&patch);
+    patch.extend_from_slice(&patch_crc.to_le_bytes());
+    patch
+}
+
+/// Applies a BPS patch after checking that it is meant for this source file
+pub fn apply_bps(source: &[u8], patch: &[u8]) -> Result<Vec<u8>, String> {
+    if !patch.starts_with(BPS_MAGIC) || patch.len() < BPS_MAGIC.len() + 12 {
+        return Err("Not a BPS patch".to_string());
+    }
+    let footer = patch.len() - 12;
+    let checksum = |at: usize| u32::from_le_bytes(patch[at..at + 4].try_into().unwrap());
+    if crc32(&patch[..footer + 8]) != checksum(footer + 8) {
+        return Err("The BPS patch is damaged, its checksum does not match".to_string());
+    }
+    if crc32(source) != checksum(footer) {
+        return Err(format!(
+            "The patch is for a different source file (CRC32 {:08x}, this file has {:08x})",
+            checksum(footer),
+            crc32(source)
+        ));
+    }
+
+    let mut reader = BpsReader {
+        data: &patch[..footer],
+        pos: BPS_MAGIC.len(),
+    };
+    let source_size = reader.number()? as usize;
+    let target_size = reader.number()? as usize;
+    let metadata_size = reader.number()? as usize;
+    reader.bytes(metadata_size)?;
+    if source_size != source.len() {
+        return Err(format!("The patch expects a source of {} bytes, this file has {}", source_size, source.len()));
+    }
+
+    let mut target: Vec<u8> = Vec::with_capacity(target_size);
+    let (mut source_relative, mut target_relative) = (0usize, 0usize);
+    let invalid = || "Invalid BPS patch: copy outside the file".to_string();
+    while reader.pos < reader.data.len() {
+        let command = reader.number()?;
+        let length = (command >> 2) as usize + 1;
+        match command & 3 {
+            BPS_SOURCE_READ => {
+                let start = target.len();
+                target.extend_from_slice(source.get(start..start + length).ok_or_else(invalid)?);
+            }
+            BPS_TARGET_READ => target.extend_from_slice(reader.bytes(length)?),
+            action => {
+                let encoded = reader.number()?;
+                let offset = (encoded >> 1) as i64 * if encoded & 1 != 0 { -1 } else { 1 };
+                let relative = if action == BPS_TARGET_COPY { &mut target_relative } else { &mut source_relative };
+                let start = usize::try_from(*relative as i64 + offset).map_err(|_| invalid())?;
+                if action == BPS_TARGET_COPY {
+                    // Target copies may overlap the bytes they produce, so copy one at a time
+                    for i in start..start + length {
+                        let byte = *target.get(i).ok_or_else(invalid)?;
+                        target.push(byte);
+                    }
+                } else {
+                    target.extend_from_slice(source.get(start..start + length).ok_or_else(invalid)?);
+                }
+                *relative = start + length;
+            }
+        }
+    }
+
+    if target.len() != target_size || crc32(&target) != checksum(footer + 4) {
+        return Err("The patched file does not match the checksum stored in the patch".to_string());
+    }
+    Ok(target)
+}
+
+/// Writes one patch per changed RDT of a modified game directory and a manifest listing them
+///
+/// Rooms that only exist in the modified directory are reported, they have no source to patch
+pub fn create_patch_set(original_dir: &Path, modified_dir: &Path, patch_dir: &Path, format: PatchFormat) -> Result<PatchSetReport, String> {
+    let mut report = PatchSetReport::default();
+    let mut manifest = PatchManifest {
+        format,
+        files: Vec::new(),
+    };
+    for path in FileHandler::rdt_paths(modified_dir)? {
+        let relative = path.strip_prefix(modified_dir).unwrap_or(&path);
+        let original_path = original_dir.join(relative);
+        if !original_path.exists() {
+            report.warnings.push(format!("{}: not in the original directory, ship it as it is", relative.display()));
+            continue;
+        }
+        let source = read_file(original_path.display().to_string())?;
+        let target = read_file(path.display().to_string())?;
+        if source == target {
+            report.unchanged += 1;
+            continue;
+        }
+
+        let patch_name = relative.with_extension(format.extension());
+        let patch = format.create(&source, &target).map_err(|e| format!("{}: {}", relative.display(), e))?;
+        write_file(&patch_dir.join(&patch_name), &patch)?;
+        manifest.files.push(PatchEntry {
+            file: portable_path(relative),
+            patch: portable_path(&patch_name),
+            source_crc32: crc32(&source),
+            target_crc32: crc32(&target),
+        });
+        report.patched += 1;
+    }
+    let json = serde_json::to_string_pretty(&manifest).map_err(|e| format!("JSON error: {}", e))?;
+    write_file(&patch_dir.join(PATCH_MANIFEST_FILE), json.as_bytes())?;
+    Ok(report)
+}
+
+/// Applies the patches of a directory written by `create_patch_set` to a copy of a game directory
+///
+/// Every source file is checked against the checksum in the manifest before anything is written
+pub fn apply_patch_set(patch_dir: &Path, game_dir: &Path, output_dir: &Path) -> Result<PatchSetReport, String> {
+    let manifest_path = patch_dir.join(PATCH_MANIFEST_FILE);
+    let contents = std::fs::read_to_string(&manifest_path)
+        .map_err(|e| format!("Unable to read '{}': {}", manifest_path.display(), e))?;
+    let manifest: PatchManifest =
+        serde_json::from_str(&contents).map_err(|e| format!("Invalid manifest '{}': {}", manifest_path.display(), e))?;
+
+    let mut patched = Vec::new();
+    for entry in &manifest.files {
+        let source = read_file(game_dir.join(&entry.file).display().to_string())?;
+        if crc32(&source) != entry.source_crc32 {
+            return Err(format!(
+                "{}: CRC32 is {:08x} but the patch was made for {:08x}",
+                entry.file,
+                crc32(&source),
+                entry.source_crc32
+            ));
+        }
+        let patch = read_file(patch_dir.join(&entry.patch).display().to_string())?;
+        let target = manifest.format.apply(&source, &patch).map_err(|e| format!("{}: {}", entry.file, e))?;
+        if crc32(&target) != entry.target_crc32 {
+            return Err(format!("{}: the patched file does not match the checksum in the manifest", entry.file));
+        }
+        patched.push((&entry.file, target));
+    }
+
+    let mut report = PatchSetReport::default();
+    for (file, target) in patched {
+        write_file(&output_dir.join(file), &target)?;
+        report.patched += 1;
+    }
+    Ok(report)
+}
+
+/// Longest number a BPS patch may hold, 63 bits of 7 bits per byte
+const BPS_MAX_NUMBER_BYTES: usize = 9;
+
+/// Reads the variable length numbers and raw bytes of a BPS patch
+struct BpsReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BpsReader<'a> {
+    fn number(&mut self) -> Result<u64, String> {
+        const TOO_LARGE: &str = "Invalid BPS patch: number too large";
+        let mut value = 0u64;
+        let mut shift = 1u64;
+        for _ in 0..BPS_MAX_NUMBER_BYTES {
+            let byte = *self.data.get(self.pos).ok_or("Invalid BPS patch: unexpected end of file")?;
+            self.pos += 1;
+            value = ((byte & 0x7f) as u64)
+                .checked_mul(shift)
+                .and_then(|low| value.checked_add(low))
+                .ok_or(TOO_LARGE)?;
+            if byte & 0x80 != 0 {
+                return Ok(value);
+            }
+            shift = shift.checked_mul(0x80).ok_or(TOO_LARGE)?;
+            value = value.checked_add(shift).ok_or(TOO_LARGE)?;
+        }
+        Err(TOO_LARGE.to_string())
+    }
+
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos.saturating_add(length))
+            .ok_or("Invalid BPS patch: unexpected end of file")?;
+        self.pos += length;
+        Ok(bytes)
+    }
+}
+
+/// Variable length number of the BPS format
+fn write_number(patch: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let low = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            patch.push(0x80 | low);
+            return;
+        }
+        patch.push(low);
+        value -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source() -> Vec<u8> {
+        (0..600u32).map(|i| (i * 7 % 251) as u8).collect()
+    }
+
+    /// The source with a few scattered edits, a moved run and bytes appended
+    fn target() -> Vec<u8> {
+        let mut target = source();
+        target[3] = 0xff;
+        target[100..110].fill(0);
+        target.copy_within(200..300, 400);
+        target.extend_from_slice(b"appended");
+        target
+    }
+
+    #[test]
+    fn ips_round_trip() {
+        let patch = create_ips(&source(), &target()).unwrap();
+        assert!(patch.starts_with(IPS_MAGIC) && patch.ends_with(IPS_EOF));
+        assert_eq!(apply_ips(&source(), &patch).unwrap(), target());
+    }
+
+    #[test]
+    fn ips_record_at_the_eof_offset_starts_one_byte_earlier() {
+        const EOF_OFFSET: usize = 0x45_4f46;
+        let source = vec![0; EOF_OFFSET + 16];
+        let mut target = source.clone();
+        target[EOF_OFFSET] = 1;
+
+        let patch = create_ips(&source, &target).unwrap();
+        assert_eq!(&patch[IPS_MAGIC.len()..IPS_MAGIC.len() + 3], [0x45, 0x4f, 0x45]);
+        assert_eq!(apply_ips(&source, &patch).unwrap(), target);
+    }
+
+    #[test]
+    fn ips_truncates_to_a_shorter_target() {
+        let mut target = source();
+        target.truncate(450);
+        target[10] = 0xaa;
+
+        let patch = create_ips(&source(), &target).unwrap();
+        assert!(patch.ends_with(&[0x00, 0x01, 0xc2]));
+        assert_eq!(apply_ips(&source(), &patch).unwrap(), target);
+    }
+
+    #[test]
+    fn ips_rejects_a_cut_off_patch() {
+        let patch = create_ips(&source(), &target()).unwrap();
+        assert!(apply_ips(&source(), &patch[..patch.len() - 4]).is_err());
+        assert!(apply_ips(&source(), b"not a patch").is_err());
+    }
+
+    #[test]
+    fn bps_round_trip() {
+        let patch = create_bps(&source(), &target());
+        assert!(patch.len() < target().len());
+        assert_eq!(apply_bps(&source(), &patch).unwrap(), target());
+    }
+
+    #[test]
+    fn bps_numbers_are_limited_to_nine_bytes() {
+        let read = |data: &[u8]| BpsReader { data, pos: 0 }.number();
+        for value in [0, 127, 128, 0x1234_5678, u64::MAX >> 1] {
+            let mut encoded = Vec::new();
+            write_number(&mut encoded, value);
+            assert_eq!(read(&encoded), Ok(value));
+        }
+        let mut largest = vec![0x7f; BPS_MAX_NUMBER_BYTES - 1];
+        largest.push(0xff);
+        assert!(read(&largest).is_ok());
+
+        let mut too_long = vec![0x00; BPS_MAX_NUMBER_BYTES];
+        too_long.push(0x80);
+        assert_eq!(read(&too_long).unwrap_err(), "Invalid BPS patch: number too large");
+        assert!(read(&[0x7f; 3]).unwrap_err().contains("unexpected end"));
+    }
+
+    #[test]
+    fn bps_rejects_another_source() {
+        let patch = create_bps(&source(), &target());
+        let mut other = source();
+        other[0] ^= 1;
+        let error = apply_bps(&other, &patch).unwrap_err();
+        assert!(error.contains("different source file"), "{}", error);
+    }
+
+    #[test]
+    fn bps_rejects_a_damaged_patch() {
+        let mut patch = create_bps(&source(), &target());
+        let middle = patch.len() / 2;
+        patch[middle] ^= 1;
+        let error = apply_bps(&source(), &patch).unwrap_err();
+        assert!(error.contains("checksum does not match"), "{}", error);
+    }
+}
@@ -0,0 +1,268 @@
+use crate::analysis::lint::{lint_room, LintConfig, Problem};
+use crate::assembler::Assembler;
+use crate::file_handler::{FileHandler, RdtFileData};
+use crate::fileio::name_tables::{NameTables, NamedParam};
+use crate::fileio::rdt::{RDTHeader, RdtSection, RDT_OFFSET_INIT_SCRIPT, RDT_OFFSET_MAIN_SCRIPT};
+use crate::fileio::rdt_scd::{ScdDecodeError, ScdFunction, ScdInstruction};
+use crate::fileio::room_layout::ScriptKind;
+use crate::writer::{placed_instructions, write_scripts};
+use std::path::Path;
+
+/// Version of the JSON document layout
+///
+/// Increase it whenever a field is renamed, removed or changes meaning. Adding an optional
+/// field does not need a new version.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A decoded room as a self-describing JSON document
+///
+/// This is the format written by `bio2scd disasm --format json`
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct RoomDocument {
+    pub schema_version: u32,
+    /// File stem, e.g. ROOM1000
+    pub room: String,
+    pub file_size: usize,
+    pub header: RDTHeader,
+    pub sections: Vec<RdtSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub init: Option<ScriptDocument>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub main: Option<ScriptDocument>,
+    /// Problems found by the linter with its default rules, including decode errors
+    pub diagnostics: Vec<Problem>,
+}
+
+/// The functions of the init or main script
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct ScriptDocument {
+    /// Offset of the function table within the RDT file
+    pub offset: u32,
+    pub functions: Vec<FunctionDocument>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct FunctionDocument {
+    pub index: usize,
+    pub instructions: Vec<InstructionDocument>,
+}
+
+/// A decoded instruction with named parameters and its pseudocode
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct InstructionDocument {
+    /// Absolute offset of the opcode byte within the RDT file
+    pub offset: usize,
+    pub opcode: u8,
+    pub name: String,
+    pub params: Vec<NamedParam>,
+    /// Instruction bytes as space separated hex, e.g. "22 01 05 01"
+    #[serde(with = "hex_bytes")]
+    pub raw: Vec<u8>,
+    /// Pseudocode as the code view shows it, which assembles back to `raw`
+    pub code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ScdDecodeError>,
+}
+
+impl RoomDocument {
+    /// Describes a loaded room, with only the given scripts
+    pub fn new(room: &str, data: &RdtFileData, names: &NameTables, scripts: &[ScriptKind]) -> Self {
+        let assembler = Assembler::new(names);
+        let script = |kind: ScriptKind, offset_index: usize| {
+            scripts.contains(&kind).then(|| ScriptDocument {
+                offset: data.header.offsets[offset_index],
+                functions: data
+                    .functions(kind)
+                    .iter()
+                    .map(|function| FunctionDocument::new(function, names, &assembler))
+                    .collect(),
+            })
+        };
+        Self {
+            schema_version: SCHEMA_VERSION,
+            room: room.to_string(),
+            file_size: data.file_size,
+            header: data.header.clone(),
+            sections: data.header.sections(data.file_size),
+            init: script(ScriptKind::Init, RDT_OFFSET_INIT_SCRIPT),
+            main: script(ScriptKind::Main, RDT_OFFSET_MAIN_SCRIPT),
+            diagnostics: lint_room(data, &LintConfig::default()),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| format!("JSON error: {}", e))
+    }
+
+    /// Reads a document, rejecting versions this build does not understand
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let value: serde_json::Value = serde_json::from_str(json).map_err(|e| format!("JSON error: {}", e))?;
+        let version = value
+            .get("schema_version")
+            .and_then(|v| v.as_u64())
+            .ok_or("Missing schema_version, not a room document")?;
+        if version != SCHEMA_VERSION as u64 {
+            return Err(format!(
+                "Unsupported schema version {}, this build reads version {}",
+                version, SCHEMA_VERSION
+            ));
+        }
+        serde_json::from_value(value).map_err(|e| format!("Invalid room document: {}", e))
+    }
+
+    /// Writes the scripts of the document into the RDT file it was made from
+    ///
+    /// Instructions are encoded from their parameter values, so `raw` and `code` are not read.
+    /// Functions with the instructions of `base` keep their bytes, the others are encoded with
+    /// recomputed block lengths, see [`write_scripts`]. Returns the file and warnings about
+    /// sections that moved.
+    pub fn write_scripts(&self, base: &[u8]) -> Result<(Vec<u8>, Vec<String>), String> {
+        let data = FileHandler::parse_rdt_file(Path::new(&self.room), base)?;
+        let mut scripts = Vec::new();
+        for (kind, script) in [(ScriptKind::Init, &self.init), (ScriptKind::Main, &self.main)] {
+            let Some(script) = script else {
+                continue;
+            };
+            let functions = script
+                .to_functions()
+                .iter()
+                .enumerate()
+                .map(|(index, function)| {
+                    let unchanged = data.functions(kind).get(index).map_or(false, |base| same_code(base, function));
+                    (!unchanged).then(|| placed_instructions(function))
+                })
+                .collect();
+            scripts.push((kind, functions));
+        }
+        write_scripts(base, &scripts)
+    }
+}
+
+/// True if both functions hold the same instructions with the same parameters
+fn same_code(a: &ScdFunction, b: &ScdFunction) -> bool {
+    a.instructions.len() == b.instructions.len()
+        && a.instructions.iter().zip(&b.instructions).all(|(a, b)| {
+            a.opcode == b.opcode && a.params == b.params && a.error == b.error && (a.error.is_none() || a.raw == b.raw)
+        })
+}
+
+impl ScriptDocument {
+    /// The functions as decoded instructions again, e.g. as input to the encoder
+    pub fn to_functions(&self) -> Vec<ScdFunction> {
+        self.functions
+            .iter()
+            .map(|function| ScdFunction {
+                index: function.index,
+                instructions: function.instructions.iter().map(InstructionDocument::to_instruction).collect(),
+            })
+            .collect()
+    }
+}
+
+impl FunctionDocument {
+    fn new(function: &ScdFunction, names: &NameTables, assembler: &Assembler<'_>) -> Self {
+        Self {
+            index: function.index,
+            instructions: function
+                .instructions
+                .iter()
+                .map(|instruction| InstructionDocument {
+                    offset: instruction.offset,
+                    opcode: instruction.opcode,
+                    name: instruction.name.clone(),
+                    params: names.named_params(instruction),
+                    raw: instruction.raw.clone(),
+                    code: assembler.disassemble(instruction),
+                    error: instruction.error.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl InstructionDocument {
+    pub fn to_instruction(&self) -> ScdInstruction {
+        ScdInstruction {
+            offset: self.offset,
+            opcode: self.opcode,
+            name: self.name.clone(),
+            params: self.params.iter().map(|param| param.value).collect(),
+            raw: self.raw.clone(),
+            error: self.error.clone(),
+        }
+    }
+}
+
+/// Serializes bytes as space separated hex, which stays readable in diffs and notebooks
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        let hex: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        serializer.serialize_str(&hex.join(" "))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.split_whitespace()
+            .map(|byte| u8::from_str_radix(byte, 16).map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rooms::{load, room};
+
+    fn document(contents: &[u8]) -> RoomDocument {
+        let names = NameTables::builtin();
+        RoomDocument::new("ROOM1000", &load(contents), &names, &[ScriptKind::Init, ScriptKind::Main])
+    }
+
+    #[test]
+    fn document_round_trips_through_json() {
+        let contents = room(
+            &["SetBit(1, 5, 1)\nIfStart(0, 4)\nCheckBit(1, 5, 1)\nEndIf()\nEvtEnd()"],
+            &["Sleep(10, 30)\nSleeping(30, 0)\nEvtEnd()", "EvtEnd()"],
+        );
+        let data = load(&contents);
+        let json = document(&contents).to_json().unwrap();
+        let back = RoomDocument::from_json(&json).unwrap();
+        assert_eq!(back.init.unwrap().to_functions(), data.init_functions);
+        assert_eq!(back.main.unwrap().to_functions(), data.main_functions);
+    }
+
+    #[test]
+    fn code_is_assembler_pseudocode() {
+        let contents = room(&["SetBit(1, 5, 1)\nEvtEnd()"], &["EvtEnd()"]);
+        let init = document(&contents).init.unwrap();
+        let code: Vec<&str> = init.functions[0].instructions.iter().map(|i| i.code.as_str()).collect();
+        assert_eq!(code, ["SetBit(bit_array: 1, bit_number: 5, operation: 1)", "EvtEnd()"]);
+    }
+
+    #[test]
+    fn from_json_rejects_other_versions() {
+        let contents = room(&["EvtEnd()"], &["EvtEnd()"]);
+        let json = document(&contents).to_json().unwrap().replacen(
+            &format!("\"schema_version\": {}", SCHEMA_VERSION),
+            "\"schema_version\": 999",
+            1,
+        );
+        assert!(RoomDocument::from_json(&json).unwrap_err().contains("999"));
+    }
+
+    #[test]
+    fn write_scripts_encodes_edited_parameters() {
+        let contents = room(&["SetBit(1, 5, 1)\nEvtEnd()"], &["EvtEnd()"]);
+        let mut document = document(&contents);
+        assert_eq!(document.write_scripts(&contents).unwrap().0, contents);
+
+        document.init.as_mut().unwrap().functions[0].instructions[0].params[1].value = 7;
+        let (written, warnings) = document.write_scripts(&contents).unwrap();
+        assert!(warnings.is_empty());
+        let data = load(&written);
+        assert_eq!(data.init_functions[0].instructions[0].params, vec![1, 7, 1]);
+        assert_eq!(data.main_functions.len(), 1);
+    }
+}
@@ -0,0 +1,50 @@
+//! Minimal RDT files built from pseudocode, for the tests of the modules that edit rooms
+
+use crate::assembler::Assembler;
+use crate::file_handler::{FileHandler, RdtFileData};
+use crate::fileio::name_tables::NameTables;
+use crate::fileio::rdt::{RDTHeader, RDT_HEADER_SIZE, RDT_OFFSET_INIT_SCRIPT, RDT_OFFSET_MAIN_SCRIPT};
+use crate::fileio::utils::with_offset_table;
+use std::path::Path;
+
+/// A room holding only an init and a main script, one function per text
+pub fn room(init: &[&str], main: &[&str]) -> Vec<u8> {
+    let names = NameTables::builtin();
+    let assembler = Assembler::new(&names);
+    let script = |functions: &[&str]| {
+        let bodies = functions.iter().map(|text| assembler.assemble(text).unwrap()).collect();
+        with_offset_table(bodies).unwrap()
+    };
+    let (init, main) = (script(init), script(main));
+
+    let mut offsets = [0; 23];
+    offsets[RDT_OFFSET_INIT_SCRIPT] = RDT_HEADER_SIZE as u32;
+    offsets[RDT_OFFSET_MAIN_SCRIPT] = (RDT_HEADER_SIZE + init.len()) as u32;
+    let mut contents = RDTHeader {
+        num_sprites: 0,
+        num_cameras: 0,
+        num_models: 0,
+        num_items: 0,
+        num_doors: 0,
+        num_rooms: 0,
+        num_reverb: 0,
+        sprite_max: 0,
+        offsets,
+    }
+    .to_bytes();
+    contents.extend(init);
+    contents.extend(main);
+    contents
+}
+
+pub fn load(contents: &[u8]) -> RdtFileData {
+    FileHandler::parse_rdt_file(Path::new("pl0/ROOM1000.RDT"), contents).unwrap()
+}
+
+/// A function of the init script as the disassembler writes it
+pub fn init_function(data: &RdtFileData, index: usize) -> String {
+    let names = NameTables::builtin();
+    let assembler = Assembler::new(&names);
+    let lines: Vec<String> = data.init_functions[index].instructions.iter().map(|i| assembler.disassemble(i)).collect();
+    lines.join("\n")
+}
@@ -0,0 +1,558 @@
+use crate::assembler::{raw_line, Assembler};
+use crate::file_handler::FileHandler;
+use crate::fileio::name_tables::NameTables;
+use crate::fileio::opcode_data::Opcode;
+use crate::fileio::rdt::{RDTHeader, RDT_HEADER_SIZE, RDT_OFFSET_INIT_SCRIPT, RDT_OFFSET_MAIN_SCRIPT};
+use crate::fileio::rdt_scd::{parse_function_body, parse_rdt_scd_functions};
+use crate::fileio::symbols::{parse_number, SymbolDictionary};
+use crate::fileio::utils::{read_file, read_u16, with_offset_table, write_file};
+use crate::writer::{encode_function, keep_block_slack, placed_instructions, section_starts};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Version of the manifest layout
+pub const TREE_VERSION: u32 = 1;
+
+/// Manifest of each unpacked room
+pub const MANIFEST_FILE: &str = "room.json";
+/// Symbol dictionary used to unpack the tree, at its root
+pub const SYMBOLS_FILE: &str = "symbols.json";
+/// Message character set used to unpack the tree, at its root
+pub const CHARSET_FILE: &str = "charset.json";
+
+/// Offset table entries holding messages
+const MESSAGE_SECTIONS: [usize; 2] = [13, 14];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkKind {
+    /// Bytes copied as they are
+    Binary,
+    /// One pseudocode file per function
+    Script,
+    /// One line of text per message
+    Messages,
+}
+
+/// A run of bytes from one section offset to the next
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Chunk {
+    pub kind: ChunkKind,
+    /// Offset table entries that point to the start of the chunk
+    pub sections: Vec<usize>,
+    /// Offset in the unpacked file, to tell when packing moves binary data
+    pub offset: u32,
+    /// Files holding the chunk, relative to the room directory; one per function for scripts
+    pub files: Vec<String>,
+    /// Hex bytes of each script function as unpacked, so an edited function is re-encoded with
+    /// the block slack of the original
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub originals: Vec<String>,
+}
+
+/// Describes how to rebuild an RDT file from the files of its room directory
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct RoomManifest {
+    pub version: u32,
+    /// Name of the RDT file, e.g. ROOM1000.RDT
+    pub file: String,
+    /// Counts and offsets of the original file; offsets of chunks are recomputed when packing,
+    /// others such as empty entries are written as they are
+    pub header: RDTHeader,
+    pub chunks: Vec<Chunk>,
+}
+
+/// Characters of the message font, indexed by byte
+///
+/// No charset is built in, the font of each release has to be given by the user. Bytes without
+/// a character are written as `{xx}`, and with an empty charset messages stay binary. Stored as JSON with decimal or 0x hex
+/// keys, e.g. `{"0x00": " ", "0x1b": "A"}`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Charset {
+    chars: BTreeMap<u8, char>,
+}
+
+/// Name of a file relative to its room directory, and its contents
+pub type TreeFile = (String, Vec<u8>);
+
+/// The files of an unpacked room, relative to its directory
+pub struct UnpackedRoom {
+    pub manifest: RoomManifest,
+    pub files: Vec<TreeFile>,
+    /// Chunks that had to be kept as binary because their text would not rebuild the same bytes
+    pub warnings: Vec<String>,
+}
+
+/// Rooms written by `unpack_game` or `pack_game`
+#[derive(Default)]
+pub struct TreeReport {
+    pub rooms: usize,
+    pub warnings: Vec<String>,
+}
+
+impl Charset {
+    pub fn load_file(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read '{}': {}", path.display(), e))?;
+        let entries: BTreeMap<String, String> = serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid charset '{}': {}", path.display(), e))?;
+
+        let mut charset = Charset::default();
+        let mut used = BTreeSet::new();
+        for (key, text) in entries {
+            let byte = parse_number(&key)
+                .and_then(|value| u8::try_from(value).ok())
+                .ok_or(format!("Invalid byte '{}' in charset", key))?;
+            let mut chars = text.chars();
+            let c = match (chars.next(), chars.next()) {
+                (Some(c), None) if !c.is_control() && c != '{' && c != '}' => c,
+                _ => return Err(format!("Charset entry {} must be a single printable character other than {{ and }}", key)),
+            };
+            if !used.insert(c) {
+                return Err(format!("Character '{}' appears twice in the charset", c));
+            }
+            charset.chars.insert(byte, c);
+        }
+        Ok(charset)
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        let entries: BTreeMap<String, String> = self
+            .chars
+            .iter()
+            .map(|(byte, c)| (format!("0x{:02x}", byte), c.to_string()))
+            .collect();
+        serde_json::to_string_pretty(&entries).map_err(|e| format!("JSON error: {}", e))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    /// Message bytes as a line of text
+    pub fn bytes_to_text(&self, bytes: &[u8]) -> String {
+        bytes
+            .iter()
+            .map(|byte| match self.chars.get(byte) {
+                Some(c) => c.to_string(),
+                None => format!("{{{:02x}}}", byte),
+            })
+            .collect()
+    }
+
+    /// A line of text as message bytes
+    pub fn text_to_bytes(&self, text: &str) -> Result<Vec<u8>, String> {
+        let mut bytes = Vec::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c == '{' {
+                let hex: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                bytes.push(u8::from_str_radix(&hex, 16).map_err(|_| format!("Invalid escape '{{{}}}'", hex))?);
+                continue;
+            }
+            let byte = self
+                .chars
+                .iter()
+                .find(|(_, &known)| known == c)
+                .map(|(&byte, _)| byte)
+                .ok_or(format!("Character '{}' is not in the charset", c))?;
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+}
+
+/// Splits an RDT file into script, message and binary files plus a manifest
+///
+/// Each chunk is packed again right away, and chunks whose text does not rebuild the same bytes
+/// are kept as binary files, so packing an unchanged room always gives the original file
+pub fn unpack_room(file: &str, contents: &[u8], names: &NameTables, charset: &Charset) -> Result<UnpackedRoom, String> {
+    let header = RDTHeader::from(contents).map_err(|e| format!("Invalid RDT file format: {}", e))?;
+    let assembler = Assembler::new(names);
+
+    // Sections may share an offset, so split the file at every distinct offset
+    let starts = section_starts(contents, &header);
+
+    let mut chunks = Vec::new();
+    let mut files = Vec::new();
+    let mut warnings = Vec::new();
+    for (i, &start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).copied().unwrap_or(contents.len());
+        let data = &contents[start..end];
+        let sections: Vec<usize> = (0..header.offsets.len())
+            .filter(|&index| header.offsets[index] as usize == start)
+            .collect();
+
+        let text_files = match sections.first() {
+            Some(&index) if index == RDT_OFFSET_INIT_SCRIPT || index == RDT_OFFSET_MAIN_SCRIPT => {
+                let prefix = if index == RDT_OFFSET_INIT_SCRIPT { "init" } else { "main" };
+                unpack_script(prefix, data, &assembler).map(|(files, originals)| (ChunkKind::Script, files, originals))
+            }
+            // Without a charset every byte would be an escape, which is no easier to edit than binary
+            Some(index) if MESSAGE_SECTIONS.contains(index) && !charset.is_empty() => {
+                unpack_messages(*index, data, charset).map(|file| (ChunkKind::Messages, vec![file], Vec::new()))
+            }
+            _ => None,
+        };
+
+        let mut chunk = Chunk {
+            kind: ChunkKind::Binary,
+            sections: sections.clone(),
+            offset: start as u32,
+            files: Vec::new(),
+            originals: Vec::new(),
+        };
+        if let Some((kind, text_files, originals)) = text_files {
+            chunk.kind = kind;
+            chunk.originals = originals;
+            chunk.files = text_files.iter().map(|(name, _)| name.clone()).collect();
+            let read = |name: &str| -> Result<Vec<u8>, String> {
+                let file = text_files.iter().find(|(file, _)| file == name);
+                Ok(file.map(|(_, bytes)| bytes.clone()).unwrap_or_default())
+            };
+            if pack_chunk(&chunk, &read, &assembler, charset).as_deref() == Ok(data) {
+                chunks.push(chunk);
+                files.extend(text_files);
+                continue;
+            }
+            warnings.push(format!("{}: section at 0x{:x} is kept as binary because its text does not rebuild it", file, start));
+            chunk.kind = ChunkKind::Binary;
+            chunk.originals.clear();
+        }
+
+        let name = match sections.first() {
+            Some(index) => format!("section_{:02}.bin", index),
+            None => format!("data_{:06x}.bin", start),
+        };
+        chunk.files = vec![name.clone()];
+        chunks.push(chunk);
+        files.push((name, data.to_vec()));
+    }
+
+    Ok(UnpackedRoom {
+        manifest: RoomManifest {
+            version: TREE_VERSION,
+            file: file.to_string(),
+            header,
+            chunks,
+        },
+        files,
+        warnings,
+    })
+}
+
+/// Rebuilds an RDT file from its manifest and the files of its room directory
+///
+/// Returns the file and warnings about binary sections that moved
+pub fn pack_room(manifest: &RoomManifest, dir: &Path, names: &NameTables, charset: &Charset) -> Result<(Vec<u8>, Vec<String>), String> {
+    if manifest.version != TREE_VERSION {
+        return Err(format!(
+            "{}: unsupported manifest version {}, this build reads version {}",
+            dir.display(),
+            manifest.version,
+            TREE_VERSION
+        ));
+    }
+    let assembler = Assembler::new(names);
+    let read = |name: &str| read_file(dir.join(name).display().to_string());
+
+    let mut header = manifest.header.clone();
+    let mut bytes = vec![0; RDT_HEADER_SIZE];
+    let mut warnings = Vec::new();
+    for chunk in &manifest.chunks {
+        let start = bytes.len();
+        for &index in &chunk.sections {
+            header.offsets[index] = start as u32;
+        }
+        if chunk.kind == ChunkKind::Binary && start != chunk.offset as usize && warnings.is_empty() {
+            warnings.push(format!(
+                "{}: binary sections from 0x{:x} moved to 0x{:x}; offsets stored inside them are not updated",
+                manifest.file, chunk.offset, start
+            ));
+        }
+        bytes.extend(pack_chunk(chunk, &read, &assembler, charset)?);
+    }
+    bytes[..RDT_HEADER_SIZE].copy_from_slice(&header.to_bytes());
+    Ok((bytes, warnings))
+}
+
+/// Unpacks every RDT below a game directory into a tree with one directory per room
+pub fn unpack_game(game_dir: &Path, tree_dir: &Path, names: &NameTables, charset: &Charset) -> Result<TreeReport, String> {
+    let mut report = TreeReport::default();
+    for path in FileHandler::rdt_paths(game_dir)? {
+        let contents = read_file(path.display().to_string())?;
+        let file = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        let room = match unpack_room(file, &contents, names, charset) {
+            Ok(room) => room,
+            Err(e) => {
+                report.warnings.push(format!("{}: {}", path.display(), e));
+                continue;
+            }
+        };
+
+        let relative = path.strip_prefix(game_dir).unwrap_or(&path);
+        let room_dir = tree_dir.join(relative.with_extension(""));
+        for (name, bytes) in &room.files {
+            write_file(&room_dir.join(name), bytes)?;
+        }
+        let manifest = serde_json::to_string_pretty(&room.manifest).map_err(|e| format!("JSON error: {}", e))?;
+        write_file(&room_dir.join(MANIFEST_FILE), manifest.as_bytes())?;
+        report.warnings.extend(room.warnings);
+        report.rooms += 1;
+    }
+
+    if !names.symbols.bit_arrays.is_empty() || !names.symbols.flags.is_empty() || !names.symbols.variables.is_empty() {
+        write_file(&tree_dir.join(SYMBOLS_FILE), names.symbols.to_json()?.as_bytes())?;
+    }
+    if !charset.is_empty() {
+        write_file(&tree_dir.join(CHARSET_FILE), charset.to_json()?.as_bytes())?;
+    }
+    Ok(report)
+}
+
+/// Packs every room of a tree written by `unpack_game` into RDT files below a directory
+///
+/// The symbol dictionary and charset stored at the root of the tree are used to read the text
+pub fn pack_game(tree_dir: &Path, game_dir: &Path) -> Result<TreeReport, String> {
+    let mut names = NameTables::builtin();
+    if tree_dir.join(SYMBOLS_FILE).exists() {
+        names.symbols = SymbolDictionary::load_file(&tree_dir.join(SYMBOLS_FILE))?;
+    }
+    let charset = match tree_dir.join(CHARSET_FILE) {
+        path if path.exists() => Charset::load_file(&path)?,
+        _ => Charset::default(),
+    };
+
+    let mut manifests = Vec::new();
+    collect_manifests(tree_dir, &mut manifests)?;
+    manifests.sort();
+
+    let mut report = TreeReport::default();
+    for manifest_path in manifests {
+        let room_dir = manifest_path.parent().unwrap_or(tree_dir);
+        let contents = std::fs::read_to_string(&manifest_path)
+            .map_err(|e| format!("Unable to read '{}': {}", manifest_path.display(), e))?;
+        let manifest: RoomManifest = serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid manifest '{}': {}", manifest_path.display(), e))?;
+
+        let (bytes, warnings) =
+            pack_room(&manifest, room_dir, &names, &charset).map_err(|e| format!("{}: {}", room_dir.display(), e))?;
+        let relative = room_dir.strip_prefix(tree_dir).unwrap_or(room_dir);
+        let parent = relative.parent().unwrap_or(Path::new(""));
+        write_file(&game_dir.join(parent).join(&manifest.file), &bytes)?;
+        report.warnings.extend(warnings);
+        report.rooms += 1;
+    }
+    Ok(report)
+}
+
+/// One pseudocode file per function, covering every byte from the function's offset to the next,
+/// and the hex bytes of each function
+fn unpack_script(prefix: &str, data: &[u8], assembler: &Assembler<'_>) -> Option<(Vec<TreeFile>, Vec<String>)> {
+    let functions = parse_rdt_scd_functions(data, 0).ok()?;
+    let offsets: Vec<usize> = (0..functions.len()).map(|i| read_u16(data, i * 2) as usize).collect();
+    if offsets.windows(2).any(|pair| pair[0] > pair[1]) || offsets.last().map_or(true, |&last| last > data.len()) {
+        return None;
+    }
+
+    let mut files = Vec::new();
+    let mut originals = Vec::new();
+    for (i, function) in functions.iter().enumerate() {
+        let end = offsets.get(i + 1).copied().unwrap_or(data.len());
+        originals.push(data[offsets[i]..end].iter().map(|b| format!("{:02x}", b)).collect());
+        let mut cursor = offsets[i];
+        let mut lines = Vec::new();
+        for instruction in &function.instructions {
+            // Sleep overlaps the Sleeping instruction after it, see Assembler::assemble
+            let size = match instruction.error {
+                None if instruction.opcode == Opcode::Sleep as u8 => 1,
+                _ => instruction.raw.len(),
+            };
+            if instruction.offset != cursor || cursor + size > end {
+                break;
+            }
+            lines.push(assembler.disassemble(instruction));
+            cursor += size;
+        }
+        // Bytes after EvtEnd or that did not decode
+        if cursor < end {
+            lines.push(raw_line(&data[cursor..end]));
+        }
+        let text: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+        files.push((format!("{}/{:03}.scd", prefix, function.index), text.into_bytes()));
+    }
+    Some((files, originals))
+}
+
+/// One line per message of the message table
+fn unpack_messages(index: usize, data: &[u8], charset: &Charset) -> Option<(String, Vec<u8>)> {
+    if data.len() < 2 {
+        return None;
+    }
+    let table_size = read_u16(data, 0) as usize;
+    if table_size == 0 || table_size % 2 != 0 || table_size > data.len() {
+        return None;
+    }
+    let offsets: Vec<usize> = (0..table_size / 2).map(|i| read_u16(data, i * 2) as usize).collect();
+    if offsets.windows(2).any(|pair| pair[0] > pair[1]) || offsets.iter().any(|&offset| offset > data.len()) {
+        return None;
+    }
+
+    let text: String = offsets
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| {
+            let end = offsets.get(i + 1).copied().unwrap_or(data.len());
+            format!("{}\n", charset.bytes_to_text(&data[start..end]))
+        })
+        .collect();
+    Some((format!("messages_{:02}.txt", index), text.into_bytes()))
+}
+
+/// Builds the bytes of a chunk from its files
+fn pack_chunk(
+    chunk: &Chunk,
+    read: &dyn Fn(&str) -> Result<Vec<u8>, String>,
+    assembler: &Assembler<'_>,
+    charset: &Charset,
+) -> Result<Vec<u8>, String> {
+    let text = |name: &str| -> Result<String, String> {
+        String::from_utf8(read(name)?).map_err(|_| format!("{} is not valid UTF-8", name))
+    };
+    match chunk.kind {
+        ChunkKind::Binary => {
+            let mut bytes = Vec::new();
+            for name in &chunk.files {
+                bytes.extend(read(name)?);
+            }
+            Ok(bytes)
+        }
+        ChunkKind::Script => {
+            let mut functions = Vec::new();
+            for (index, name) in chunk.files.iter().enumerate() {
+                let bytes = assembler.assemble(&text(name)?).map_err(|errors| {
+                    let lines: Vec<String> = errors
+                        .iter()
+                        .map(|error| format!("{}:{}: {}", name, error.line + 1, error.message))
+                        .collect();
+                    lines.join("\n")
+                })?;
+                let original = match chunk.originals.get(index) {
+                    Some(hex) => Some(parse_hex(hex).ok_or(format!("Invalid original bytes of {} in the manifest", name))?),
+                    None => None,
+                };
+                // Unchanged functions keep their bytes, edited ones get their block lengths recomputed
+                functions.push(match original {
+                    Some(original) if original == bytes => bytes,
+                    original => reencode_function(&bytes, original.as_deref()).map_err(|e| format!("{}: {}", name, e))?,
+                });
+            }
+            with_offset_table(functions)
+        }
+        ChunkKind::Messages => {
+            let mut messages = Vec::new();
+            for name in &chunk.files {
+                for (line, message) in text(name)?.lines().enumerate() {
+                    messages.push(charset.text_to_bytes(message).map_err(|e| format!("{}:{}: {}", name, line + 1, e))?);
+                }
+            }
+            with_offset_table(messages)
+        }
+    }
+}
+
+/// Encodes an edited function again with block lengths that match its instructions
+///
+/// Blocks it shares with the original keep their slack, see [`keep_block_slack`]. Bytes after
+/// the EvtEnd are kept as they are.
+fn reencode_function(bytes: &[u8], original: Option<&[u8]>) -> Result<Vec<u8>, String> {
+    let function = parse_function_body(bytes);
+    let mut placed = placed_instructions(&function);
+    if let Some(original) = original {
+        keep_block_slack(&placed_instructions(&parse_function_body(original)), &mut placed);
+    }
+    let mut encoded = encode_function(&placed)?;
+    encoded.extend_from_slice(&bytes[function.size().min(bytes.len())..]);
+    Ok(encoded)
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok()).collect()
+}
+
+fn collect_manifests(dir: &Path, manifests: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("Unable to read directory '{}': {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_manifests(&path, manifests)?;
+        } else if path.file_name().map_or(false, |name| name == MANIFEST_FILE) {
+            manifests.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rooms::{init_function, load, room};
+
+    const INIT: &str = "IfStart(dummy: 0, block_length: 8)
+CheckBit(bit_array: 1, bit_number: 5, value: 0)
+SetBit(bit_array: 1, bit_number: 3, operation: 1)
+EndIf()
+EvtEnd()";
+
+    /// Unpacks the room into a directory unique to the test, lets `edit` change its files and packs it again
+    fn repack(test: &str, contents: &[u8], edit: impl FnOnce(&Path)) -> (RoomManifest, Vec<u8>) {
+        let names = NameTables::builtin();
+        let charset = Charset::default();
+        let unpacked = unpack_room("ROOM1000.RDT", contents, &names, &charset).unwrap();
+        assert!(unpacked.warnings.is_empty());
+
+        let dir = std::env::temp_dir().join(format!("bio2-scd-{}-{}", std::process::id(), test));
+        let _ = std::fs::remove_dir_all(&dir);
+        for (name, bytes) in &unpacked.files {
+            write_file(&dir.join(name), bytes).unwrap();
+        }
+        edit(&dir);
+        let (bytes, warnings) = pack_room(&unpacked.manifest, &dir, &names, &charset).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(warnings.is_empty());
+        (unpacked.manifest, bytes)
+    }
+
+    #[test]
+    fn unchanged_room_packs_to_the_same_bytes() {
+        let contents = room(&[INIT, "EvtEnd()"], &["SetBit(bit_array: 1, bit_number: 1, operation: 1)\nEvtEnd()"]);
+        let (manifest, bytes) = repack("text-tree-unchanged", &contents, |_| {});
+        let scripts: Vec<&Chunk> = manifest.chunks.iter().filter(|chunk| chunk.kind == ChunkKind::Script).collect();
+        assert_eq!(scripts.len(), 2);
+        assert_eq!(scripts[0].files, ["init/000.scd", "init/001.scd"]);
+        assert_eq!(bytes, contents);
+    }
+
+    #[test]
+    fn edited_function_gets_its_block_lengths_recomputed() {
+        let contents = room(&[INIT], &["EvtEnd()"]);
+        let (_, bytes) = repack("text-tree-edited", &contents, |dir| {
+            let path = dir.join("init/000.scd");
+            let text = std::fs::read_to_string(&path).unwrap();
+            let edited = text.replace("EndIf()", "SetBit(bit_array: 1, bit_number: 4, operation: 1)\nEndIf()");
+            std::fs::write(&path, edited).unwrap();
+        });
+
+        let expected = INIT
+            .replace("block_length: 8", "block_length: 12")
+            .replace("EndIf()", "SetBit(bit_array: 1, bit_number: 4, operation: 1)\nEndIf()");
+        let data = load(&bytes);
+        assert_eq!(init_function(&data, 0), expected);
+        assert_eq!(bytes.len(), contents.len() + 4);
+        let main_offset = load(&contents).header.offsets[RDT_OFFSET_MAIN_SCRIPT];
+        assert_eq!(data.header.offsets[RDT_OFFSET_MAIN_SCRIPT], main_offset + 4);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod debugger;
+pub mod effects;
+pub mod machine;
+pub mod state;
+pub mod structure;
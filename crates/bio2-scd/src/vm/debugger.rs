@@ -0,0 +1,66 @@
+use crate::fileio::room_layout::ScriptLocation;
+use crate::vm::machine::Vm;
+use std::collections::HashSet;
+
+/// Frames a step or Continue may run before pausing, so endless loops cannot hang the debugger
+pub const RUN_FRAME_LIMIT: u32 = 10_000;
+
+/// How far to run before pausing again
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepMode {
+    /// One instruction of the current task
+    Into,
+    /// Until the current task reaches another instruction of the same or an outer function
+    Over,
+    /// Until the current task returns from its function
+    Out,
+    /// Until a breakpoint or the end of every task
+    Continue,
+}
+
+/// Why the machine paused
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    Step,
+    Breakpoint(ScriptLocation),
+    Finished,
+    FrameLimit,
+}
+
+impl Vm {
+    /// Runs according to `mode`, pausing early at any breakpoint reached by any task
+    ///
+    /// The instruction the current task is paused at runs even if it has a breakpoint.
+    pub fn run_until(&mut self, mode: StepMode, breakpoints: &HashSet<ScriptLocation>) -> StopReason {
+        let start = self.current_task().map(|task| (task.id, task.frames.len(), task.location()));
+        let start_frame = self.frame;
+        loop {
+            if !self.step() || self.is_finished() {
+                return StopReason::Finished;
+            }
+            let Some(task) = self.current_task() else {
+                return StopReason::Finished;
+            };
+            if let Some(location) = task.location().filter(|l| breakpoints.contains(l)) {
+                return StopReason::Breakpoint(location);
+            }
+            let done = match (mode, start) {
+                (StepMode::Into, _) | (_, None) => true,
+                (StepMode::Continue, _) => false,
+                // The stepped task has to be the current one again, or it ended
+                (StepMode::Over, Some((id, depth, location))) => self.task(id).map_or(true, |started| {
+                    started.id == task.id && started.frames.len() <= depth && started.location() != location
+                }),
+                (StepMode::Out, Some((id, depth, _))) => self
+                    .task(id)
+                    .map_or(true, |started| started.id == task.id && started.frames.len() < depth),
+            };
+            if done {
+                return StopReason::Step;
+            }
+            if self.frame - start_frame >= RUN_FRAME_LIMIT {
+                return StopReason::FrameLimit;
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::fileio::name_tables::enemy_name;
+use crate::fileio::opcode_data::Opcode;
+use crate::fileio::rdt_scd::ScdInstruction;
+use crate::fileio::room_id::RoomId;
+use crate::fileio::room_layout::{
+    aot_type_name, ScriptKind, ScriptLocation, PARAM_AOT, PARAM_AOT_FLOOR, PARAM_AOT_TYPE,
+    PARAM_DOOR_NEXT_CAMERA, PARAM_DOOR_NEXT_ROOM, PARAM_DOOR_NEXT_STAGE, PARAM_EM_ID, PARAM_EM_NO, PARAM_EM_X,
+    PARAM_EM_Y, PARAM_EM_Z, PARAM_ITEM_AMOUNT, PARAM_ITEM_ID,
+};
+use std::fmt;
+
+/// Something a script did that is visible outside its own control flow
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum Effect {
+    TaskStarted { slot: i32, script: ScriptKind, function: usize },
+    TaskEnded,
+    TaskKilled { slot: i32 },
+    FlagChanged { bit_array: i32, bit_number: i32, value: bool },
+    VariableChanged { var_id: i32, old: i32, new: i32 },
+    AotSet { aot: i32, aot_type: i32, floor: i32 },
+    Door { aot: i32, next_stage: i32, next_room: i32, next_camera: i32 },
+    Item { aot: i32, item_id: i32, amount: i32 },
+    EntitySpawn { em_no: i32, id: i32, x: i32, y: i32, z: i32 },
+    CameraChange { camera: i32 },
+    Sound,
+    Message,
+    /// Any other instruction acting on the world, which the machine does not model
+    Other,
+    /// A condition the game state cannot evaluate, taken as true
+    Assumed,
+    /// Control flow the machine cannot follow; the instruction is skipped
+    Unsupported,
+    /// The task ran too many instructions in one frame and was suspended
+    InstructionLimit,
+}
+
+/// One entry of the world-state log
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct WorldEvent {
+    pub frame: u32,
+    /// Id of the task that ran the instruction
+    pub task: usize,
+    pub location: ScriptLocation,
+    pub effect: Effect,
+    /// The instruction as pseudocode
+    pub code: String,
+}
+
+impl Effect {
+    /// The world effect of an instruction that does not touch flags, variables or control flow
+    pub fn from_instruction(instruction: &ScdInstruction) -> Option<Effect> {
+        let p = &instruction.params;
+        let effect = match num::FromPrimitive::from_u8(instruction.opcode)? {
+            Opcode::AotSet | Opcode::AotSet4p => Effect::AotSet {
+                aot: p[PARAM_AOT],
+                aot_type: p[PARAM_AOT_TYPE],
+                floor: p[PARAM_AOT_FLOOR],
+            },
+            Opcode::DoorAotSet => Effect::Door {
+                aot: p[PARAM_AOT],
+                next_stage: p[PARAM_DOOR_NEXT_STAGE],
+                next_room: p[PARAM_DOOR_NEXT_ROOM],
+                next_camera: p[PARAM_DOOR_NEXT_CAMERA],
+            },
+            Opcode::ItemAotSet => Effect::Item {
+                aot: p[PARAM_AOT],
+                item_id: p[PARAM_ITEM_ID],
+                amount: p[PARAM_ITEM_AMOUNT],
+            },
+            Opcode::SceEmSet => Effect::EntitySpawn {
+                em_no: p[PARAM_EM_NO],
+                id: p[PARAM_EM_ID],
+                x: p[PARAM_EM_X],
+                y: p[PARAM_EM_Y],
+                z: p[PARAM_EM_Z],
+            },
+            Opcode::CutChg => Effect::CameraChange { camera: p[0] },
+            Opcode::SeOn | Opcode::SceBgmControl | Opcode::SceBgmtblSet | Opcode::XaOn | Opcode::XaVol => Effect::Sound,
+            Opcode::MessageOn => Effect::Message,
+            Opcode::NoOp => return None,
+            _ => Effect::Other,
+        };
+        Some(effect)
+    }
+}
+
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Effect::TaskStarted { slot, script, function } => {
+                write!(f, "task started in slot {} at {} function {}", slot, script.name(), function)
+            }
+            Effect::TaskEnded => write!(f, "task ended"),
+            Effect::TaskKilled { slot } => write!(f, "task in slot {} killed", slot),
+            Effect::FlagChanged { bit_array, bit_number, value } => {
+                write!(f, "bit[{}][{}] = {}", bit_array, bit_number, *value as i32)
+            }
+            Effect::VariableChanged { var_id, old, new } => write!(f, "var[{}] = {} (was {})", var_id, new, old),
+            Effect::AotSet { aot, aot_type, floor } => {
+                write!(f, "AOT {} set: {} on floor {}", aot, aot_type_name(*aot_type), floor)
+            }
+            Effect::Door { aot, next_stage, next_room, next_camera } => write!(
+                f,
+                "door AOT {} set to {} camera {}",
+                aot,
+                RoomId::from_door(*next_stage, *next_room, 0),
+                next_camera
+            ),
+            Effect::Item { aot, item_id, amount } => write!(f, "item AOT {} set: item {} x{}", aot, item_id, amount),
+            Effect::EntitySpawn { em_no, id, x, y, z } => {
+                write!(f, "{} spawned in slot {} at ({}, {}, {})", enemy_name(*id), em_no, x, y, z)
+            }
+            Effect::CameraChange { camera } => write!(f, "camera changed to {}", camera),
+            Effect::Sound => write!(f, "sound"),
+            Effect::Message => write!(f, "message"),
+            Effect::Other => write!(f, "other"),
+            Effect::Assumed => write!(f, "condition assumed true"),
+            Effect::Unsupported => write!(f, "unsupported, skipped"),
+            Effect::InstructionLimit => write!(f, "instruction limit reached, task suspended"),
+        }
+    }
+}
+
+impl fmt::Display for WorldEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[frame {}] task {} {} {}:{} {}",
+            self.frame,
+            self.task,
+            self.location.script.name(),
+            self.location.function,
+            self.location.instruction,
+            self.effect
+        )?;
+        if !self.code.is_empty() {
+            write!(f, ": {}", self.code)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,688 @@
+use crate::analysis::conditions::Condition;
+use crate::file_handler::RdtFileData;
+use crate::fileio::name_tables::NameTables;
+use crate::fileio::opcode_data::Opcode;
+use crate::fileio::rdt_scd::{ScdFunction, ScdInstruction};
+use crate::fileio::room_layout::{ScriptKind, ScriptLocation};
+use crate::vm::effects::{Effect, WorldEvent};
+use crate::vm::state::{calculate, GameState};
+use crate::vm::structure::FunctionStructure;
+use std::collections::HashMap;
+
+/// Instructions a task may run in one frame before it is suspended, which stops
+/// loops without a Sleep or EvtNext from hanging the machine
+pub const FRAME_INSTRUCTION_LIMIT: usize = 1000;
+
+/// Slot of the task running the init script, outside the range of EvtExec slots
+const INIT_SLOT: i32 = -1;
+
+/// Where a block continues once the conditions following IfStart, WhileStart or DoEnd are read
+#[derive(Clone, Debug)]
+struct PendingConditions {
+    /// Instruction to jump to when a condition fails
+    fail: usize,
+    /// Instruction to jump to when every condition holds, or None to run on
+    pass: Option<usize>,
+}
+
+/// One function on the call stack of a task
+#[derive(Clone, Debug)]
+pub struct CallFrame {
+    pub script: ScriptKind,
+    pub function: usize,
+    /// Index of the next instruction to run
+    pub pc: usize,
+    pending: Option<PendingConditions>,
+    /// Remaining iterations of each running ForStart, keyed by its instruction index
+    for_counts: HashMap<usize, i32>,
+}
+
+/// A script thread started for the init script, main functions 0 and 1 or by EvtExec
+#[derive(Clone, Debug)]
+pub struct Task {
+    pub id: usize,
+    /// Event slot given to EvtExec, used by EvtKill
+    pub slot: i32,
+    /// Call stack, innermost function last
+    pub frames: Vec<CallFrame>,
+    /// Frames left to wait in Sleeping
+    pub sleep: Option<i32>,
+    /// Instructions run in the current frame
+    executed: usize,
+}
+
+/// What the scheduler does after an instruction
+enum Outcome {
+    Continue,
+    /// Suspend the task until the next frame
+    Yield,
+    End,
+}
+
+/// Runs the scripts of one room against a game state, recording what they do
+///
+/// Tasks run in turn once per frame, each until it sleeps, yields or ends.
+/// The init script runs first, main functions 0 and 1 start on their own tasks once it ends.
+pub struct Vm {
+    init_functions: Vec<ScdFunction>,
+    main_functions: Vec<ScdFunction>,
+    init_structure: Vec<FunctionStructure>,
+    main_structure: Vec<FunctionStructure>,
+    names: NameTables,
+    pub state: GameState,
+    pub tasks: Vec<Task>,
+    pub log: Vec<WorldEvent>,
+    pub frame: u32,
+    /// Index in `tasks` of the task that runs next
+    current: usize,
+    next_task_id: usize,
+}
+
+impl Task {
+    /// The next instruction the task runs
+    pub fn location(&self) -> Option<ScriptLocation> {
+        self.frames.last().map(|frame| ScriptLocation {
+            script: frame.script,
+            function: frame.function,
+            instruction: frame.pc,
+        })
+    }
+}
+
+impl Vm {
+    /// Prepares a room for execution, formatting logged instructions with `names`
+    pub fn new(data: &RdtFileData, state: GameState, names: NameTables) -> Self {
+        let mut vm = Vm {
+            init_structure: data.init_functions.iter().map(FunctionStructure::new).collect(),
+            main_structure: data.main_functions.iter().map(FunctionStructure::new).collect(),
+            init_functions: data.init_functions.clone(),
+            main_functions: data.main_functions.clone(),
+            names,
+            state,
+            tasks: Vec::new(),
+            log: Vec::new(),
+            frame: 0,
+            current: 0,
+            next_task_id: 0,
+        };
+        if vm.init_functions.is_empty() {
+            vm.start_main();
+        } else {
+            vm.spawn(ScriptKind::Init, 0, INIT_SLOT, None);
+        }
+        vm
+    }
+
+    pub fn functions(&self, script: ScriptKind) -> &[ScdFunction] {
+        match script {
+            ScriptKind::Init => &self.init_functions,
+            ScriptKind::Main => &self.main_functions,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// The task that runs the next instruction
+    pub fn current_task(&self) -> Option<&Task> {
+        self.tasks.get(self.current)
+    }
+
+    pub fn task(&self, id: usize) -> Option<&Task> {
+        self.tasks.iter().find(|task| task.id == id)
+    }
+
+    /// Runs a single instruction of the current task, returns false once every task has ended
+    pub fn step(&mut self) -> bool {
+        if self.tasks.is_empty() {
+            return false;
+        }
+        match self.execute(self.current) {
+            Outcome::Continue => {}
+            Outcome::Yield => self.current += 1,
+            Outcome::End => {
+                self.tasks.remove(self.current);
+            }
+        }
+        if self.current >= self.tasks.len() {
+            self.end_frame();
+        }
+        true
+    }
+
+    /// Runs every task until the frame ends
+    pub fn run_frame(&mut self) {
+        let frame = self.frame;
+        while self.frame == frame && self.step() {}
+    }
+
+    /// Runs up to `frames` frames, stopping early once every task has ended
+    pub fn run(&mut self, frames: u32) {
+        for _ in 0..frames {
+            if self.is_finished() {
+                break;
+            }
+            self.run_frame();
+        }
+    }
+
+    /// Runs until the init script has ended, as the game does before showing the room
+    ///
+    /// Returns false if it was still running after `max_frames` frames.
+    pub fn run_init(&mut self, max_frames: u32) -> bool {
+        while self.tasks.iter().any(|task| task.slot == INIT_SLOT) {
+            if self.frame >= max_frames {
+                return false;
+            }
+            self.step();
+        }
+        true
+    }
+
+    fn end_frame(&mut self) {
+        self.frame += 1;
+        self.current = 0;
+        for task in &mut self.tasks {
+            task.executed = 0;
+        }
+    }
+
+    /// Starts a task at a function; `parent` is the task and instruction that started it
+    fn spawn(&mut self, script: ScriptKind, function: usize, slot: i32, parent: Option<(usize, ScriptLocation, &ScdInstruction)>) {
+        let id = self.next_task_id;
+        self.next_task_id += 1;
+        self.tasks.push(Task {
+            id,
+            slot,
+            frames: vec![new_frame(script, function)],
+            sleep: None,
+            executed: 0,
+        });
+        let effect = Effect::TaskStarted { slot, script, function };
+        let (task, location, code) = match parent {
+            Some((task, location, instruction)) => (task, location, self.names.code_line(instruction)),
+            None => (id, ScriptLocation { script, function, instruction: 0 }, String::new()),
+        };
+        self.log.push(WorldEvent {
+            frame: self.frame,
+            task,
+            location,
+            effect,
+            code,
+        });
+    }
+
+    fn record(&mut self, task: usize, location: ScriptLocation, effect: Effect, instruction: &ScdInstruction) {
+        self.log.push(WorldEvent {
+            frame: self.frame,
+            task,
+            location,
+            effect,
+            code: self.names.code_line(instruction),
+        });
+    }
+
+    fn execute(&mut self, index: usize) -> Outcome {
+        let Some(location) = self.tasks[index].location() else {
+            return Outcome::End;
+        };
+        let task_id = self.tasks[index].id;
+        let Some(function) = self.functions(location.script).get(location.function) else {
+            return self.return_from(index, location);
+        };
+        let Some(instruction) = function.instructions.get(location.instruction).cloned() else {
+            // Running off the end of a function behaves like EvtEnd
+            return self.return_from(index, location);
+        };
+
+        let task = &mut self.tasks[index];
+        task.executed += 1;
+        if task.executed > FRAME_INSTRUCTION_LIMIT {
+            self.record(task_id, location, Effect::InstructionLimit, &instruction);
+            return Outcome::Yield;
+        }
+
+        let condition = Condition::from_instruction(&instruction);
+        let frame = self.tasks[index].frames.last_mut().unwrap();
+        if condition.is_none() {
+            // The conditions of the open block all held
+            if let Some(PendingConditions { pass: Some(pass), .. }) = frame.pending.take() {
+                frame.pc = pass;
+                return Outcome::Continue;
+            }
+        }
+        frame.pc += 1;
+
+        if let Some(condition) = condition {
+            let holds = match self.state.evaluate(&condition) {
+                Some(holds) => holds,
+                None => {
+                    self.record(task_id, location, Effect::Assumed, &instruction);
+                    true
+                }
+            };
+            let frame = self.tasks[index].frames.last_mut().unwrap();
+            if !holds {
+                if let Some(pending) = frame.pending.take() {
+                    frame.pc = pending.fail;
+                }
+            }
+            return Outcome::Continue;
+        }
+
+        if instruction.error.is_some() {
+            self.record(task_id, location, Effect::Unsupported, &instruction);
+            return Outcome::Continue;
+        }
+        let structure = match location.script {
+            ScriptKind::Init => &self.init_structure[location.function],
+            ScriptKind::Main => &self.main_structure[location.function],
+        };
+        let pc = location.instruction;
+        let partner = structure.partner[pc];
+        let break_target = structure.break_target[pc];
+        let after_break = break_target.and_then(|end| structure.partner[end]);
+        let cases = structure.cases.get(&pc).cloned().unwrap_or_default();
+        let p = &instruction.params;
+
+        match num::FromPrimitive::from_u8(instruction.opcode) {
+            Some(Opcode::EvtEnd) => return self.return_from(index, location),
+            Some(Opcode::EvtNext) | Some(Opcode::Wsleep) | Some(Opcode::Wsleeping) => return Outcome::Yield,
+            Some(Opcode::IfStart) | Some(Opcode::WhileStart) => match partner {
+                Some(end) => {
+                    self.call_frame(index).pending = Some(PendingConditions {
+                        fail: end + 1,
+                        pass: None,
+                    })
+                }
+                None => self.record(task_id, location, Effect::Unsupported, &instruction),
+            },
+            // Only reached at the end of the if branch, so the else branch is skipped
+            Some(Opcode::ElseStart) => {
+                if let Some(end) = partner {
+                    self.call_frame(index).pc = end + 1;
+                }
+            }
+            Some(Opcode::WhileEnd) => {
+                if let Some(start) = partner {
+                    self.call_frame(index).pc = start;
+                }
+            }
+            Some(Opcode::ForStart) => {
+                let frame = self.call_frame(index);
+                match partner {
+                    Some(end) if p[2] <= 0 => frame.pc = end + 1,
+                    _ => {
+                        frame.for_counts.insert(pc, p[2]);
+                    }
+                }
+            }
+            Some(Opcode::ForEnd) => {
+                if let Some(start) = partner {
+                    let frame = self.call_frame(index);
+                    let remaining = frame.for_counts.get(&start).copied().unwrap_or(1) - 1;
+                    if remaining > 0 {
+                        frame.for_counts.insert(start, remaining);
+                        frame.pc = start + 1;
+                    } else {
+                        frame.for_counts.remove(&start);
+                    }
+                }
+            }
+            // The conditions after DoEnd decide whether the loop runs again
+            Some(Opcode::DoEnd) => {
+                if let Some(start) = partner {
+                    let fail = first_non_condition(&self.functions(location.script)[location.function], pc + 1);
+                    self.call_frame(index).pending = Some(PendingConditions {
+                        fail,
+                        pass: Some(start + 1),
+                    });
+                }
+            }
+            Some(Opcode::Switch) => {
+                let value = self.state.variable(p[0]);
+                let function = &self.functions(location.script)[location.function];
+                let case = cases.into_iter().find(|&case| function.instructions[case].params[2] == value);
+                match (case, partner) {
+                    (Some(case), _) => self.call_frame(index).pc = case + 1,
+                    (None, Some(end)) => self.call_frame(index).pc = end + 1,
+                    (None, None) => self.record(task_id, location, Effect::Unsupported, &instruction),
+                }
+            }
+            Some(Opcode::Break) => match break_target {
+                Some(end) => {
+                    let frame = self.call_frame(index);
+                    if let Some(start) = after_break {
+                        frame.for_counts.remove(&start);
+                    }
+                    frame.pc = end + 1;
+                }
+                None => self.record(task_id, location, Effect::Unsupported, &instruction),
+            },
+            Some(Opcode::GoSub) => {
+                self.tasks[index].frames.push(new_frame(location.script, p[0] as usize));
+            }
+            Some(Opcode::EvtExec) => {
+                self.spawn(ScriptKind::Main, p[2] as usize, p[0], Some((task_id, location, &instruction)));
+            }
+            Some(Opcode::EvtKill) => {
+                let slot = p[0];
+                self.record(task_id, location, Effect::TaskKilled { slot }, &instruction);
+                let own_slot = self.tasks[index].slot;
+                let before = self.tasks[..index].iter().filter(|t| t.slot == slot).count();
+                self.tasks.retain(|t| t.slot != slot || t.id == task_id);
+                self.current -= before;
+                if own_slot == slot {
+                    return Outcome::End;
+                }
+            }
+            Some(Opcode::Sleep) => self.tasks[index].sleep = Some(p[1]),
+            Some(Opcode::Sleeping) => {
+                let task = &mut self.tasks[index];
+                let remaining = task.sleep.get_or_insert(p[0] | (p[1] << 8));
+                if *remaining > 0 {
+                    *remaining -= 1;
+                    task.frames.last_mut().unwrap().pc = pc;
+                    return Outcome::Yield;
+                }
+                task.sleep = None;
+            }
+            Some(Opcode::SetBit) => {
+                let (bit_array, bit_number) = (p[0], p[1]);
+                let value = match p[2] {
+                    0 => false,
+                    1 => true,
+                    7 => !self.state.flag(bit_array, bit_number),
+                    _ => {
+                        self.record(task_id, location, Effect::Unsupported, &instruction);
+                        return Outcome::Continue;
+                    }
+                };
+                self.state.set_flag(bit_array, bit_number, value);
+                self.record(task_id, location, Effect::FlagChanged { bit_array, bit_number, value }, &instruction);
+            }
+            Some(Opcode::Save) => self.write_variable(task_id, location, p[0], p[1], &instruction),
+            Some(Opcode::Copy) => {
+                let value = self.state.variable(p[1]);
+                self.write_variable(task_id, location, p[0], value, &instruction);
+            }
+            Some(Opcode::Calc) => match calculate(self.state.variable(p[2]), p[1], p[3]) {
+                Some(value) => self.write_variable(task_id, location, p[2], value, &instruction),
+                None => self.record(task_id, location, Effect::Unsupported, &instruction),
+            },
+            Some(Opcode::Goto) | Some(Opcode::EvtChain) => {
+                self.record(task_id, location, Effect::Unsupported, &instruction);
+            }
+            Some(Opcode::EndIf) | Some(Opcode::DoStart) | Some(Opcode::Case) | Some(Opcode::EndSwitch) => {}
+            opcode => {
+                if opcode == Some(Opcode::CutChg) {
+                    self.state.camera = Some(p[0]);
+                }
+                if let Some(effect) = Effect::from_instruction(&instruction) {
+                    self.record(task_id, location, effect, &instruction);
+                }
+            }
+        }
+        Outcome::Continue
+    }
+
+    /// Innermost call frame of a task
+    fn call_frame(&mut self, index: usize) -> &mut CallFrame {
+        self.tasks[index].frames.last_mut().unwrap()
+    }
+
+    fn write_variable(&mut self, task: usize, location: ScriptLocation, var_id: i32, value: i32, instruction: &ScdInstruction) {
+        let old = self.state.variable(var_id);
+        self.state.set_variable(var_id, value);
+        let new = self.state.variable(var_id);
+        self.record(task, location, Effect::VariableChanged { var_id, old, new }, instruction);
+    }
+
+    /// Leaves the innermost function of a task; the main script starts when the init script ends
+    fn return_from(&mut self, index: usize, location: ScriptLocation) -> Outcome {
+        let task = &mut self.tasks[index];
+        task.frames.pop();
+        if !task.frames.is_empty() {
+            return Outcome::Continue;
+        }
+        let (task_id, slot) = (task.id, task.slot);
+        self.log.push(WorldEvent {
+            frame: self.frame,
+            task: task_id,
+            location,
+            effect: Effect::TaskEnded,
+            code: String::new(),
+        });
+        if location.script == ScriptKind::Init && slot == INIT_SLOT {
+            self.start_main();
+        }
+        Outcome::End
+    }
+
+    /// Starts the entry points of the main script, function 0 and 1
+    fn start_main(&mut self) {
+        for function in 0..self.main_functions.len().min(2) {
+            self.spawn(ScriptKind::Main, function, function as i32, None);
+        }
+    }
+}
+
+fn new_frame(script: ScriptKind, function: usize) -> CallFrame {
+    CallFrame {
+        script,
+        function,
+        pc: 0,
+        pending: None,
+        for_counts: HashMap::new(),
+    }
+}
+
+/// Index of the first instruction from `start` on that is not a condition
+fn first_non_condition(function: &ScdFunction, start: usize) -> usize {
+    function.instructions[start.min(function.instructions.len())..]
+        .iter()
+        .position(|i| Condition::from_instruction(i).is_none())
+        .map_or(function.instructions.len(), |offset| start + offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_rooms::{load, room};
+
+    /// A room with an empty init script and the given main functions, before its first frame
+    fn vm(main: &[&str], state: GameState) -> Vm {
+        Vm::new(&load(&room(&["EvtEnd()"], main)), state, NameTables::builtin())
+    }
+
+    /// Bits set in bit array 1
+    fn bits(vm: &Vm) -> Vec<i32> {
+        vm.state.flags.get(&1).map_or(Vec::new(), |bits| bits.iter().copied().collect())
+    }
+
+    fn with_flag(bit_array: i32, bit_number: i32) -> GameState {
+        let mut state = GameState::default();
+        state.set_flag(bit_array, bit_number, true);
+        state
+    }
+
+    fn with_variable(var_id: i32, value: i32) -> GameState {
+        let mut state = GameState::default();
+        state.set_variable(var_id, value);
+        state
+    }
+
+    #[test]
+    fn if_else_runs_the_branch_its_condition_picks() {
+        let main = "IfStart(0, 0)
+CheckBit(2, 5, 1)
+SetBit(1, 0, 1)
+ElseStart(0, 0)
+SetBit(1, 1, 1)
+EndIf()
+SetBit(1, 2, 1)
+EvtEnd()";
+        let mut taken = vm(&[main], with_flag(2, 5));
+        taken.run(1);
+        assert_eq!(bits(&taken), [0, 2]);
+
+        let mut not_taken = vm(&[main], GameState::default());
+        not_taken.run(1);
+        assert_eq!(bits(&not_taken), [1, 2]);
+        assert!(not_taken.is_finished());
+    }
+
+    #[test]
+    fn sleeping_waits_the_frames_given_to_sleep() {
+        let mut vm = vm(&["Sleep(10, 3)\nSleeping(3, 0)\nSetBit(1, 0, 1)\nEvtEnd()"], GameState::default());
+        vm.run(3);
+        assert_eq!(vm.frame, 3);
+        assert!(bits(&vm).is_empty());
+        assert_eq!(vm.current_task().unwrap().sleep, Some(0));
+
+        vm.run_frame();
+        assert_eq!(bits(&vm), [0]);
+        let set = vm.log.iter().find(|event| matches!(event.effect, Effect::FlagChanged { .. })).unwrap();
+        assert_eq!(set.frame, 3);
+    }
+
+    #[test]
+    fn for_loop_repeats_its_count_and_break_leaves_it() {
+        let mut full = vm(&["ForStart(0, 0, 5)\nCalc(0, 0, 3, 1)\nForEnd(0)\nEvtEnd()"], GameState::default());
+        full.run(1);
+        assert_eq!(full.state.variable(3), 5);
+
+        let main = "ForStart(0, 0, 5)
+Calc(0, 0, 3, 1)
+IfStart(0, 0)
+Compare(0, 3, 0, 2)
+Break(0)
+EndIf()
+ForEnd(0)
+SetBit(1, 0, 1)
+EvtEnd()";
+        let mut broken = vm(&[main], GameState::default());
+        broken.run(1);
+        assert_eq!(broken.state.variable(3), 2);
+        assert_eq!(bits(&broken), [0]);
+    }
+
+    #[test]
+    fn while_loop_stops_on_its_condition_or_break() {
+        let main = "WhileStart(0, 0)
+Compare(0, 3, 3, 10)
+Calc(0, 0, 3, 1)
+IfStart(0, 0)
+Compare(0, 3, 0, 4)
+Break(0)
+EndIf()
+WhileEnd(0)
+SetBit(1, 0, 1)
+EvtEnd()";
+        let mut broken = vm(&[main], GameState::default());
+        broken.run(1);
+        assert_eq!(broken.state.variable(3), 4);
+        assert_eq!(bits(&broken), [0]);
+
+        let mut ended = vm(&[main], with_variable(3, 6));
+        ended.run(1);
+        assert_eq!(ended.state.variable(3), 10);
+        assert_eq!(bits(&ended), [0]);
+    }
+
+    #[test]
+    fn do_loop_runs_until_its_condition_fails_or_break() {
+        let main = "DoStart(0, 0)
+Calc(0, 0, 3, 1)
+IfStart(0, 0)
+Compare(0, 3, 0, 3)
+Break(0)
+EndIf()
+DoEnd(0)
+Compare(0, 3, 3, 10)
+SetBit(1, 0, 1)
+EvtEnd()";
+        let mut broken = vm(&[main], GameState::default());
+        broken.run(1);
+        assert_eq!(broken.state.variable(3), 3);
+        assert_eq!(bits(&broken), [0]);
+
+        // Runs once more even though the condition already fails on entry
+        let mut ended = vm(&[main], with_variable(3, 20));
+        ended.run(1);
+        assert_eq!(ended.state.variable(3), 21);
+        assert_eq!(bits(&ended), [0]);
+    }
+
+    #[test]
+    fn switch_falls_through_cases_until_break() {
+        let main = "Switch(3, 0)
+Case(0, 0, 1)
+SetBit(1, 1, 1)
+Case(0, 0, 2)
+SetBit(1, 2, 1)
+Break(0)
+Case(0, 0, 3)
+SetBit(1, 3, 1)
+EndSwitch(0)
+SetBit(1, 0, 1)
+EvtEnd()";
+        for (value, expected) in [(1, vec![0, 1, 2]), (2, vec![0, 2]), (3, vec![0, 3]), (4, vec![0])] {
+            let mut vm = vm(&[main], with_variable(3, value));
+            vm.run(1);
+            assert_eq!(bits(&vm), expected, "var[3] = {}", value);
+        }
+    }
+
+    #[test]
+    fn go_sub_returns_to_the_caller() {
+        let mut vm = vm(&["GoSub(2)\nSetBit(1, 1, 1)\nEvtEnd()", "EvtEnd()", "SetBit(1, 0, 1)\nEvtEnd()"], GameState::default());
+        vm.run(1);
+        let set: Vec<(i32, ScriptLocation)> = vm
+            .log
+            .iter()
+            .filter_map(|event| match event.effect {
+                Effect::FlagChanged { bit_number, .. } => Some((bit_number, event.location)),
+                _ => None,
+            })
+            .collect();
+        let at = |function, instruction| ScriptLocation { script: ScriptKind::Main, function, instruction };
+        assert_eq!(set, [(0, at(2, 0)), (1, at(0, 1))]);
+        assert!(vm.is_finished());
+    }
+
+    #[test]
+    fn evt_exec_starts_a_task_that_runs_in_the_same_frame() {
+        let mut vm = vm(&["EvtExec(4, 0, 2)\nEvtEnd()", "EvtEnd()", "SetBit(1, 0, 1)\nEvtEnd()"], GameState::default());
+        vm.run_frame();
+        assert_eq!(vm.frame, 1);
+        assert_eq!(bits(&vm), [0]);
+        assert!(vm.log.iter().any(|event| event.effect
+            == Effect::TaskStarted { slot: 4, script: ScriptKind::Main, function: 2 }));
+    }
+
+    #[test]
+    fn evt_kill_of_an_earlier_task_keeps_running_the_current_one() {
+        let waiting = "WhileStart(0, 0)\nEvtNext()\nWhileEnd(0)\nEvtEnd()";
+        let mut vm = vm(&[waiting, "EvtKill(0)\nSetBit(1, 0, 1)\nEvtEnd()"], GameState::default());
+        assert!(vm.run_init(1));
+        let killer = vm.tasks[1].id;
+        vm.run_frame();
+
+        // The killed task came first, so the index of the current task moved down with it
+        assert_eq!(bits(&vm), [0]);
+        let set = vm.log.iter().find(|event| matches!(event.effect, Effect::FlagChanged { .. })).unwrap();
+        assert_eq!((set.frame, set.task), (0, killer));
+        assert!(vm.is_finished());
+    }
+
+    #[test]
+    fn evt_kill_of_its_own_slot_ends_the_task() {
+        let mut vm = vm(&["EvtEnd()", "EvtKill(1)\nSetBit(1, 0, 1)\nEvtEnd()"], GameState::default());
+        vm.run(1);
+        assert!(bits(&vm).is_empty());
+        assert!(vm.is_finished());
+        assert!(vm.log.iter().any(|event| event.effect == Effect::TaskKilled { slot: 1 }));
+    }
+}
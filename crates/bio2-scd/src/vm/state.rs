@@ -0,0 +1,133 @@
+use crate::analysis::conditions::Condition;
+use crate::analysis::xref::XrefTarget;
+use crate::fileio::symbols::{parse_number, SymbolDictionary};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Flags, work variables and entity members read and written by room scripts
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct GameState {
+    /// Set bits of each bit array; bits not listed are clear
+    pub flags: BTreeMap<i32, BTreeSet<i32>>,
+    /// Work variables; variables not listed are 0
+    pub variables: BTreeMap<i32, i32>,
+    /// Members of the current work entity, read by MemberCmp
+    pub members: BTreeMap<i32, i32>,
+    /// Camera selected by the last CutChg
+    pub camera: Option<i32>,
+}
+
+impl GameState {
+    pub fn flag(&self, bit_array: i32, bit_number: i32) -> bool {
+        self.flags.get(&bit_array).map_or(false, |bits| bits.contains(&bit_number))
+    }
+
+    pub fn set_flag(&mut self, bit_array: i32, bit_number: i32, value: bool) {
+        if value {
+            self.flags.entry(bit_array).or_default().insert(bit_number);
+        } else if let Some(bits) = self.flags.get_mut(&bit_array) {
+            bits.remove(&bit_number);
+            if bits.is_empty() {
+                self.flags.remove(&bit_array);
+            }
+        }
+    }
+
+    pub fn variable(&self, var_id: i32) -> i32 {
+        self.variables.get(&var_id).copied().unwrap_or(0)
+    }
+
+    /// Stores a work variable, which holds 16 bits like in the game
+    pub fn set_variable(&mut self, var_id: i32, value: i32) {
+        let value = value as i16 as i32;
+        if value == 0 {
+            self.variables.remove(&var_id);
+        } else {
+            self.variables.insert(var_id, value);
+        }
+    }
+
+    pub fn member(&self, member: i32) -> i32 {
+        self.members.get(&member).copied().unwrap_or(0)
+    }
+
+    /// Current value of a flag, variable or member, flags as 0 or 1
+    pub fn value(&self, target: XrefTarget) -> i32 {
+        match target {
+            XrefTarget::Flag(array, bit) => self.flag(array, bit) as i32,
+            XrefTarget::Variable(var_id) => self.variable(var_id),
+            XrefTarget::Member(member) => self.member(member),
+        }
+    }
+
+    pub fn set_value(&mut self, target: XrefTarget, value: i32) {
+        match target {
+            XrefTarget::Flag(array, bit) => self.set_flag(array, bit, value != 0),
+            XrefTarget::Variable(var_id) => self.set_variable(var_id, value),
+            XrefTarget::Member(member) => {
+                self.members.insert(member, value);
+            }
+        }
+    }
+
+    /// Applies comma separated assignments like "flag:1:5=1,var:ROOM_STATE=7"
+    pub fn apply_assignments(&mut self, text: &str, symbols: &SymbolDictionary) -> Result<(), String> {
+        for assignment in text.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+            let (target, value) = assignment
+                .split_once('=')
+                .ok_or(format!("Invalid assignment '{}', expected target=value", assignment))?;
+            let value = parse_number(value).ok_or(format!("Invalid value in '{}'", assignment))?;
+            self.set_value(XrefTarget::parse(target.trim(), symbols)?, value);
+        }
+        Ok(())
+    }
+
+    /// Evaluates a condition, or returns None for conditions the state does not model
+    pub fn evaluate(&self, condition: &Condition) -> Option<bool> {
+        match *condition {
+            Condition::Bit { bit_array, bit_number, value } => Some(self.flag(bit_array, bit_number) == (value != 0)),
+            Condition::Compare { var_id, operation, value } => compare(self.variable(var_id), operation, value),
+            Condition::Member { member, operation, value } => compare(self.member(member), operation, value),
+            Condition::Case { var_id, value } => Some(self.variable(var_id) == value),
+            Condition::Other(_) => None,
+        }
+    }
+}
+
+/// Applies a Compare/MemberCmp operator, see `COMPARE_OPERATORS`
+fn compare(left: i32, operation: i32, right: i32) -> Option<bool> {
+    match operation {
+        0 => Some(left == right),
+        1 => Some(left > right),
+        2 => Some(left >= right),
+        3 => Some(left < right),
+        4 => Some(left <= right),
+        5 => Some(left != right),
+        6 => Some(left & right != 0),
+        _ => None,
+    }
+}
+
+/// Applies a Calc operation to a work variable value, or returns None for unknown operations
+///
+/// Operations: 0 add, 1 sub, 2 mul, 3 div, 4 mod, 5 or, 6 and, 7 xor, 8 not,
+/// 9 shift left, 10 logical shift right, 11 arithmetic shift right
+pub fn calculate(left: i32, operation: i32, right: i32) -> Option<i32> {
+    let (l, r) = (left as i16, right as i16);
+    let shift = (right & 0xf) as u32;
+    let result = match operation {
+        0 => l.wrapping_add(r),
+        1 => l.wrapping_sub(r),
+        2 => l.wrapping_mul(r),
+        3 => l.checked_div(r).unwrap_or(0),
+        4 => l.checked_rem(r).unwrap_or(0),
+        5 => l | r,
+        6 => l & r,
+        7 => l ^ r,
+        8 => !l,
+        9 => l.wrapping_shl(shift),
+        10 => ((l as u16) >> shift) as i16,
+        11 => l >> shift,
+        _ => return None,
+    };
+    Some(result as i32)
+}
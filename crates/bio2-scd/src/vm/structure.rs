@@ -0,0 +1,131 @@
+use crate::fileio::opcode_data::Opcode;
+use crate::fileio::rdt_scd::ScdFunction;
+use std::collections::HashMap;
+
+/// Parameter holding the block length of IfStart, ElseStart, loops, Switch and Case
+pub const PARAM_BLOCK_LENGTH: usize = 1;
+
+/// Matching block instructions of one function, found by nesting rather than block lengths
+///
+/// Every vector is indexed like `function.instructions`
+#[derive(Clone, Debug, Default)]
+pub struct FunctionStructure {
+    /// Opener to closer and back: IfStart → ElseStart or EndIf, ElseStart → EndIf,
+    /// WhileStart ↔ WhileEnd, ForStart ↔ ForEnd, DoStart ↔ DoEnd, Switch → EndSwitch
+    pub partner: Vec<Option<usize>>,
+    /// Case instructions of each Switch
+    pub cases: HashMap<usize, Vec<usize>>,
+    /// Closer of the loop or switch a Break leaves
+    pub break_target: Vec<Option<usize>>,
+}
+
+impl FunctionStructure {
+    pub fn new(function: &ScdFunction) -> Self {
+        let count = function.instructions.len();
+        let mut structure = FunctionStructure {
+            partner: vec![None; count],
+            cases: HashMap::new(),
+            break_target: vec![None; count],
+        };
+        // Open blocks as (opcode, index); an ElseStart replaces its IfStart
+        let mut open: Vec<(Opcode, usize)> = Vec::new();
+
+        for (index, instruction) in function.instructions.iter().enumerate() {
+            if instruction.error.is_some() {
+                continue;
+            }
+            let Some(opcode) = num::FromPrimitive::from_u8(instruction.opcode) else {
+                continue;
+            };
+            match opcode {
+                Opcode::IfStart | Opcode::WhileStart | Opcode::ForStart | Opcode::DoStart | Opcode::Switch => {
+                    open.push((opcode, index));
+                }
+                Opcode::ElseStart => {
+                    if let Some((Opcode::IfStart, start)) = open.last().copied() {
+                        structure.partner[start] = Some(index);
+                        open.pop();
+                        open.push((Opcode::ElseStart, index));
+                    }
+                }
+                Opcode::Case => {
+                    if let Some(&(Opcode::Switch, start)) = open.iter().rev().find(|(op, _)| *op == Opcode::Switch) {
+                        structure.cases.entry(start).or_default().push(index);
+                    }
+                }
+                Opcode::Break => {
+                    structure.break_target[index] = open
+                        .iter()
+                        .rev()
+                        .find(|(op, _)| matches!(op, Opcode::WhileStart | Opcode::ForStart | Opcode::DoStart | Opcode::Switch))
+                        .map(|&(_, start)| start);
+                }
+                _ => {
+                    let Some(opener) = closed_by(opcode) else {
+                        continue;
+                    };
+                    if let Some(&(top, start)) = open.last() {
+                        if opener.contains(&top) {
+                            open.pop();
+                            structure.partner[index] = Some(start);
+                            structure.partner[start] = Some(index);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Breaks point at the opener until now, move them to its closer
+        for target in structure.break_target.iter_mut() {
+            *target = target.and_then(|start| structure.partner[start]);
+        }
+        structure
+    }
+
+    /// Instructions with a block length paired with the instruction their block ends at
+    ///
+    /// IfStart, ElseStart, loops and Switch end at their partner, Case at the next Case or the
+    /// EndSwitch. Sorted by opener
+    pub fn block_ends(&self, function: &ScdFunction) -> Vec<(usize, usize)> {
+        let mut ends: Vec<(usize, usize)> = Vec::new();
+        for (index, partner) in self.partner.iter().enumerate() {
+            let opens = matches!(
+                num::FromPrimitive::from_u8(function.instructions[index].opcode),
+                Some(Opcode::IfStart | Opcode::ElseStart | Opcode::WhileStart | Opcode::ForStart | Opcode::DoStart | Opcode::Switch)
+            );
+            if let Some(partner) = partner.filter(|&partner| opens && partner > index) {
+                ends.push((index, partner));
+            }
+        }
+        for (&switch, cases) in &self.cases {
+            let end_switch = self.partner[switch];
+            for (position, &case) in cases.iter().enumerate() {
+                if let Some(end) = cases.get(position + 1).copied().or(end_switch) {
+                    ends.push((case, end));
+                }
+            }
+        }
+        ends.sort_unstable();
+        ends
+    }
+}
+
+/// The openers a block closing opcode can end
+pub fn closed_by(opcode: Opcode) -> Option<&'static [Opcode]> {
+    match opcode {
+        Opcode::EndIf => Some(&[Opcode::IfStart, Opcode::ElseStart]),
+        Opcode::WhileEnd => Some(&[Opcode::WhileStart]),
+        Opcode::ForEnd => Some(&[Opcode::ForStart]),
+        Opcode::DoEnd => Some(&[Opcode::DoStart]),
+        Opcode::EndSwitch => Some(&[Opcode::Switch]),
+        _ => None,
+    }
+}
+
+/// True for the instructions whose block length parameter depends on the size of their block
+pub fn has_block_length(opcode: Opcode) -> bool {
+    matches!(
+        opcode,
+        Opcode::IfStart | Opcode::ElseStart | Opcode::WhileStart | Opcode::ForStart | Opcode::DoStart | Opcode::Switch | Opcode::Case
+    )
+}
@@ -0,0 +1,285 @@
+use crate::analysis::script_diff::{common_subsequence, comparable};
+use crate::fileio::opcode_data::Opcode;
+use crate::fileio::rdt::{RDTHeader, RDT_HEADER_SIZE, RDT_OFFSET_INIT_SCRIPT, RDT_OFFSET_MAIN_SCRIPT};
+use crate::fileio::rdt_scd::{ScdFunction, ScdInstruction};
+use crate::fileio::room_layout::ScriptKind;
+use crate::fileio::utils::{read_u16, with_offset_table};
+use crate::vm::structure::{FunctionStructure, PARAM_BLOCK_LENGTH};
+use std::collections::BTreeSet;
+
+/// An instruction to write, with what its block length adds to the bytes its block spans
+///
+/// The game does not count every kind of block the same way, e.g. a ForStart also counts its
+/// ForEnd, so the difference found in the function the instruction came from is kept when the
+/// block changes size.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct PlacedInstruction {
+    pub instruction: ScdInstruction,
+    pub block_slack: i32,
+}
+
+/// The instructions of a decoded function with the block slack they have in it
+pub fn placed_instructions(function: &ScdFunction) -> Vec<PlacedInstruction> {
+    let mut placed: Vec<PlacedInstruction> = function
+        .instructions
+        .iter()
+        .map(|instruction| PlacedInstruction {
+            instruction: instruction.clone(),
+            block_slack: 0,
+        })
+        .collect();
+    let instructions = &function.instructions;
+    for (start, end) in FunctionStructure::new(function).block_ends(function) {
+        if let Some(&block_length) = instructions[start].params.get(PARAM_BLOCK_LENGTH) {
+            let span = instructions[end].offset as i32 - instructions[start + 1].offset as i32;
+            placed[start].block_slack = block_length - span;
+        }
+    }
+    placed
+}
+
+/// Gives the instructions an edit of a function kept the block slack they had before it
+///
+/// Instructions are aligned as in [`crate::analysis::script_diff`], ignoring block lengths, so a
+/// block whose contents were edited keeps its slack while new blocks keep the one written in them
+pub fn keep_block_slack(original: &[PlacedInstruction], edited: &mut [PlacedInstruction]) {
+    let keys = |instructions: &[PlacedInstruction]| -> Vec<_> {
+        instructions.iter().map(|placed| comparable(&placed.instruction)).collect()
+    };
+    for (old, new) in common_subsequence(&keys(original), &keys(edited)) {
+        edited[new].block_slack = original[old].block_slack;
+    }
+}
+
+/// Encodes the body of a function, recomputing block lengths from the new instruction sizes
+///
+/// Block lengths of 0 are left as they are, since some rooms do not fill them in
+pub fn encode_function(instructions: &[PlacedInstruction]) -> Result<Vec<u8>, String> {
+    let mut function = ScdFunction {
+        index: 0,
+        instructions: instructions.iter().map(|placed| placed.instruction.clone()).collect(),
+    };
+    let mut encoded: Vec<Vec<u8>> = function
+        .instructions
+        .iter()
+        .map(encode_placed)
+        .collect::<Result<_, String>>()?;
+    let mut offsets = Vec::with_capacity(encoded.len() + 1);
+    let mut offset = 0;
+    for bytes in &encoded {
+        offsets.push(offset as i32);
+        offset += bytes.len();
+    }
+    offsets.push(offset as i32);
+
+    for (start, end) in FunctionStructure::new(&function).block_ends(&function) {
+        let instruction = &mut function.instructions[start];
+        match instruction.params.get_mut(PARAM_BLOCK_LENGTH) {
+            Some(block_length) if *block_length != 0 && instruction.error.is_none() => {
+                *block_length = offsets[end] - offsets[start + 1] + instructions[start].block_slack;
+            }
+            _ => continue,
+        }
+        encoded[start] = encode_placed(instruction)
+            .map_err(|e| format!("{} at instruction {}: {}", instruction.name, start, e))?;
+    }
+    Ok(encoded.concat())
+}
+
+/// Bytes an instruction takes in a function
+fn encode_placed(instruction: &ScdInstruction) -> Result<Vec<u8>, String> {
+    let mut bytes = instruction.encode()?;
+    // The decoder reads the Sleeping instruction inside Sleep on its own, see Assembler::assemble
+    if instruction.error.is_none() && instruction.opcode == Opcode::Sleep as u8 {
+        bytes.truncate(1);
+    }
+    Ok(bytes)
+}
+
+/// The bytes of each function of a script section, up to the next function or section
+pub fn script_function_bytes(contents: &[u8], header: &RDTHeader, section: usize) -> Vec<Vec<u8>> {
+    let Some(data) = section_data(contents, header, section) else {
+        return Vec::new();
+    };
+    if data.len() < 2 {
+        return Vec::new();
+    }
+    let count = read_u16(data, 0) as usize / 2;
+    if data.len() < count * 2 {
+        return Vec::new();
+    }
+    let offsets: Vec<usize> = (0..count).map(|i| read_u16(data, i * 2) as usize).collect();
+    (0..count)
+        .map(|i| {
+            let start = offsets[i].min(data.len());
+            let end = offsets.get(i + 1).copied().unwrap_or(data.len()).clamp(start, data.len());
+            data[start..end].to_vec()
+        })
+        .collect()
+}
+
+/// Writes new bodies for some script functions into an RDT file
+///
+/// Each script lists its functions by index, where None keeps the original bytes of the
+/// function. Changed functions are encoded with recomputed block lengths and the function
+/// tables and section offsets are rebuilt around them. Returns the file and warnings about
+/// sections that moved.
+pub fn write_scripts(
+    contents: &[u8],
+    scripts: &[(ScriptKind, Vec<Option<Vec<PlacedInstruction>>>)],
+) -> Result<(Vec<u8>, Vec<String>), String> {
+    let header = RDTHeader::from(contents).map_err(|e| format!("Invalid RDT file format: {}", e))?;
+    let mut replacements = Vec::new();
+    for (script, functions) in scripts {
+        if functions.iter().all(Option::is_none) {
+            continue;
+        }
+        let section = match script {
+            ScriptKind::Init => RDT_OFFSET_INIT_SCRIPT,
+            ScriptKind::Main => RDT_OFFSET_MAIN_SCRIPT,
+        };
+        let original = script_function_bytes(contents, &header, section);
+        let mut bodies = Vec::with_capacity(functions.len());
+        for (index, function) in functions.iter().enumerate() {
+            let body = match function {
+                Some(instructions) => encode_function(instructions)
+                    .map_err(|e| format!("{} function {}: {}", script.name(), index, e))?,
+                None => original.get(index).cloned().unwrap_or_default(),
+            };
+            bodies.push(body);
+        }
+        // Functions are called by index, so only those removed at the end can be left out
+        while bodies.last().map_or(false, |body| body.is_empty()) {
+            bodies.pop();
+        }
+        replacements.push((section, with_offset_table(bodies)?));
+    }
+    if replacements.is_empty() {
+        return Ok((contents.to_vec(), Vec::new()));
+    }
+    replace_sections(contents, &replacements)
+}
+
+/// Rebuilds an RDT file with new contents for some sections of its offset table
+///
+/// The file is split at every distinct section offset and the parts starting at a replaced
+/// section are swapped out. Returns the file and warnings about sections that moved.
+pub fn replace_sections(contents: &[u8], replacements: &[(usize, Vec<u8>)]) -> Result<(Vec<u8>, Vec<String>), String> {
+    let mut header = RDTHeader::from(contents).map_err(|e| format!("Invalid RDT file format: {}", e))?;
+    let starts = section_starts(contents, &header);
+    if let Some((index, _)) = replacements.iter().find(|(index, _)| !starts.contains(&(header.offsets[*index] as usize))) {
+        return Err(format!("Section {} is not present in the file", index));
+    }
+
+    let mut bytes = contents[..RDT_HEADER_SIZE.min(contents.len())].to_vec();
+    let mut warnings = Vec::new();
+    let original_offsets = header.offsets;
+    for (i, &start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).copied().unwrap_or(contents.len());
+        let sections: Vec<usize> = (0..original_offsets.len())
+            .filter(|&index| original_offsets[index] as usize == start)
+            .collect();
+        let new_start = bytes.len();
+        for &index in &sections {
+            header.offsets[index] = new_start as u32;
+        }
+        match replacements.iter().find(|(index, _)| sections.contains(index)) {
+            Some((_, replacement)) => bytes.extend_from_slice(replacement),
+            None => {
+                if new_start != start && warnings.is_empty() {
+                    warnings.push(format!(
+                        "sections from 0x{:x} moved to 0x{:x}; offsets stored inside them are not updated",
+                        start, new_start
+                    ));
+                }
+                bytes.extend_from_slice(&contents[start..end]);
+            }
+        }
+    }
+    bytes[..RDT_HEADER_SIZE].copy_from_slice(&header.to_bytes());
+    Ok((bytes, warnings))
+}
+
+/// The bytes from a section's offset to the next distinct offset
+fn section_data<'a>(contents: &'a [u8], header: &RDTHeader, section: usize) -> Option<&'a [u8]> {
+    let starts = section_starts(contents, header);
+    let start = header.offsets[section] as usize;
+    let position = starts.iter().position(|&offset| offset == start)?;
+    let end = starts.get(position + 1).copied().unwrap_or(contents.len());
+    Some(&contents[start..end])
+}
+
+/// Distinct offsets within the file, sections may share one
+pub(crate) fn section_starts(contents: &[u8], header: &RDTHeader) -> Vec<usize> {
+    let mut starts: BTreeSet<usize> = header
+        .offsets
+        .iter()
+        .map(|&offset| offset as usize)
+        .filter(|&offset| offset >= RDT_HEADER_SIZE && offset < contents.len())
+        .collect();
+    if contents.len() > RDT_HEADER_SIZE {
+        starts.insert(RDT_HEADER_SIZE);
+    }
+    starts.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fileio::rdt_scd::parse_function_body;
+
+    /// IfStart with a CheckBit and a SetBit, EndIf, a Sleep and EvtEnd
+    const FUNCTION: &[u8] = &[
+        0x06, 0x00, 0x0a, 0x00, // IfStart(dummy: 0, block_length: 10)
+        0x21, 0x01, 0x05, 0x00, // CheckBit(bit_array: 1, bit_number: 5, value: 0)
+        0x22, 0x01, 0x06, 0x01, // SetBit(bit_array: 1, bit_number: 6, operation: 1)
+        0x08, // EndIf
+        0x09, 0x0a, 0x1e, 0x00, // Sleep and the Sleeping it holds
+        0x01, // EvtEnd
+    ];
+
+    #[test]
+    fn decoded_function_encodes_to_the_same_bytes() {
+        let function = parse_function_body(FUNCTION);
+        assert_eq!(function.size(), FUNCTION.len());
+        assert_eq!(encode_function(&placed_instructions(&function)).unwrap(), FUNCTION);
+    }
+
+    #[test]
+    fn insert_recomputes_the_enclosing_block_length() {
+        let mut placed = placed_instructions(&parse_function_body(FUNCTION));
+        assert_eq!(placed[0].block_slack, 2);
+        let inserted = placed[2].clone();
+        placed.insert(2, inserted);
+
+        let bytes = encode_function(&placed).unwrap();
+        assert_eq!(bytes.len(), FUNCTION.len() + 4);
+        assert_eq!(read_u16(&bytes, 2), 14);
+        assert_eq!(&bytes[4..], [&FUNCTION[4..12], &FUNCTION[8..]].concat());
+    }
+
+    #[test]
+    fn edit_keeps_the_block_slack_of_the_original() {
+        let original = placed_instructions(&parse_function_body(FUNCTION));
+        let mut edited = original.clone();
+        edited.remove(2);
+        for placed in &mut edited {
+            placed.block_slack = 0;
+        }
+        keep_block_slack(&original, &mut edited);
+
+        let bytes = encode_function(&edited).unwrap();
+        assert_eq!(read_u16(&bytes, 2), 6);
+    }
+
+    #[test]
+    fn zero_block_length_is_left_alone() {
+        let mut function = FUNCTION.to_vec();
+        function[2] = 0;
+        let mut placed = placed_instructions(&parse_function_body(&function));
+        placed.remove(2);
+
+        let bytes = encode_function(&placed).unwrap();
+        assert_eq!(read_u16(&bytes, 2), 0);
+    }
+}
@@ -0,0 +1,995 @@
+use bio2_scd::analysis::door_graph::DoorGraph;
+use bio2_scd::analysis::enemy_table::collect_enemies;
+use bio2_scd::analysis::item_table::collect_items;
+use bio2_scd::analysis::lint::{lint_room, LintConfig, Severity, RULES};
+use bio2_scd::analysis::reachability::Reachability;
+use bio2_scd::analysis::room_state::{diff_tables, RoomState};
+use bio2_scd::analysis::script_diff::{LineChange, RoomDiff};
+use bio2_scd::analysis::table::{rows_to_csv, rows_to_json, PlacementRow};
+use bio2_scd::analysis::validate::ValidationReport;
+use bio2_scd::analysis::xref::{XrefIndex, XrefTarget};
+use bio2_scd::file_handler::{FileHandler, LoadedRoom, RdtFileData};
+use bio2_scd::fileio::name_tables::NameTables;
+use bio2_scd::fileio::rdt::RDTHeader;
+use bio2_scd::fileio::room_layout::ScriptKind;
+use bio2_scd::fileio::symbols::{parse_number, SymbolDictionary};
+use bio2_scd::fileio::utils::{hexdump, read_file};
+use bio2_scd::game_data::GameData;
+use bio2_scd::merge::RoomMerge;
+use bio2_scd::mod_manifest::{ModManifest, ModdedGame};
+use bio2_scd::patch::{apply_patch_set, create_patch_set, PatchFormat};
+use bio2_scd::schema::RoomDocument;
+use bio2_scd::text_tree::{pack_game, unpack_game, Charset};
+use bio2_scd::vm::machine::Vm;
+use bio2_scd::vm::state::GameState;
+use crate::map_export::{render_png, render_svg, MapExportOptions};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "Usage: bio2scd <command> [options]
+
+Commands:
+  info <file.rdt> [--format text|json]
+      Print the RDT header counts and the offset, size and name of every
+      section in the file.
+  disasm <file.rdt> [--script init|main|both] [--symbols dict.json]
+         [--format text|json] [--output file]
+      Disassemble the init and/or main script. Text output matches the code
+      view of the GUI; JSON is a versioned room document with the header,
+      section map, every instruction with its offset, opcode, named
+      parameters, raw bytes and pseudocode, and the lint diagnostics.
+  asm <room.json> <base.rdt> --output out.rdt
+      Write the scripts of a JSON room document (see disasm) into the RDT it
+      was made from. Instructions are encoded from their parameter values;
+      functions that changed get their block lengths recomputed.
+  dump-all <game dir> --output-dir <dir> [--script init|main|both]
+           [--symbols dict.json] [--format text|json]
+      Disassemble every RDT in the directory into one file per room, keeping
+      the directory layout, e.g. to track script changes in git.
+  unpack <game dir> --output-dir <tree dir> [--symbols dict.json]
+         [--charset charset.json]
+      Turn every RDT into a directory with one pseudocode file per script
+      function, the other sections as binary files and a room.json manifest.
+      No charset is built in, so messages stay binary unless --charset gives
+      the characters of the font, e.g. {\"0x1b\": \"A\"}; they are then
+      written as text, with {xx} for bytes without a character.
+  pack <tree dir> --output-dir <game dir>
+      Rebuild the RDT files of an unpacked tree. Unchanged rooms are rebuilt
+      byte for byte.
+  diff <old.rdt> <new.rdt> [--script init|main|both] [--symbols dict.json]
+       [--format text|json] [--output file]
+      Compare the scripts of two rooms instruction by instruction. Functions
+      are matched by index and instructions by opcode and parameters, so code
+      that only moved is not reported. Lists inserted (+), removed (-) and
+      changed (~) instructions with the parameters that differ.
+  merge <base.rdt> <ours.rdt> <theirs.rdt> --output merged.rdt
+        [--resolve ours|theirs|both|base] [--symbols dict.json]
+      Merge the script edits of two mods of the same room against the
+      original. Runs of instructions changed by only one mod are taken from
+      it; runs both changed differently are conflicts. Conflicts are listed
+      and nothing is written (exit code 2) unless --resolve picks a side for
+      all of them. Block lengths, function tables and section offsets of the
+      merged file are recomputed.
+  make-patch <original> <modified> --output <patch.bps|patch.ips|dir>
+             [--format bps|ips]
+      Write a patch turning the original RDT into the modified one. With
+      two game directories, one patch per changed room is written below the
+      output directory with a patches.json manifest holding the CRC32 of
+      every original and patched file. Directories default to BPS.
+  apply-patch <patch.bps|patch.ips|dir> <original> --output <file|dir>
+      Apply a patch or patch directory. The original is checked against the
+      CRC32 in BPS patches and patch manifests before anything is written;
+      single IPS patches carry no checksum.
+  apply-mod <game dir> <mod.toml>... --output-dir <dir> [--symbols dict.json]
+      Apply the script edits of one or more TOML mod manifests, in order, to
+      an unmodified game and write the changed rooms below the output
+      directory. Each edit names a room, script and function, an anchor
+      instruction (by name and parameters, whole line, or a label set by an
+      earlier edit) and an action: replace, insert_before, insert_after,
+      delete or set. Nothing is written if any anchor does not match exactly
+      one instruction.
+  hexdump <file.rdt> [--section N] [--offset X] [--length N]
+      Print a hex dump of the file, of section N of the offset table (see
+      info), or of --length bytes from --offset. Numbers may be hex (0x...).
+  map <file.rdt> [--svg out.svg] [--png out.png] [--width pixels]
+      Render a top-down map of the room. Writes <ROOM>.svg when no output is given.
+  doors <game dir> [--names tables.json] [--format dot|json] [--output file]
+      Build the door graph of every RDT in the directory, labelling rooms
+      with the names of the \"room\" name table. Doors leading to missing
+      rooms are listed on stderr.
+  items <game dir> [--format csv|json] [--output file]
+      List every ItemAotSet with its position and enclosing conditions.
+  enemies <game dir> [--format csv|json] [--output file]
+      List every SceEmSet with its enemy, position and enclosing conditions.
+      Slots that can be filled twice are listed on stderr.
+  xref <game dir> [--target flag:A:B|var:N|member:N] [--symbols dict.json]
+       [--names tables.json] [--format text|csv|json] [--output file]
+      List every read (CheckBit, Compare, MemberCmp) and write (SetBit, Save,
+      Calc, Copy) of flags and variables, or only those of one target. With a
+      symbol dictionary the target may be a name, e.g. flag:MET_ADA.
+  run <file.rdt> [--set flag:A:B=1,var:N=7] [--symbols dict.json]
+      [--names tables.json] [--frames N] [--format text|json] [--output file]
+      Execute the init and main scripts of a room from the given flags and
+      variables and log the AOTs, entities, camera changes, sounds and state
+      changes they produce, followed by the final state.
+  state <file.rdt> [--set flag:A:B=1,var:N=7] [--compare flag:A:B=0,...]
+        [--symbols dict.json] [--names tables.json] [--format text|json]
+        [--output file]
+      Run the init script from the given flags and list the AOT slots, entity
+      slots, doors, items, lights and camera it sets up. With --compare the
+      room is also evaluated from the second flag state and only the
+      differences are listed, e.g. first visit vs. after picking up an item.
+  reach <file.rdt> [--show all|never] [--symbols dict.json] [--names tables.json]
+        [--format text|json] [--output file]
+      Print the simplified condition under which each instruction runs, built
+      from its enclosing CheckBit/Compare blocks, switch cases and the single
+      GoSub or EvtExec calling its function. --show never lists only the
+      instructions whose conditions contradict each other.
+  lint <file.rdt|game dir> [--config lint.json] [--fail-on error|warning|info]
+       [--format text|json] [--output file]
+      Check the scripts for nesting and block length errors, unknown opcodes,
+      duplicate AOTs, out of range cameras and models, misplaced Breaks and
+      code after EvtEnd. The configuration lists rules to turn off and changed
+      severities, e.g. {\"disabled\": [\"block-length\"], \"severities\":
+      {\"duplicate-aot\": \"Error\"}}. Exits with 2 when a problem is at least
+      as severe as --fail-on (default error).
+  lint-rules
+      List the lint rules with their default severity.
+  validate <game dir> [--symbols dict.json] [--output report.json]
+      Check a mod's RDT directory as a whole and write a JSON report of
+      item_picked_index values shared by different items, doors leading
+      outside the target room's collision or to a missing stage, room or
+      camera, flags that are checked but never set, and file names whose
+      stage or player disagrees with the room's pl0/pl1 scenario. Unset flags
+      an item pickup or enemy kill may set are only listed as info. Exits
+      with 2 when any other problem was found.
+  help
+      Show this message";
+
+const DEFAULT_MAP_WIDTH: u32 = 1024;
+const DEFAULT_RUN_FRAMES: u32 = 300;
+
+/// Positional arguments and `--name value` options of a command
+struct CommandArgs {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl CommandArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.strip_prefix("--") {
+                Some(name) => {
+                    let value = iter.next().ok_or(format!("Missing value for --{}", name))?;
+                    options.insert(name.to_string(), value.clone());
+                }
+                None => positional.push(arg.clone()),
+            }
+        }
+        Ok(Self { positional, options })
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(|s| s.as_str())
+    }
+
+    fn input_file(&self) -> Result<&Path, String> {
+        self.positional
+            .first()
+            .map(Path::new)
+            .ok_or_else(|| format!("Missing input file\n\n{}", USAGE))
+    }
+}
+
+/// Exit code of `lint` and `validate` when they found problems
+pub const EXIT_CHECK_FAILED: i32 = 2;
+
+/// Runs a bio2scd command with the given arguments (without the program name)
+///
+/// Returns the exit code of a successful run, which is only non-zero for failed checks
+pub fn run(args: &[String]) -> Result<i32, String> {
+    let Some(command) = args.first() else {
+        return Err(USAGE.to_string());
+    };
+    let command_args = CommandArgs::parse(&args[1..])?;
+
+    match command.as_str() {
+        "info" => run_info(&command_args),
+        "disasm" => run_disasm(&command_args),
+        "asm" => run_asm(&command_args),
+        "dump-all" => run_dump_all(&command_args),
+        "hexdump" => run_hexdump(&command_args),
+        "unpack" => run_unpack(&command_args),
+        "pack" => run_pack(&command_args),
+        "diff" => run_diff(&command_args),
+        "merge" => return run_merge(&command_args),
+        "make-patch" => run_make_patch(&command_args),
+        "apply-patch" => run_apply_patch(&command_args),
+        "apply-mod" => run_apply_mod(&command_args),
+        "map" => run_map(&command_args),
+        "doors" => run_doors(&command_args),
+        "items" => run_items(&command_args),
+        "enemies" => run_enemies(&command_args),
+        "xref" => run_xref(&command_args),
+        "run" => run_scripts(&command_args),
+        "state" => run_room_state(&command_args),
+        "reach" => run_reachability(&command_args),
+        "lint" => return run_lint(&command_args),
+        "validate" => return run_validate(&command_args),
+        "lint-rules" => {
+            for rule in RULES {
+                println!("{:20} {:8} {}", rule.id, rule.severity, rule.description);
+            }
+            Ok(())
+        }
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(format!("Unknown command '{}'\n\n{}", command, USAGE)),
+    }
+    .map(|()| 0)
+}
+
+/// Prints the header counts and section map of an RDT file
+fn run_info(args: &CommandArgs) -> Result<(), String> {
+    let input = args.input_file()?;
+    let contents = read_file(input.display().to_string())?;
+    let header = RDTHeader::from(&contents).map_err(|e| format!("Invalid RDT file format: {}", e))?;
+    let sections = header.sections(contents.len());
+
+    let output = match args.option("format").unwrap_or("text") {
+        "text" => {
+            let mut text = format!("{}: {} bytes\n", file_stem(input), contents.len());
+            text.push_str(&format!(
+                "cameras {}, models {}, items {}, doors {}, rooms {}, sprites {} (max {} per camera), reverb {}\n\n",
+                header.num_cameras,
+                header.num_models,
+                header.num_items,
+                header.num_doors,
+                header.num_rooms,
+                header.num_sprites,
+                header.sprite_max,
+                header.num_reverb
+            ));
+            text.push_str(&format!("{:>5}  {:>8}  {:>8}  {}\n", "index", "offset", "size", "section"));
+            for section in &sections {
+                text.push_str(&format!(
+                    "{:>5}  {:>#8x}  {:>8}  {}\n",
+                    section.index, section.offset, section.size, section.name
+                ));
+            }
+            text
+        }
+        "json" => serde_json::to_string_pretty(&serde_json::json!({
+            "file_size": contents.len(),
+            "header": header,
+            "sections": sections,
+        }))
+        .map_err(|e| format!("JSON error: {}", e))?,
+        other => return Err(format!("Unknown format '{}', expected text or json", other)),
+    };
+    write_output(args.option("output"), &output)
+}
+
+/// Disassembles the scripts of one RDT file
+fn run_disasm(args: &CommandArgs) -> Result<(), String> {
+    let input = args.input_file()?;
+    let mut file_data = FileHandler::load_rdt_file(input)?;
+    let names = load_names(args)?;
+    let scripts = script_selection(args)?;
+    let json = output_is_json(args)?;
+
+    file_data.refresh_scripts(&names);
+    let output = disassemble(&file_stem(input), &file_data, &scripts, &names, json)?;
+    write_output(args.option("output"), &output)
+}
+
+/// Writes the scripts of a JSON room document into its RDT file
+fn run_asm(args: &CommandArgs) -> Result<(), String> {
+    let [document_path, base] = two_inputs(args, "the room document and the RDT it was made from")?;
+    let output = args.option("output").ok_or("Missing --output")?;
+    let json = std::fs::read_to_string(document_path)
+        .map_err(|e| format!("Unable to read '{}': {}", document_path.display(), e))?;
+    let document = RoomDocument::from_json(&json)?;
+    if !document.room.eq_ignore_ascii_case(&file_stem(base)) {
+        eprintln!("warning: the document describes {}, not {}", document.room, file_stem(base));
+    }
+
+    let base_contents = read_file(base.display().to_string())?;
+    let (bytes, warnings) = document.write_scripts(&base_contents)?;
+    report_load_errors(&warnings);
+    std::fs::write(output, bytes).map_err(|e| format!("Unable to write '{}': {}", output, e))?;
+    println!("Wrote {}", output);
+    Ok(())
+}
+
+/// Disassembles every RDT of a directory into one file per room
+fn run_dump_all(args: &CommandArgs) -> Result<(), String> {
+    let input = args.input_file()?;
+    let output_dir = PathBuf::from(args.option("output-dir").ok_or("Missing --output-dir")?);
+    let names = load_names(args)?;
+    let scripts = script_selection(args)?;
+    let json = output_is_json(args)?;
+
+    let (mut rooms, errors) = FileHandler::load_rdt_directory(input)?;
+    report_load_errors(&errors);
+
+    for room in &mut rooms {
+        room.data.refresh_scripts(&names);
+        let output = disassemble(&room.name, &room.data, &scripts, &names, json)?;
+        // Keep the layout below the game directory so pl0 and pl1 rooms do not overwrite each other
+        let relative = room.path.strip_prefix(input).unwrap_or(&room.path);
+        let path = output_dir.join(relative).with_extension(if json { "json" } else { "txt" });
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Unable to create '{}': {}", parent.display(), e))?;
+        }
+        std::fs::write(&path, output).map_err(|e| format!("Unable to write '{}': {}", path.display(), e))?;
+    }
+    println!("Wrote {} files to {}", rooms.len(), output_dir.display());
+    Ok(())
+}
+
+/// Prints a hex dump of an RDT file, one of its sections or a byte range
+fn run_hexdump(args: &CommandArgs) -> Result<(), String> {
+    let input = args.input_file()?;
+    let contents = read_file(input.display().to_string())?;
+    let number = |name: &str| -> Result<Option<usize>, String> {
+        match args.option(name) {
+            Some(text) => match parse_number(text) {
+                Some(value) if value >= 0 => Ok(Some(value as usize)),
+                _ => Err(format!("Invalid value '{}' for --{}", text, name)),
+            },
+            None => Ok(None),
+        }
+    };
+
+    let (mut start, mut length) = (0, contents.len());
+    if let Some(index) = number("section")? {
+        let header = RDTHeader::from(&contents).map_err(|e| format!("Invalid RDT file format: {}", e))?;
+        let section = header
+            .sections(contents.len())
+            .into_iter()
+            .find(|section| section.index == index)
+            .ok_or(format!("Section {} is not present in {}", index, file_stem(input)))?;
+        (start, length) = (section.offset as usize, section.size as usize);
+    }
+    if let Some(offset) = number("offset")? {
+        start = offset;
+        length = contents.len().saturating_sub(offset);
+    }
+    if let Some(bytes) = number("length")? {
+        length = bytes;
+    }
+    if start > contents.len() {
+        return Err(format!("Offset {:#x} is past the end of the file ({:#x} bytes)", start, contents.len()));
+    }
+    let end = start.saturating_add(length).min(contents.len());
+    write_output(args.option("output"), &hexdump(&contents[start..end], start))
+}
+
+/// Unpacks a game directory into a tree of text and binary files
+fn run_unpack(args: &CommandArgs) -> Result<(), String> {
+    let input = args.input_file()?;
+    let output_dir = PathBuf::from(args.option("output-dir").ok_or("Missing --output-dir")?);
+    let names = load_names(args)?;
+    let charset = match args.option("charset") {
+        Some(path) => Charset::load_file(Path::new(path))?,
+        None => Charset::default(),
+    };
+
+    let report = unpack_game(input, &output_dir, &names, &charset)?;
+    report_load_errors(&report.warnings);
+    println!("Unpacked {} rooms to {}", report.rooms, output_dir.display());
+    Ok(())
+}
+
+/// Packs an unpacked tree into RDT files
+fn run_pack(args: &CommandArgs) -> Result<(), String> {
+    let input = args.input_file()?;
+    let output_dir = PathBuf::from(args.option("output-dir").ok_or("Missing --output-dir")?);
+
+    let report = pack_game(input, &output_dir)?;
+    report_load_errors(&report.warnings);
+    println!("Packed {} rooms to {}", report.rooms, output_dir.display());
+    Ok(())
+}
+
+/// Compares the scripts of two RDT files
+fn run_diff(args: &CommandArgs) -> Result<(), String> {
+    let [old_path, new_path] = two_inputs(args, "the old and the new file")?;
+    let old = FileHandler::load_rdt_file(old_path)?;
+    let new = FileHandler::load_rdt_file(new_path)?;
+    let names = load_names(args)?;
+    let scripts = script_selection(args)?;
+    let json = output_is_json(args)?;
+
+    let diff = RoomDiff::new(&old, &new, &names, &scripts);
+    if json {
+        let changed: Vec<_> = diff.changed().collect();
+        let output = serde_json::to_string_pretty(&changed).map_err(|e| format!("JSON error: {}", e))?;
+        return write_output(args.option("output"), &format!("{}\n", output));
+    }
+
+    let mut text = format!("--- {}\n+++ {}\n", old_path.display(), new_path.display());
+    for function in diff.changed() {
+        text.push_str(&format!("\n{} function {} ({})\n", function.script.name(), function.function, function.status));
+        for line in function.changes() {
+            let index = |index: Option<usize>| index.map(|i| i.to_string()).unwrap_or_default();
+            let (marker, code) = match line.change {
+                LineChange::Inserted => ('+', &line.new_code),
+                LineChange::Removed => ('-', &line.old_code),
+                _ => ('~', &line.new_code),
+            };
+            text.push_str(&format!(
+                "{:>5} {:>5} {} {}\n",
+                index(line.old),
+                index(line.new),
+                marker,
+                code.as_deref().unwrap_or_default()
+            ));
+            for param in &line.params {
+                text.push_str(&format!("{:>14}{}\n", "", param));
+            }
+        }
+    }
+    let (inserted, removed, changed) = diff.counts();
+    text.push_str(&format!(
+        "\n{} functions differ: {} inserted, {} removed, {} changed instructions\n",
+        diff.changed().count(),
+        inserted,
+        removed,
+        changed
+    ));
+    write_output(args.option("output"), &text)
+}
+
+/// Merges the scripts of two mods of a room and writes the result
+fn run_merge(args: &CommandArgs) -> Result<i32, String> {
+    let [base_path, our_path, their_path] = match &args.positional[..] {
+        [base, ours, theirs] => [base, ours, theirs].map(Path::new),
+        _ => return Err(format!("Expected the original room and two mods\n\n{}", USAGE)),
+    };
+    let output = args.option("output").ok_or("Missing --output")?;
+    let names = load_names(args)?;
+    let base_contents = read_file(base_path.display().to_string())?;
+
+    let mut merge = RoomMerge::new(
+        &FileHandler::load_rdt_file(base_path)?,
+        &FileHandler::load_rdt_file(our_path)?,
+        &FileHandler::load_rdt_file(their_path)?,
+    );
+    if let Some(resolution) = args.option("resolve") {
+        merge.resolve_all(resolution.parse()?);
+    }
+
+    let mut report = String::new();
+    for conflict in merge.conflicts.iter().filter(|conflict| conflict.resolution.is_none()) {
+        report.push_str(&format!("{}\n", conflict));
+        for (side, instructions) in [
+            (base_path, &conflict.base),
+            (our_path, &conflict.ours),
+            (their_path, &conflict.theirs),
+        ] {
+            report.push_str(&format!("  {}:\n", side.display()));
+            for placed in instructions {
+                report.push_str(&format!("      {}\n", names.code_line(&placed.instruction)));
+            }
+        }
+        report.push('\n');
+    }
+    print!("{}", report);
+    if merge.unresolved() > 0 {
+        eprintln!(
+            "{} conflicts, nothing written; use --resolve to pick a side for all of them",
+            merge.unresolved()
+        );
+        return Ok(EXIT_CHECK_FAILED);
+    }
+
+    let (bytes, warnings) = merge.write(&base_contents)?;
+    report_load_errors(&warnings);
+    std::fs::write(output, bytes).map_err(|e| format!("Unable to write '{}': {}", output, e))?;
+    println!(
+        "Merged {} functions automatically, resolved {} conflicts, wrote {}",
+        merge.merged_automatically(),
+        merge.conflicts.len(),
+        output
+    );
+    Ok(0)
+}
+
+/// Writes a patch between two RDT files or two game directories
+fn run_make_patch(args: &CommandArgs) -> Result<(), String> {
+    let [original, modified] = two_inputs(args, "the original and the modified file or directory")?;
+    let output = Path::new(args.option("output").ok_or("Missing --output")?);
+    let format = match args.option("format") {
+        Some(format) => Some(format.parse::<PatchFormat>()?),
+        None => PatchFormat::from_path(output),
+    };
+
+    if original.is_dir() {
+        let report = create_patch_set(original, modified, output, format.unwrap_or(PatchFormat::Bps))?;
+        report_load_errors(&report.warnings);
+        println!(
+            "Wrote {} patches to {}, {} rooms unchanged",
+            report.patched,
+            output.display(),
+            report.unchanged
+        );
+        return Ok(());
+    }
+    let format = format.ok_or("Use --format or an .ips or .bps output file to pick the patch format")?;
+    let source = read_file(original.display().to_string())?;
+    let target = read_file(modified.display().to_string())?;
+    let patch = format.create(&source, &target)?;
+    std::fs::write(output, &patch).map_err(|e| format!("Unable to write '{}': {}", output.display(), e))?;
+    println!("Wrote {} ({} bytes)", output.display(), patch.len());
+    Ok(())
+}
+
+/// Applies a patch file or patch directory
+fn run_apply_patch(args: &CommandArgs) -> Result<(), String> {
+    let [patch_path, original] = two_inputs(args, "the patch and the original file or directory")?;
+    let output = Path::new(args.option("output").ok_or("Missing --output")?);
+
+    if patch_path.is_dir() {
+        let report = apply_patch_set(patch_path, original, output)?;
+        println!("Patched {} rooms into {}", report.patched, output.display());
+        return Ok(());
+    }
+    let format = PatchFormat::from_path(patch_path).ok_or("Expected an .ips or .bps patch file")?;
+    if format == PatchFormat::Ips {
+        eprintln!("warning: IPS patches carry no checksum, the original file is not verified");
+    }
+    let source = read_file(original.display().to_string())?;
+    let patch = read_file(patch_path.display().to_string())?;
+    let target = format.apply(&source, &patch)?;
+    std::fs::write(output, target).map_err(|e| format!("Unable to write '{}': {}", output.display(), e))?;
+    println!("Wrote {}", output.display());
+    Ok(())
+}
+
+/// Applies mod manifests to a game directory and writes the changed rooms
+fn run_apply_mod(args: &CommandArgs) -> Result<(), String> {
+    let (game_dir, manifests) = match &args.positional[..] {
+        [game_dir, manifests @ ..] if !manifests.is_empty() => (Path::new(game_dir), manifests),
+        _ => return Err(format!("Expected the game directory and at least one mod manifest\n\n{}", USAGE)),
+    };
+    let output_dir = PathBuf::from(args.option("output-dir").ok_or("Missing --output-dir")?);
+    let names = load_names(args)?;
+
+    let mut game = ModdedGame::new(game_dir)?;
+    for path in manifests {
+        let manifest = ModManifest::load_file(Path::new(path))?;
+        game.apply(&manifest, &names).map_err(|e| format!("{}: {}", path, e))?;
+        match manifest.name.as_str() {
+            "" => println!("Applied {} edits of {}", manifest.edits.len(), path),
+            name => println!("Applied {} edits of {} ({})", manifest.edits.len(), name, path),
+        }
+    }
+    for room in game.write(&output_dir)? {
+        let warnings: Vec<String> = room.warnings.iter().map(|w| format!("{}: {}", room.file, w)).collect();
+        report_load_errors(&warnings);
+    }
+    println!("Wrote {} rooms to {}", game.changed_rooms(), output_dir.display());
+    Ok(())
+}
+
+/// Exports the room map as SVG and/or PNG
+fn run_map(args: &CommandArgs) -> Result<(), String> {
+    let input = args.input_file()?;
+    let file_data = FileHandler::load_rdt_file(input)?;
+    let room_name = file_stem(input);
+
+    let width = match args.option("width") {
+        Some(w) => w.parse().map_err(|_| format!("Invalid width: {}", w))?,
+        None => DEFAULT_MAP_WIDTH,
+    };
+    let options = MapExportOptions {
+        width,
+        title: room_name.clone(),
+    };
+
+    let png_path = args.option("png");
+    let svg_path = match (args.option("svg"), png_path) {
+        (Some(path), _) => Some(path.to_string()),
+        (None, None) => Some(format!("{}.svg", room_name)),
+        (None, Some(_)) => None,
+    };
+
+    if let Some(path) = svg_path {
+        std::fs::write(&path, render_svg(&file_data.room_layout, &options))
+            .map_err(|e| format!("Unable to write '{}': {}", path, e))?;
+        println!("Wrote {}", path);
+    }
+    if let Some(path) = png_path {
+        std::fs::write(path, render_png(&file_data.room_layout, &options)?)
+            .map_err(|e| format!("Unable to write '{}': {}", path, e))?;
+        println!("Wrote {}", path);
+    }
+    Ok(())
+}
+
+/// Exports the game-wide door graph
+fn run_doors(args: &CommandArgs) -> Result<(), String> {
+    let (rooms, errors) = FileHandler::load_rdt_directory(args.input_file()?)?;
+    report_load_errors(&errors);
+
+    let names = load_names(args)?;
+    let graph = DoorGraph::build(&rooms);
+    for edge in graph.missing_targets() {
+        eprintln!("warning: {} door {} leads to missing room {}", edge.from, edge.aot, edge.to);
+    }
+
+    let output = match args.option("format").unwrap_or("dot") {
+        "dot" => graph.to_dot(&names),
+        "json" => graph.to_json()?,
+        other => return Err(format!("Unknown format '{}', expected dot or json", other)),
+    };
+    write_output(args.option("output"), &output)
+}
+
+/// Exports the game-wide item placement table
+fn run_items(args: &CommandArgs) -> Result<(), String> {
+    let (rooms, errors) = FileHandler::load_rdt_directory(args.input_file()?)?;
+    report_load_errors(&errors);
+
+    write_table(args, &collect_items(&rooms))
+}
+
+/// Exports the game-wide enemy placement table
+fn run_enemies(args: &CommandArgs) -> Result<(), String> {
+    let (rooms, errors) = FileHandler::load_rdt_directory(args.input_file()?)?;
+    report_load_errors(&errors);
+
+    let enemies = collect_enemies(&rooms);
+    for enemy in enemies.iter().filter(|e| e.duplicate_slot) {
+        eprintln!(
+            "warning: {} slot {} is set more than once (offset 0x{:x})",
+            enemy.room, enemy.em_no, enemy.offset
+        );
+    }
+    write_table(args, &enemies)
+}
+
+/// Lists the flag and variable references of a game directory
+fn run_xref(args: &CommandArgs) -> Result<(), String> {
+    let (rooms, errors) = FileHandler::load_rdt_directory(args.input_file()?)?;
+    report_load_errors(&errors);
+
+    let names = load_names(args)?;
+    let index = XrefIndex::build(&rooms, &names);
+    let references = match args.option("target") {
+        Some(target) => index.find(XrefTarget::parse(target, &names.symbols)?),
+        None => index.references,
+    };
+
+    if args.option("format").unwrap_or("text") != "text" {
+        return write_table(args, &references);
+    }
+    let text: String = references
+        .iter()
+        .map(|r| {
+            format!(
+                "{} {} function {} 0x{:x} {} {}: {}\n",
+                names.room_label(&r.room),
+                r.source.script.name(),
+                r.source.function,
+                r.offset,
+                r.access,
+                r.target.label(&names.symbols),
+                r.code
+            )
+        })
+        .collect();
+    write_output(args.option("output"), &text)
+}
+
+/// Executes the scripts of a room and prints the world-state log
+fn run_scripts(args: &CommandArgs) -> Result<(), String> {
+    let file_data = FileHandler::load_rdt_file(args.input_file()?)?;
+
+    let names = load_names(args)?;
+    let mut state = GameState::default();
+    if let Some(assignments) = args.option("set") {
+        state.apply_assignments(assignments, &names.symbols)?;
+    }
+    let frames = match args.option("frames") {
+        Some(f) => f.parse().map_err(|_| format!("Invalid frame count: {}", f))?,
+        None => DEFAULT_RUN_FRAMES,
+    };
+
+    let mut vm = Vm::new(&file_data, state, names);
+    vm.run(frames);
+
+    let output = match args.option("format").unwrap_or("text") {
+        "text" => {
+            let mut text: String = vm.log.iter().map(|event| format!("{}\n", event)).collect();
+            let status = if vm.is_finished() { "all tasks ended" } else { "tasks still running" };
+            text.push_str(&format!("\nAfter {} frames, {}:\n", vm.frame, status));
+            text.push_str(&serde_json::to_string_pretty(&vm.state).map_err(|e| format!("JSON error: {}", e))?);
+            text.push('\n');
+            text
+        }
+        "json" => serde_json::to_string_pretty(&serde_json::json!({
+            "frames": vm.frame,
+            "finished": vm.is_finished(),
+            "log": vm.log,
+            "state": vm.state,
+        }))
+        .map_err(|e| format!("JSON error: {}", e))?,
+        other => return Err(format!("Unknown format '{}', expected text or json", other)),
+    };
+    write_output(args.option("output"), &output)
+}
+
+/// Lists the room state after the init script, or the differences between two flag states
+fn run_room_state(args: &CommandArgs) -> Result<(), String> {
+    let input = args.input_file()?;
+    let file_data = FileHandler::load_rdt_file(input)?;
+
+    let names = load_names(args)?;
+    let evaluate = |assignments: Option<&str>| -> Result<RoomState, String> {
+        let mut state = GameState::default();
+        state.apply_assignments(assignments.unwrap_or_default(), &names.symbols)?;
+        let room = RoomState::evaluate(&file_data, state, &names);
+        if room.incomplete {
+            eprintln!("warning: {} init script did not end, showing its state so far", file_stem(input));
+        }
+        Ok(room)
+    };
+    let room = evaluate(args.option("set"))?;
+    let json = match args.option("format").unwrap_or("text") {
+        "text" => false,
+        "json" => true,
+        other => return Err(format!("Unknown format '{}', expected text or json", other)),
+    };
+
+    let output = match args.option("compare") {
+        Some(compare) => {
+            let diffs = diff_tables(&room.tables(&names), &evaluate(Some(compare))?.tables(&names));
+            if json {
+                serde_json::to_string_pretty(&diffs).map_err(|e| format!("JSON error: {}", e))?
+            } else if diffs.is_empty() {
+                "No differences\n".to_string()
+            } else {
+                diffs.iter().map(|diff| format!("{}\n", diff)).collect()
+            }
+        }
+        None if json => serde_json::to_string_pretty(&room).map_err(|e| format!("JSON error: {}", e))?,
+        None => room.tables(&names).iter().map(|table| format!("{}\n", table)).collect(),
+    };
+    write_output(args.option("output"), &output)
+}
+
+/// Lists the path condition of every instruction, or only of those that can never run
+fn run_reachability(args: &CommandArgs) -> Result<(), String> {
+    let file_data = FileHandler::load_rdt_file(args.input_file()?)?;
+    let names = load_names(args)?;
+    let reachability = Reachability::new(&file_data.init_functions, &file_data.main_functions);
+
+    let never_only = match args.option("show").unwrap_or("all") {
+        "all" => false,
+        "never" => true,
+        other => return Err(format!("Unknown value '{}' for --show, expected all or never", other)),
+    };
+    let paths: Vec<_> = reachability
+        .iter()
+        .filter(|(_, path)| !never_only || !path.is_reachable())
+        .map(|(location, path)| (location, names.code_line(file_data.instruction(location)), path))
+        .collect();
+
+    let output = match args.option("format").unwrap_or("text") {
+        "text" => paths
+            .iter()
+            .map(|(location, code, path)| {
+                let (script, function, instruction) = (location.script.name(), location.function, location.instruction);
+                let via = path
+                    .caller
+                    .map(|c| format!(" (via {} {}:{})", c.script.name(), c.function, c.instruction))
+                    .unwrap_or_default();
+                format!("{} {}:{} {}\n    when {}{}\n", script, function, instruction, code, path, via)
+            })
+            .collect(),
+        "json" => {
+            let entries: Vec<serde_json::Value> = paths
+                .iter()
+                .map(|(location, code, path)| {
+                    serde_json::json!({
+                        "location": location,
+                        "code": code,
+                        "condition": path.to_string(),
+                        "path": path,
+                    })
+                })
+                .collect();
+            serde_json::to_string_pretty(&entries).map_err(|e| format!("JSON error: {}", e))?
+        }
+        other => return Err(format!("Unknown format '{}', expected text or json", other)),
+    };
+    write_output(args.option("output"), &output)
+}
+
+/// Lints one RDT file or every RDT of a directory
+fn run_lint(args: &CommandArgs) -> Result<i32, String> {
+    let input = args.input_file()?;
+    let config = match args.option("config") {
+        Some(path) => LintConfig::load_file(Path::new(path))?,
+        None => LintConfig::default(),
+    };
+    let fail_on = match args.option("fail-on").unwrap_or("error") {
+        "error" => Severity::Error,
+        "warning" => Severity::Warning,
+        "info" => Severity::Info,
+        other => return Err(format!("Unknown severity '{}', expected error, warning or info", other)),
+    };
+
+    let rooms = if input.is_dir() {
+        let (rooms, errors) = FileHandler::load_rdt_directory(input)?;
+        for error in errors {
+            eprintln!("{}", error);
+        }
+        rooms
+    } else {
+        let data = FileHandler::load_rdt_file(input)?;
+        vec![LoadedRoom {
+            path: input.to_path_buf(),
+            name: file_stem(input).to_uppercase(),
+            data,
+        }]
+    };
+    let results: Vec<_> = rooms.iter().map(|room| (room.name.as_str(), lint_room(&room.data, &config))).collect();
+
+    let output = match args.option("format").unwrap_or("text") {
+        "text" => {
+            let mut text: String = results
+                .iter()
+                .flat_map(|(room, problems)| problems.iter().map(move |problem| format!("{} {}\n", room, problem)))
+                .collect();
+            let count = |severity| results.iter().flat_map(|(_, p)| p).filter(|p| p.severity == severity).count();
+            text.push_str(&format!(
+                "{} rooms checked: {} errors, {} warnings, {} infos\n",
+                results.len(),
+                count(Severity::Error),
+                count(Severity::Warning),
+                count(Severity::Info)
+            ));
+            text
+        }
+        "json" => {
+            let rooms: Vec<serde_json::Value> = results
+                .iter()
+                .map(|(room, problems)| serde_json::json!({ "room": room, "problems": problems }))
+                .collect();
+            serde_json::to_string_pretty(&rooms).map_err(|e| format!("JSON error: {}", e))?
+        }
+        other => return Err(format!("Unknown format '{}', expected text or json", other)),
+    };
+    write_output(args.option("output"), &output)?;
+
+    let failed = results.iter().flat_map(|(_, p)| p).any(|problem| problem.severity >= fail_on);
+    Ok(if failed { EXIT_CHECK_FAILED } else { 0 })
+}
+
+/// Writes the game-wide consistency report as JSON
+fn run_validate(args: &CommandArgs) -> Result<i32, String> {
+    let game = GameData::load(args.input_file()?)?;
+    let symbols = match args.option("symbols") {
+        Some(path) => SymbolDictionary::load_file(Path::new(path))?,
+        None => SymbolDictionary::default(),
+    };
+    let report = ValidationReport::build(&game, &symbols);
+    write_output(args.option("output"), &format!("{}\n", report.to_json()?))?;
+    eprintln!("{}", report.summary());
+    Ok(if report.issue_count() > 0 { EXIT_CHECK_FAILED } else { 0 })
+}
+
+/// Formats the selected scripts of a room as pseudocode or JSON
+fn disassemble(
+    room: &str,
+    file_data: &RdtFileData,
+    scripts: &[ScriptKind],
+    names: &NameTables,
+    json: bool,
+) -> Result<String, String> {
+    for &script in scripts {
+        if file_data.functions(script).is_empty() {
+            eprintln!("warning: {} {} script has no readable function table", room, script.name());
+        }
+    }
+
+    if json {
+        return RoomDocument::new(room, file_data, names, scripts).to_json();
+    }
+
+    let mut text = String::new();
+    for &script in scripts {
+        let lines = match script {
+            ScriptKind::Init => &file_data.init_script,
+            ScriptKind::Main => &file_data.main_script,
+        };
+        text.push_str(&format!("; {} {} script\n", room, script.name()));
+        for line in lines {
+            text.push_str(line);
+            text.push('\n');
+        }
+    }
+    Ok(text)
+}
+
+/// The two positional arguments of a command
+fn two_inputs<'a>(args: &'a CommandArgs, expected: &str) -> Result<[&'a Path; 2], String> {
+    match &args.positional[..] {
+        [first, second] => Ok([Path::new(first), Path::new(second)]),
+        _ => Err(format!("Expected {}\n\n{}", expected, USAGE)),
+    }
+}
+
+/// The scripts chosen with --script, both by default
+fn script_selection(args: &CommandArgs) -> Result<Vec<ScriptKind>, String> {
+    match args.option("script").unwrap_or("both") {
+        "init" => Ok(vec![ScriptKind::Init]),
+        "main" => Ok(vec![ScriptKind::Main]),
+        "both" => Ok(vec![ScriptKind::Init, ScriptKind::Main]),
+        other => Err(format!("Unknown script '{}', expected init, main or both", other)),
+    }
+}
+
+fn output_is_json(args: &CommandArgs) -> Result<bool, String> {
+    match args.option("format").unwrap_or("text") {
+        "text" => Ok(false),
+        "json" => Ok(true),
+        other => Err(format!("Unknown format '{}', expected text or json", other)),
+    }
+}
+
+/// The name tables given by --names, or the built-in ones, with the symbol dictionary given by
+/// --symbols
+fn load_names(args: &CommandArgs) -> Result<NameTables, String> {
+    let mut names = match args.option("names") {
+        Some(path) => NameTables::load_file(Path::new(path))?,
+        None => NameTables::builtin(),
+    };
+    if let Some(path) = args.option("symbols") {
+        names.symbols = SymbolDictionary::load_file(Path::new(path))?;
+    }
+    Ok(names)
+}
+
+/// Writes a placement table as CSV or JSON
+fn write_table<T: PlacementRow>(args: &CommandArgs, rows: &[T]) -> Result<(), String> {
+    let output = match args.option("format").unwrap_or("csv") {
+        "csv" => rows_to_csv(rows),
+        "json" => rows_to_json(rows)?,
+        other => return Err(format!("Unknown format '{}', expected csv or json", other)),
+    };
+    write_output(args.option("output"), &output)
+}
+
+/// Writes to the given file, or stdout when no file is given
+fn write_output(path: Option<&str>, contents: &str) -> Result<(), String> {
+    match path {
+        Some(path) => std::fs::write(path, contents).map_err(|e| format!("Unable to write '{}': {}", path, e)),
+        None => {
+            print!("{}", contents);
+            Ok(())
+        }
+    }
+}
+
+fn report_load_errors(errors: &[String]) {
+    for error in errors {
+        eprintln!("warning: {}", error);
+    }
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("room")
+        .to_string()
+}
@@ -0,0 +1,17 @@
+#![warn(clippy::all, rust_2018_idioms)]
+
+mod cli;
+mod map_export;
+
+// Command line companion to the script viewer
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match cli::run(&args) {
+        Ok(0) => {}
+        Ok(code) => std::process::exit(code),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
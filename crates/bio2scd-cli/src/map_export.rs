@@ -0,0 +1,522 @@
+use bio2_scd::fileio::room_layout::{aot_type_color, aot_type_name, AotShape, RoomLayout, FULL_TURN};
+use std::fmt::Write;
+
+// Colours shared by the SVG and PNG output
+const BACKGROUND_COLOR: (u8, u8, u8) = (20, 20, 20);
+const COLLISION_COLOR: (u8, u8, u8) = (140, 140, 150);
+const CAMERA_SWITCH_COLOR: (u8, u8, u8) = (110, 110, 140);
+const DOOR_COLOR: (u8, u8, u8) = (230, 160, 60);
+const ITEM_COLOR: (u8, u8, u8) = (80, 220, 120);
+const ENEMY_COLOR: (u8, u8, u8) = (255, 80, 80);
+const TEXT_COLOR: (u8, u8, u8) = (230, 230, 230);
+const AOT_FILL_ALPHA: u8 = 60;
+
+// Layout of the exported image
+const MAP_MARGIN: f32 = 20.0;
+const LEGEND_WIDTH: f32 = 180.0;
+const LEGEND_ROW_HEIGHT: f32 = 20.0;
+const FONT_SIZE: f32 = 13.0;
+const MARKER_RADIUS: f32 = 5.0;
+const ARROW_LENGTH: f32 = 24.0;
+const DASH_PATTERN: [f32; 2] = [6.0, 4.0];
+/// Largest width or height of the map area, so long thin rooms do not make huge images
+const MAX_MAP_SIZE: f32 = 4096.0;
+
+/// Options for exporting the room map
+pub struct MapExportOptions {
+    /// Width of the map area in pixels, not counting the legend
+    pub width: u32,
+    pub title: String,
+}
+
+/// A drawing command in image coordinates
+enum MapPrimitive {
+    Polygon {
+        points: Vec<(f32, f32)>,
+        fill: Option<(u8, u8, u8, u8)>,
+        stroke: (u8, u8, u8),
+        stroke_width: f32,
+        dashed: bool,
+    },
+    Circle {
+        center: (f32, f32),
+        radius: f32,
+        fill: (u8, u8, u8),
+    },
+    Line {
+        from: (f32, f32),
+        to: (f32, f32),
+        color: (u8, u8, u8),
+        width: f32,
+    },
+    Text {
+        pos: (f32, f32),
+        text: String,
+        color: (u8, u8, u8),
+    },
+}
+
+/// The full list of primitives and the image size
+struct MapScene {
+    width: u32,
+    height: u32,
+    primitives: Vec<MapPrimitive>,
+}
+
+/// Render the room map as an SVG document
+pub fn render_svg(layout: &RoomLayout, options: &MapExportOptions) -> String {
+    let scene = build_scene(layout, options);
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+        scene.width, scene.height
+    );
+    let _ = writeln!(
+        svg,
+        r#"<rect width="100%" height="100%" fill="{}"/>"#,
+        svg_color(BACKGROUND_COLOR)
+    );
+
+    for primitive in &scene.primitives {
+        match primitive {
+            MapPrimitive::Polygon { points, fill, stroke, stroke_width, dashed } => {
+                let points: Vec<String> = points.iter().map(|(x, y)| format!("{:.1},{:.1}", x, y)).collect();
+                let fill = match fill {
+                    Some((r, g, b, a)) => format!(r#"fill="{}" fill-opacity="{:.2}""#, svg_color((*r, *g, *b)), *a as f32 / 255.0),
+                    None => r#"fill="none""#.to_string(),
+                };
+                let dash = if *dashed {
+                    format!(r#" stroke-dasharray="{},{}""#, DASH_PATTERN[0], DASH_PATTERN[1])
+                } else {
+                    String::new()
+                };
+                let _ = writeln!(
+                    svg,
+                    r#"<polygon points="{}" {} stroke="{}" stroke-width="{}"{}/>"#,
+                    points.join(" "),
+                    fill,
+                    svg_color(*stroke),
+                    stroke_width,
+                    dash
+                );
+            }
+            MapPrimitive::Circle { center, radius, fill } => {
+                let _ = writeln!(
+                    svg,
+                    r#"<circle cx="{:.1}" cy="{:.1}" r="{}" fill="{}"/>"#,
+                    center.0, center.1, radius, svg_color(*fill)
+                );
+            }
+            MapPrimitive::Line { from, to, color, width } => {
+                let _ = writeln!(
+                    svg,
+                    r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}" stroke-width="{}"/>"#,
+                    from.0, from.1, to.0, to.1, svg_color(*color), width
+                );
+            }
+            MapPrimitive::Text { pos, text, color } => {
+                let _ = writeln!(
+                    svg,
+                    r#"<text x="{:.1}" y="{:.1}" fill="{}" font-family="sans-serif" font-size="{}">{}</text>"#,
+                    pos.0, pos.1, svg_color(*color), FONT_SIZE, escape_xml(text)
+                );
+            }
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Rasterise the room map on the CPU and encode it as a PNG
+pub fn render_png(layout: &RoomLayout, options: &MapExportOptions) -> Result<Vec<u8>, String> {
+    use tiny_skia::{FillRule, Paint, PathBuilder, Pixmap, Stroke, StrokeDash, Transform};
+
+    let scene = build_scene(layout, options);
+    let mut pixmap = Pixmap::new(scene.width, scene.height).ok_or("Invalid image size")?;
+    let (r, g, b) = BACKGROUND_COLOR;
+    pixmap.fill(tiny_skia::Color::from_rgba8(r, g, b, 255));
+
+    let font_data = epaint::text::FontDefinitions::default()
+        .font_data
+        .remove("Ubuntu-Light")
+        .ok_or("Default font not available")?;
+    let font = ab_glyph::FontRef::try_from_slice(&font_data.font).map_err(|e| format!("Invalid font: {}", e))?;
+
+    let paint_for = |(r, g, b): (u8, u8, u8), a: u8| {
+        let mut paint = Paint::default();
+        paint.set_color_rgba8(r, g, b, a);
+        paint.anti_alias = true;
+        paint
+    };
+
+    for primitive in &scene.primitives {
+        match primitive {
+            MapPrimitive::Polygon { points, fill, stroke, stroke_width, dashed } => {
+                let mut builder = PathBuilder::new();
+                for (i, &(x, y)) in points.iter().enumerate() {
+                    if i == 0 {
+                        builder.move_to(x, y);
+                    } else {
+                        builder.line_to(x, y);
+                    }
+                }
+                builder.close();
+                let Some(path) = builder.finish() else {
+                    continue;
+                };
+                if let Some((r, g, b, a)) = fill {
+                    pixmap.fill_path(&path, &paint_for((*r, *g, *b), *a), FillRule::Winding, Transform::identity(), None);
+                }
+                let line = Stroke {
+                    width: *stroke_width,
+                    dash: if *dashed { StrokeDash::new(DASH_PATTERN.to_vec(), 0.0) } else { None },
+                    ..Stroke::default()
+                };
+                pixmap.stroke_path(&path, &paint_for(*stroke, 255), &line, Transform::identity(), None);
+            }
+            MapPrimitive::Circle { center, radius, fill } => {
+                if let Some(path) = PathBuilder::from_circle(center.0, center.1, *radius) {
+                    pixmap.fill_path(&path, &paint_for(*fill, 255), FillRule::Winding, Transform::identity(), None);
+                }
+            }
+            MapPrimitive::Line { from, to, color, width } => {
+                let mut builder = PathBuilder::new();
+                builder.move_to(from.0, from.1);
+                builder.line_to(to.0, to.1);
+                if let Some(path) = builder.finish() {
+                    let line = Stroke {
+                        width: *width,
+                        ..Stroke::default()
+                    };
+                    pixmap.stroke_path(&path, &paint_for(*color, 255), &line, Transform::identity(), None);
+                }
+            }
+            MapPrimitive::Text { pos, text, color } => {
+                draw_text(&mut pixmap, &font, *pos, text, *color);
+            }
+        }
+    }
+
+    pixmap.encode_png().map_err(|e| format!("PNG encoding error: {}", e))
+}
+
+/// Draws a line of text with its baseline at `pos`
+fn draw_text(pixmap: &mut tiny_skia::Pixmap, font: &ab_glyph::FontRef<'_>, pos: (f32, f32), text: &str, color: (u8, u8, u8)) {
+    use ab_glyph::{Font, ScaleFont};
+
+    let scaled = font.as_scaled(FONT_SIZE);
+    let mut caret = pos.0;
+    for c in text.chars() {
+        let glyph_id = font.glyph_id(c);
+        let glyph = glyph_id.with_scale_and_position(FONT_SIZE, ab_glyph::point(caret, pos.1));
+        caret += scaled.h_advance(glyph_id);
+
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|x, y, coverage| {
+            let px = bounds.min.x + x as f32;
+            let py = bounds.min.y + y as f32;
+            let alpha = (coverage.clamp(0.0, 1.0) * 255.0) as u8;
+            if alpha == 0 {
+                return;
+            }
+            if let Some(rect) = tiny_skia::Rect::from_xywh(px, py, 1.0, 1.0) {
+                let mut paint = tiny_skia::Paint::default();
+                paint.set_color_rgba8(color.0, color.1, color.2, alpha);
+                pixmap.fill_rect(rect, &paint, tiny_skia::Transform::identity(), None);
+            }
+        });
+    }
+}
+
+/// Lays out the map and legend in image coordinates
+fn build_scene(layout: &RoomLayout, options: &MapExportOptions) -> MapScene {
+    let map_width = (options.width.max(100) as f32).min(MAX_MAP_SIZE);
+    let (min_x, min_z, max_x, max_z) = layout.bounds().unwrap_or((0, 0, 1, 1));
+    let room_width = (max_x - min_x).max(1) as f32;
+    let room_depth = (max_z - min_z).max(1) as f32;
+    let scale = ((map_width - 2.0 * MAP_MARGIN) / room_width).min((MAX_MAP_SIZE - 2.0 * MAP_MARGIN) / room_depth);
+    let map_height = room_depth * scale + 2.0 * MAP_MARGIN;
+
+    // Room z grows upwards, image y grows downwards
+    let to_image = |x: i32, z: i32| {
+        (
+            MAP_MARGIN + (x - min_x) as f32 * scale,
+            MAP_MARGIN + (max_z - z) as f32 * scale,
+        )
+    };
+    let area_points = |area: &AotShape| area.points.iter().map(|&(x, z)| to_image(x, z)).collect::<Vec<_>>();
+
+    let mut primitives = Vec::new();
+
+    for switch in &layout.camera_switches {
+        primitives.push(MapPrimitive::Polygon {
+            points: switch.points.iter().map(|&(x, z)| to_image(x as i32, z as i32)).collect(),
+            fill: None,
+            stroke: CAMERA_SWITCH_COLOR,
+            stroke_width: 1.0,
+            dashed: true,
+        });
+    }
+
+    for b in &layout.collision {
+        let (x0, z0) = (b.x as i32, b.z as i32);
+        let (x1, z1) = (x0 + b.width as i32, z0 + b.depth as i32);
+        primitives.push(MapPrimitive::Polygon {
+            points: vec![to_image(x0, z0), to_image(x1, z0), to_image(x1, z1), to_image(x0, z1)],
+            fill: None,
+            stroke: COLLISION_COLOR,
+            stroke_width: 1.0,
+            dashed: false,
+        });
+    }
+
+    let mut aot_types: Vec<i32> = Vec::new();
+    for aot in &layout.aots {
+        let (r, g, b) = aot_type_color(aot.aot_type);
+        primitives.push(MapPrimitive::Polygon {
+            points: area_points(aot),
+            fill: Some((r, g, b, AOT_FILL_ALPHA)),
+            stroke: (r, g, b),
+            stroke_width: 1.5,
+            dashed: false,
+        });
+        if !aot_types.contains(&aot.aot_type) {
+            aot_types.push(aot.aot_type);
+        }
+    }
+    aot_types.sort_unstable();
+
+    for door in &layout.doors {
+        primitives.push(MapPrimitive::Polygon {
+            points: area_points(&door.area),
+            fill: None,
+            stroke: DOOR_COLOR,
+            stroke_width: 1.5,
+            dashed: false,
+        });
+        let (cx, cz) = door.area.center();
+        let center = to_image(cx, cz);
+        let angle = door.next_dir as f32 / FULL_TURN as f32 * std::f32::consts::TAU;
+        primitives.extend(arrow(center, angle, DOOR_COLOR));
+    }
+
+    for item in &layout.items {
+        primitives.push(MapPrimitive::Polygon {
+            points: area_points(&item.area),
+            fill: None,
+            stroke: ITEM_COLOR,
+            stroke_width: 1.5,
+            dashed: false,
+        });
+        let (cx, cz) = item.area.center();
+        primitives.push(MapPrimitive::Circle {
+            center: to_image(cx, cz),
+            radius: MARKER_RADIUS,
+            fill: ITEM_COLOR,
+        });
+    }
+
+    for enemy in &layout.enemies {
+        let center = to_image(enemy.x, enemy.z);
+        let angle = enemy.dir as f32 / FULL_TURN as f32 * std::f32::consts::TAU;
+        primitives.push(MapPrimitive::Circle {
+            center,
+            radius: MARKER_RADIUS,
+            fill: ENEMY_COLOR,
+        });
+        primitives.push(MapPrimitive::Line {
+            from: center,
+            to: (center.0 + angle.sin() * MARKER_RADIUS * 2.5, center.1 - angle.cos() * MARKER_RADIUS * 2.5),
+            color: ENEMY_COLOR,
+            width: 2.0,
+        });
+    }
+
+    // Legend to the right of the map
+    let legend_x = map_width + 10.0;
+    let mut legend_y = MAP_MARGIN;
+    primitives.push(MapPrimitive::Text {
+        pos: (legend_x, legend_y + FONT_SIZE),
+        text: options.title.clone(),
+        color: TEXT_COLOR,
+    });
+    legend_y += LEGEND_ROW_HEIGHT * 1.5;
+
+    let mut entries: Vec<(String, (u8, u8, u8), bool)> = vec![
+        ("Collision".to_string(), COLLISION_COLOR, false),
+        ("Camera switch".to_string(), CAMERA_SWITCH_COLOR, false),
+        ("Door".to_string(), DOOR_COLOR, false),
+        ("Item".to_string(), ITEM_COLOR, true),
+        ("Enemy".to_string(), ENEMY_COLOR, true),
+    ];
+    entries.extend(aot_types.iter().map(|&t| (format!("AOT: {}", aot_type_name(t)), aot_type_color(t), false)));
+
+    for (label, color, is_marker) in entries {
+        let swatch_y = legend_y + LEGEND_ROW_HEIGHT / 2.0;
+        if is_marker {
+            primitives.push(MapPrimitive::Circle {
+                center: (legend_x + 7.0, swatch_y),
+                radius: MARKER_RADIUS,
+                fill: color,
+            });
+        } else {
+            primitives.push(MapPrimitive::Polygon {
+                points: vec![
+                    (legend_x, swatch_y - 6.0),
+                    (legend_x + 14.0, swatch_y - 6.0),
+                    (legend_x + 14.0, swatch_y + 6.0),
+                    (legend_x, swatch_y + 6.0),
+                ],
+                fill: Some((color.0, color.1, color.2, AOT_FILL_ALPHA)),
+                stroke: color,
+                stroke_width: 1.5,
+                dashed: false,
+            });
+        }
+        primitives.push(MapPrimitive::Text {
+            pos: (legend_x + 22.0, swatch_y + FONT_SIZE / 3.0),
+            text: label,
+            color: TEXT_COLOR,
+        });
+        legend_y += LEGEND_ROW_HEIGHT;
+    }
+
+    MapScene {
+        width: (map_width + LEGEND_WIDTH) as u32,
+        height: map_height.max(legend_y + MAP_MARGIN).ceil() as u32,
+        primitives,
+    }
+}
+
+/// An arrow centred on `center` pointing along `angle`
+fn arrow(center: (f32, f32), angle: f32, color: (u8, u8, u8)) -> Vec<MapPrimitive> {
+    let (dx, dy) = (angle.sin() * ARROW_LENGTH / 2.0, -angle.cos() * ARROW_LENGTH / 2.0);
+    let tail = (center.0 - dx, center.1 - dy);
+    let tip = (center.0 + dx, center.1 + dy);
+    let head = |side: f32| {
+        let head_angle = angle + std::f32::consts::PI + side * 0.5;
+        (tip.0 + head_angle.sin() * 8.0, tip.1 - head_angle.cos() * 8.0)
+    };
+    vec![
+        MapPrimitive::Line { from: tail, to: tip, color, width: 2.0 },
+        MapPrimitive::Line { from: tip, to: head(1.0), color, width: 2.0 },
+        MapPrimitive::Line { from: tip, to: head(-1.0), color, width: 2.0 },
+    ]
+}
+
+fn svg_color((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bio2_scd::fileio::rdt_sca::CollisionBox;
+    use bio2_scd::fileio::room_layout::{EnemyMarker, ItemMarker, ScriptKind, ScriptLocation};
+
+    const SOURCE: ScriptLocation = ScriptLocation {
+        script: ScriptKind::Init,
+        function: 0,
+        instruction: 0,
+    };
+
+    /// A room with one collision box, an item AOT and an enemy inside it
+    fn layout(width: u16, depth: u16) -> RoomLayout {
+        RoomLayout {
+            collision: vec![CollisionBox {
+                x: 0,
+                z: 0,
+                width,
+                depth,
+                id: 0,
+                shape_type: 0,
+                floor: 0,
+            }],
+            items: vec![ItemMarker {
+                area: AotShape {
+                    source: SOURCE,
+                    aot: 0,
+                    aot_type: 2,
+                    floor: 0,
+                    points: vec![(1000, 1000), (2000, 1000), (2000, 2000), (1000, 2000)],
+                },
+                item_id: 1,
+                amount: 1,
+                item_picked_index: 0,
+            }],
+            enemies: vec![EnemyMarker {
+                source: SOURCE,
+                em_no: 0,
+                id: 0,
+                enemy_type: 16,
+                status: 0,
+                em_set_flag: 0,
+                x: 5000,
+                y: 0,
+                z: 5000,
+                dir: 0,
+                motion: 0,
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn options() -> MapExportOptions {
+        MapExportOptions {
+            width: 400,
+            title: "ROOM1000".to_string(),
+        }
+    }
+
+    #[test]
+    fn svg_has_legend_and_markers() {
+        let svg = render_svg(&layout(10000, 10000), &options());
+        for label in ["ROOM1000", "Collision", "Door", "Item", "Enemy"] {
+            assert!(svg.contains(&format!(">{}</text>", label)), "{} missing from the legend", label);
+        }
+        // Item and enemy markers on the map besides their legend swatches
+        assert_eq!(svg.matches(&format!(r#"fill="{}"/>"#, svg_color(ITEM_COLOR))).count(), 2);
+        assert_eq!(svg.matches(&format!(r#"fill="{}"/>"#, svg_color(ENEMY_COLOR))).count(), 2);
+        // The enemy in the middle of the room is drawn in the middle of the map area
+        assert!(svg.contains(r##"<circle cx="200.0" cy="200.0" r="5" fill="#ff5050"/>"##));
+    }
+
+    #[test]
+    fn png_has_legend_and_markers() {
+        let png = render_png(&layout(10000, 10000), &options()).unwrap();
+        let pixmap = tiny_skia::Pixmap::decode_png(&png).unwrap();
+        assert_eq!(pixmap.width(), 400 + LEGEND_WIDTH as u32);
+        let color = |x: u32, y: u32| {
+            let pixel = pixmap.pixel(x, y).unwrap();
+            (pixel.red(), pixel.green(), pixel.blue())
+        };
+        assert_eq!(color(200, 200), ENEMY_COLOR);
+        // Antialiased text blends into the background, so look for light grey pixels
+        let legend_has_text = (400..pixmap.width())
+            .flat_map(|x| (0..pixmap.height()).map(move |y| (x, y)))
+            .any(|(x, y)| matches!(color(x, y), (r, g, b) if r == g && g == b && r > 100));
+        assert!(legend_has_text);
+    }
+
+    #[test]
+    fn long_rooms_are_scaled_to_fit() {
+        let scene = build_scene(&layout(100, 60000), &options());
+        assert!(scene.height as f32 <= MAX_MAP_SIZE, "height {}", scene.height);
+        assert_eq!(scene.width, 400 + LEGEND_WIDTH as u32);
+
+        let scene = build_scene(&layout(10000, 10000), &MapExportOptions {
+            width: 100_000,
+            title: String::new(),
+        });
+        assert!(scene.width as f32 <= MAX_MAP_SIZE + LEGEND_WIDTH && scene.height as f32 <= MAX_MAP_SIZE);
+    }
+}
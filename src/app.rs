@@ -1,5 +1,31 @@
-use crate::file_handler::{FileHandler, RdtFileData};
-use crate::fileio::opcode_data::{
+use bio2_scd::analysis::lint::{lint_room, Problem};
+use bio2_scd::analysis::reachability::Reachability;
+use bio2_scd::analysis::table::{rows_to_csv, rows_to_json, PlacementRow};
+use bio2_scd::analysis::xref::{Reference as XrefReference, XrefTarget};
+use crate::code_editor_view::CodeEditor;
+use crate::debugger_view::{DebuggerAction, DebuggerView};
+use crate::diff_view::DiffView;
+use crate::merge_view::MergeView;
+use crate::door_graph_view::show_door_graph;
+use bio2_scd::file_handler::{FileHandler, RdtFileData};
+use bio2_scd::fileio::name_tables::NameTables;
+use bio2_scd::fileio::room_id::RoomId;
+use bio2_scd::fileio::rdt_scd::ScdInstruction;
+use bio2_scd::fileio::symbols::{SymbolDictionary, SymbolKind};
+use crate::symbols_view::SymbolEditor;
+use bio2_scd::game_data::GameData;
+use bio2_scd::merge::RoomMerge;
+use bio2_scd::patch::PatchFormat;
+use bio2_scd::writer::{write_scripts, PlacedInstruction};
+use bio2_scd::fileio::utils::read_file;
+use bio2_scd::fileio::room_layout::{ScriptKind, ScriptLocation};
+use crate::problems_view::ProblemsView;
+use crate::room_map::RoomMapView;
+use crate::room_state_view::RoomStateView;
+use crate::table_view::{show_sortable_table, TableSort};
+use bio2_scd::vm::machine::Vm;
+use bio2_scd::vm::state::GameState;
+use bio2_scd::fileio::opcode_data::{
     OPCODE_IF_START, OPCODE_ELSE_START, OPCODE_END_IF,
     OPCODE_FOR_START, OPCODE_FOR_END,
     OPCODE_WHILE_START, OPCODE_WHILE_END,
@@ -9,10 +35,15 @@ use crate::fileio::opcode_data::{
 use std::collections::HashMap;
 
 // UI Constants
-const KEYWORD_COLOR: egui::Color32 = egui::Color32::from_rgb(198, 120, 221);
-const FUNCTION_COLOR: egui::Color32 = egui::Color32::from_rgb(93, 166, 226);
+pub(crate) const KEYWORD_COLOR: egui::Color32 = egui::Color32::from_rgb(198, 120, 221);
+pub(crate) const FUNCTION_COLOR: egui::Color32 = egui::Color32::from_rgb(93, 166, 226);
+const SELECTED_LINE_COLOR: egui::Color32 = egui::Color32::from_rgb(70, 70, 30);
+const CURRENT_LINE_COLOR: egui::Color32 = egui::Color32::from_rgb(30, 80, 40);
+const BREAKPOINT_COLOR: egui::Color32 = egui::Color32::from_rgb(220, 60, 60);
+const UNREACHABLE_COLOR: egui::Color32 = egui::Color32::from_rgb(128, 128, 128);
+const ROOM_WARNING_COLOR: egui::Color32 = egui::Color32::from_rgb(220, 180, 80);
 
-const KEYWORD_LIST: &[&str] = &[
+pub(crate) const KEYWORD_LIST: &[&str] = &[
     OPCODE_IF_START,
     OPCODE_ELSE_START, 
     OPCODE_END_IF,
@@ -34,6 +65,28 @@ const SOURCE_CODE_HEADING_PREFIX: &str = "Source code for";
 const RAW_HEX_HEADING: &str = "Raw hex values";
 const FUNCTION_HEADING_PREFIX: &str = "Function ";
 const COPY_CODE_BUTTON: &str = "Copy code📋";
+const EDIT_CODE_BUTTON: &str = "Edit code ✏";
+const ROOM_MAP_BUTTON: &str = "Room map 🗺";
+const ROOM_MAP_HEADING: &str = "Room map";
+const DOOR_GRAPH_BUTTON: &str = "Door graph 🚪";
+const DOOR_GRAPH_HEADING: &str = "Door graph";
+const ITEM_TABLE_BUTTON: &str = "Items 💊";
+const ITEM_TABLE_HEADING: &str = "Item placements";
+const ENEMY_TABLE_BUTTON: &str = "Enemies 🧟";
+const ENEMY_TABLE_HEADING: &str = "Enemy placements";
+const SYMBOLS_BUTTON: &str = "Symbols 🏷";
+const SYMBOLS_HEADING: &str = "Symbol dictionary";
+const REFERENCES_HEADING: &str = "References";
+const DEBUGGER_BUTTON: &str = "Debugger 🐞";
+const DEBUGGER_HEADING: &str = "Debugger";
+const ROOM_STATE_BUTTON: &str = "Room state 🏠";
+const ROOM_STATE_HEADING: &str = "Room state";
+const PROBLEMS_BUTTON: &str = "Problems ⚠";
+const PROBLEMS_HEADING: &str = "Problems";
+const DIFF_BUTTON: &str = "Compare ⇄";
+const DIFF_HEADING: &str = "Script differences";
+const MERGE_BUTTON: &str = "Merge 🔀";
+const MERGE_HEADING: &str = "Merge mods";
 
 // Script Names
 const INIT_SCRIPT_NAME: &str = "init.scd";
@@ -53,6 +106,37 @@ struct UiState {
     toast_message: Option<String>,
     toast_timer: f32,
     error_message: Option<String>,
+    show_room_map: bool,
+    room_map: RoomMapView,
+    show_door_graph: bool,
+    show_item_table: bool,
+    item_table_sort: TableSort,
+    show_enemy_table: bool,
+    enemy_table_sort: TableSort,
+    show_symbols: bool,
+    symbol_editor: SymbolEditor,
+    show_references: bool,
+    /// Flag or variable listed in the references window
+    reference_target: Option<XrefTarget>,
+    references_sort: TableSort,
+    /// Instruction picked on the room map, highlighted in the code panel
+    selected_location: Option<ScriptLocation>,
+    scroll_to_selected: bool,
+    show_debugger: bool,
+    debugger: DebuggerView,
+    /// Scroll the code panel to the instruction the debugger paused at
+    scroll_to_current: bool,
+    show_room_state: bool,
+    room_state: RoomStateView,
+    show_problems: bool,
+    problems: ProblemsView,
+    show_diff: bool,
+    diff: DiffView,
+    show_merge: bool,
+    merge: MergeView,
+    /// Show the functions as editable text instead of the read-only listing
+    edit_code: bool,
+    code_editor: CodeEditor,
 }
 
 impl Default for UiState {
@@ -65,10 +149,62 @@ impl Default for UiState {
             toast_message: None,
             toast_timer: 0.0,
             error_message: None,
+            show_room_map: false,
+            room_map: RoomMapView::default(),
+            show_door_graph: false,
+            show_item_table: false,
+            item_table_sort: TableSort::default(),
+            show_enemy_table: false,
+            enemy_table_sort: TableSort::default(),
+            show_symbols: false,
+            symbol_editor: SymbolEditor::default(),
+            show_references: false,
+            reference_target: None,
+            references_sort: TableSort::default(),
+            selected_location: None,
+            scroll_to_selected: false,
+            show_debugger: false,
+            debugger: DebuggerView::default(),
+            scroll_to_current: false,
+            show_room_state: false,
+            room_state: RoomStateView::default(),
+            show_problems: false,
+            problems: ProblemsView::default(),
+            show_diff: false,
+            diff: DiffView::default(),
+            show_merge: false,
+            merge: MergeView::default(),
+            edit_code: false,
+            code_editor: CodeEditor::default(),
         }
     }
 }
 
+/// Action picked from the context menu of a parameter in the code view
+#[derive(Clone, Copy)]
+enum CodeAction {
+    NameSymbol(SymbolKind),
+    FindReferences(XrefTarget),
+    /// Instruction index within the function the gutter was clicked in
+    ToggleBreakpoint(usize),
+    /// Instruction index within the function whose name was clicked
+    Select(usize),
+}
+
+/// Highlights and breakpoints of the lines of one function in the code panel
+#[derive(Default)]
+struct LineMarkers {
+    /// Instruction picked on the map or in a table
+    selected: Option<usize>,
+    /// Next instruction of the task the debugger is paused on
+    current: Option<usize>,
+    scroll_to: Option<usize>,
+    /// Lines with a breakpoint, or None to hide the breakpoint gutter
+    breakpoints: Option<Vec<usize>>,
+    /// Lines whose path condition can never hold, with the reason
+    unreachable: HashMap<usize, String>,
+}
+
 /// Groups code lines into functions based on "End Function" markers
 fn group_code_into_functions(code_string: &[String]) -> Vec<Vec<String>> {
     let mut function_grouping = Vec::new();
@@ -95,7 +231,7 @@ impl TemplateApp {
 
     /// Updates toast timer and clears when expired
     fn update_toast(&mut self, delta_time: f32) {
-        if let Some(_) = self.ui_state.toast_message {
+        if self.ui_state.toast_message.is_some() {
             self.ui_state.toast_timer -= delta_time;
             if self.ui_state.toast_timer <= 0.0 {
                 self.ui_state.toast_message = None;
@@ -121,16 +257,200 @@ impl TemplateApp {
             .ok_or("Invalid file name")?
             .to_string();
 
-        // Use the FileHandler to load and parse the file
-        let file_data = FileHandler::load_rdt_file(file_path)?;
+        // Use the FileHandler to parse the file, keeping its contents for the code editor
+        let contents = read_file(file_path.display().to_string()).map_err(|e| format!("File read error: {}", e))?;
+        let mut file_data = FileHandler::parse_rdt_file(file_path, &contents)?;
+        if self.name_table_path.is_some() {
+            file_data.refresh_scripts(&self.name_tables);
+        }
 
-        // Update the app state
+        // Update the app state, keeping the open windows
         self.file_data = Some(file_data);
-        self.ui_state = UiState::default();
+        self.rdt_file = Some((file_path.to_path_buf(), contents));
+        self.ui_state = UiState {
+            show_room_map: self.ui_state.show_room_map,
+            show_door_graph: self.ui_state.show_door_graph,
+            show_item_table: self.ui_state.show_item_table,
+            item_table_sort: self.ui_state.item_table_sort.clone(),
+            show_enemy_table: self.ui_state.show_enemy_table,
+            enemy_table_sort: self.ui_state.enemy_table_sort.clone(),
+            show_symbols: self.ui_state.show_symbols,
+            symbol_editor: self.ui_state.symbol_editor.clone(),
+            show_references: self.ui_state.show_references,
+            reference_target: self.ui_state.reference_target,
+            references_sort: self.ui_state.references_sort.clone(),
+            show_debugger: self.ui_state.show_debugger,
+            debugger: self.ui_state.debugger.for_new_room(),
+            show_room_state: self.ui_state.show_room_state,
+            room_state: self.ui_state.room_state.for_new_room(),
+            show_problems: self.ui_state.show_problems,
+            problems: self.ui_state.problems.clone(),
+            show_diff: self.ui_state.show_diff,
+            diff: self.ui_state.diff.for_new_room(),
+            show_merge: self.ui_state.show_merge,
+            merge: self.ui_state.merge.clone(),
+            edit_code: self.ui_state.edit_code,
+            ..UiState::default()
+        };
+        self.vm = None;
+        self.reachability = None;
+        self.problems = None;
+
+        Ok(())
+    }
+
+    /// Writes the instructions of an edited function into the open room and decodes it again
+    ///
+    /// Returns warnings about sections that moved
+    fn apply_code_edit(&mut self, function: usize, instructions: Vec<PlacedInstruction>) -> Result<Vec<String>, String> {
+        let (path, contents) = self.rdt_file.as_ref().ok_or("Open the room again to edit its scripts")?;
+        let script = self.displayed_script();
+        let count = self.file_data.as_ref().map_or(0, |f| f.functions(script).len()).max(function + 1);
+        let mut functions = vec![None; count];
+        functions[function] = Some(instructions);
+        let (contents, warnings) = write_scripts(contents, &[(script, functions)])?;
+        let file_data = FileHandler::parse_rdt_file(path, &contents)?;
+
+        self.file_data = Some(file_data);
+        self.rdt_file = Some((path.clone(), contents));
+        self.vm = None;
+        self.reachability = None;
+        self.problems = None;
+        self.refresh_code_view();
+        Ok(warnings)
+    }
+
+    /// Asks for a file name and writes the open room with its edits
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_room(&mut self) {
+        let Some((path, contents)) = self.rdt_file.as_ref() else {
+            return;
+        };
+        let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("RDT Files", &["rdt"])
+            .set_file_name(&file_name)
+            .save_file()
+        else {
+            return;
+        };
+        match std::fs::write(&path, contents) {
+            Ok(()) => self.show_toast(format!("Saved {}", path.display())),
+            Err(e) => self.show_error(format!("Error saving file: {}", e)),
+        }
+    }
+
+    /// Loads a second RDT file to compare the scripts of the open room with
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_compared_file(&mut self, file_path: &std::path::Path) -> Result<(), String> {
+        let file_data = FileHandler::load_rdt_file(file_path)?;
+        let name = file_path.file_stem().and_then(|s| s.to_str()).unwrap_or("room").to_string();
+        self.ui_state.diff.compare_with(name, file_data);
+        self.ui_state.show_diff = true;
+        Ok(())
+    }
 
+    /// Asks for the original room and two mods of it and merges their scripts
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start_merge(&mut self) -> Result<(), String> {
+        let mut paths = Vec::new();
+        for title in ["Original room", "First mod", "Second mod"] {
+            let Some(path) = rfd::FileDialog::new()
+                .set_title(title)
+                .add_filter("RDT Files", &["rdt"])
+                .pick_file()
+            else {
+                return Ok(());
+            };
+            paths.push(path);
+        }
+        let base_contents = read_file(paths[0].display().to_string())?;
+        let merge = RoomMerge::new(
+            &FileHandler::load_rdt_file(&paths[0])?,
+            &FileHandler::load_rdt_file(&paths[1])?,
+            &FileHandler::load_rdt_file(&paths[2])?,
+        );
+        let names = [0, 1, 2].map(|i| paths[i].display().to_string());
+        self.ui_state.merge.start(names, base_contents, merge);
+        self.ui_state.show_merge = true;
         Ok(())
     }
 
+    /// Asks for an original and a modified RDT and writes a BPS or IPS patch between them
+    ///
+    /// Returns the path of the patch, or None when a dialog was cancelled
+    #[cfg(not(target_arch = "wasm32"))]
+    fn create_patch(&self) -> Result<Option<std::path::PathBuf>, String> {
+        let pick = |title: &str| rfd::FileDialog::new().set_title(title).add_filter("RDT Files", &["rdt"]).pick_file();
+        let Some(original) = pick("Original room") else {
+            return Ok(None);
+        };
+        let Some(modified) = pick("Modified room") else {
+            return Ok(None);
+        };
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("BPS patch", &["bps"])
+            .add_filter("IPS patch", &["ips"])
+            .save_file()
+        else {
+            return Ok(None);
+        };
+        let format = PatchFormat::from_path(&path).unwrap_or(PatchFormat::Bps);
+        let patch = format.create(&read_file(original.display().to_string())?, &read_file(modified.display().to_string())?)?;
+        std::fs::write(&path, patch).map_err(|e| e.to_string())?;
+        Ok(Some(path))
+    }
+
+    /// Asks for a patch and the original RDT it was made for and writes the patched file
+    ///
+    /// BPS patches check the original and the result against their checksums
+    #[cfg(not(target_arch = "wasm32"))]
+    fn apply_patch(&self) -> Result<Option<std::path::PathBuf>, String> {
+        let Some(patch_path) = rfd::FileDialog::new().add_filter("Patch files", &["bps", "ips"]).pick_file() else {
+            return Ok(None);
+        };
+        let format = PatchFormat::from_path(&patch_path).ok_or("Expected an .ips or .bps patch file")?;
+        let Some(original) = rfd::FileDialog::new()
+            .set_title("Original room")
+            .add_filter("RDT Files", &["rdt"])
+            .pick_file()
+        else {
+            return Ok(None);
+        };
+        let target = format.apply(&read_file(original.display().to_string())?, &read_file(patch_path.display().to_string())?)?;
+        let Some(path) = rfd::FileDialog::new().add_filter("RDT Files", &["rdt"]).save_file() else {
+            return Ok(None);
+        };
+        std::fs::write(&path, target).map_err(|e| e.to_string())?;
+        Ok(Some(path))
+    }
+
+    /// Loads every RDT file of a game directory for the game-wide views
+    fn load_game_directory(&mut self, dir: &std::path::Path) -> Result<(), String> {
+        let game_data = GameData::load(dir)?;
+        if !game_data.load_errors.is_empty() {
+            self.show_toast(format!("{} files could not be loaded", game_data.load_errors.len()));
+        }
+        self.game_data = Some(game_data);
+        self.ui_state.show_door_graph = true;
+        Ok(())
+    }
+
+    /// Asks for a file name and writes text to it
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_text_file(&mut self, filter_name: &str, extension: &str, contents: Result<String, String>) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(filter_name, &[extension])
+            .save_file()
+        else {
+            return;
+        };
+        match contents.and_then(|c| std::fs::write(&path, c).map_err(|e| e.to_string())) {
+            Ok(()) => self.show_toast(format!("Saved {}", path.display())),
+            Err(e) => self.show_error(format!("Error saving file: {}", e)),
+        }
+    }
+
     /// Renders the top panel with menu bar
     fn render_top_panel(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         #[cfg(not(target_arch = "wasm32"))] // no File->Quit on web pages!
@@ -148,6 +468,73 @@ impl TemplateApp {
                             }
                         }
                     }
+                    if ui.add_enabled(self.rdt_file.is_some(), egui::Button::new("Save room as…")).clicked() {
+                        self.save_room();
+                        ui.close_menu();
+                    }
+                    if ui.button("Open game directory…").clicked() {
+                        if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                            if let Err(e) = self.load_game_directory(&dir) {
+                                self.show_error(format!("Error loading directory: {}", e));
+                            }
+                        }
+                    }
+                    if ui.button("Compare with…").clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("RDT Files", &["rdt"])
+                            .pick_file()
+                        {
+                            if let Err(e) = self.load_compared_file(&path) {
+                                self.show_error(format!("Error loading file: {}", e));
+                            }
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.button("Merge mods…").clicked() {
+                        if let Err(e) = self.start_merge() {
+                            self.show_error(format!("Error merging: {}", e));
+                        }
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("Create patch…").clicked() {
+                        match self.create_patch() {
+                            Ok(Some(path)) => self.show_toast(format!("Saved {}", path.display())),
+                            Ok(None) => {}
+                            Err(e) => self.show_error(format!("Error creating patch: {}", e)),
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.button("Apply patch…").clicked() {
+                        match self.apply_patch() {
+                            Ok(Some(path)) => self.show_toast(format!("Saved {}", path.display())),
+                            Ok(None) => {}
+                            Err(e) => self.show_error(format!("Error applying patch: {}", e)),
+                        }
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("Load name tables…").clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("Name tables", &["json"])
+                            .pick_file()
+                        {
+                            match self.load_name_tables(Some(path)) {
+                                Ok(()) => self.show_toast("Name tables loaded".to_string()),
+                                Err(e) => self.show_error(format!("Error loading name tables: {}", e)),
+                            }
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.button("Export name tables…").clicked() {
+                        self.save_text_file("Name tables", "json", self.name_tables.to_json());
+                        ui.close_menu();
+                    }
+                    if self.name_table_path.is_some() && ui.button("Use built-in name tables").clicked() {
+                        let _ = self.load_name_tables(None);
+                        ui.close_menu();
+                    }
+                    ui.separator();
                     if ui.button("Quit").clicked() {
                         frame.close();
                     }
@@ -179,18 +566,38 @@ impl TemplateApp {
                 ui.separator();
 
                 if ui.add(egui::Button::new(COPY_CODE_BUTTON)).clicked() {
-                    ui.output_mut(|o| o.copied_text = String::from(self.ui_state.code_string.join("\n")));
+                    ui.output_mut(|o| o.copied_text = self.ui_state.code_string.join("\n"));
                     self.show_toast("Code copied to clipboard! 📋".to_string());
                 }
+                ui.toggle_value(&mut self.ui_state.edit_code, EDIT_CODE_BUTTON);
+
+                ui.toggle_value(&mut self.ui_state.show_room_map, ROOM_MAP_BUTTON);
+                ui.toggle_value(&mut self.ui_state.show_door_graph, DOOR_GRAPH_BUTTON);
+                ui.toggle_value(&mut self.ui_state.show_item_table, ITEM_TABLE_BUTTON);
+                ui.toggle_value(&mut self.ui_state.show_enemy_table, ENEMY_TABLE_BUTTON);
+                ui.toggle_value(&mut self.ui_state.show_symbols, SYMBOLS_BUTTON);
+                ui.toggle_value(&mut self.ui_state.show_debugger, DEBUGGER_BUTTON);
+                ui.toggle_value(&mut self.ui_state.show_room_state, ROOM_STATE_BUTTON);
+                ui.toggle_value(&mut self.ui_state.show_problems, PROBLEMS_BUTTON);
+                ui.toggle_value(&mut self.ui_state.show_diff, DIFF_BUTTON);
+                ui.toggle_value(&mut self.ui_state.show_merge, MERGE_BUTTON);
             });
         });
     }
 
     /// Renders the central panel with code display
     fn render_code_panel(&mut self, ctx: &egui::Context) {
+        let mut code_action = None;
         egui::CentralPanel::default().show(ctx, |ui| {
             // The central panel the region left after adding TopPanel's and SidePanel's
-            ui.heading(format!("{} {}", SOURCE_CODE_HEADING_PREFIX, self.picked_path));
+            ui.heading(format!("{} {}", SOURCE_CODE_HEADING_PREFIX, self.name_tables.room_label(&self.picked_path)));
+            if let Some(ref file_data) = self.file_data {
+                if let Some(id) = RoomId::parse(&self.picked_path) {
+                    for problem in id.check(file_data) {
+                        ui.colored_label(ROOM_WARNING_COLOR, format!("⚠ {}", problem));
+                    }
+                }
+            }
             egui::warn_if_debug_build(ui);
 
             // Render error message (prominent, requires manual dismissal)
@@ -221,40 +628,577 @@ impl TemplateApp {
                 ui.add_space(10.0);
             }
 
+            if let Some(file_data) = self.file_data.as_ref().filter(|_| self.reachability.is_none()) {
+                self.reachability = Some(Reachability::new(&file_data.init_functions, &file_data.main_functions));
+            }
+            let selected_path = self.ui_state.selected_location.and_then(|location| {
+                let path = self.reachability.as_ref()?.get(location)?;
+                (location.script == self.displayed_script()).then_some((location, path))
+            });
+            if let Some((location, path)) = selected_path {
+                let via = path
+                    .caller
+                    .map(|c| format!(", called from {} function {} line {}", c.script.name(), c.function, c.instruction))
+                    .unwrap_or_default();
+                let text = format!("Function {} line {} runs when {}{}", location.function, location.instruction, path, via);
+                if path.is_reachable() {
+                    ui.label(text);
+                } else {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), text);
+                }
+                ui.separator();
+            }
+
+            if self.ui_state.edit_code {
+                egui::ScrollArea::both().show(ui, |ui| self.show_code_editor(ui));
+                return;
+            }
+
             egui::ScrollArea::vertical().show(ui, |ui| {
                 ui.style_mut().wrap = Some(false);
 
                 let function_grouping = group_code_into_functions(&self.ui_state.code_string);
+                let functions = self.file_data.as_ref().map(|f| f.functions(self.displayed_script())).unwrap_or_default();
 
-                let mut function_num = 0;
-                for current_function in function_grouping.iter() {
-                    egui::CollapsingHeader::new(format!("{}{}", FUNCTION_HEADING_PREFIX, function_num))
-                        .default_open(true)
-                        .show(ui, |ui| {
+                let selected_line = self.selected_code_line();
+                let current_line = self.current_code_line();
+                let scroll_to_selected = std::mem::take(&mut self.ui_state.scroll_to_selected);
+                let scroll_to_current = std::mem::take(&mut self.ui_state.scroll_to_current);
+                let script = self.displayed_script();
+
+                for (function_num, current_function) in function_grouping.iter().enumerate() {
+                    let line_in_function = |line: Option<(usize, usize)>| {
+                        line.filter(|(function, _)| *function == function_num).map(|(_, line)| line)
+                    };
+                    let mut markers = LineMarkers {
+                        selected: line_in_function(selected_line),
+                        current: line_in_function(current_line),
+                        ..Default::default()
+                    };
+                    markers.scroll_to = match (scroll_to_current, scroll_to_selected) {
+                        (true, _) => markers.current,
+                        (false, true) => markers.selected,
+                        (false, false) => None,
+                    };
+                    if let Some(paths) = self.reachability.as_ref().and_then(|r| r.script(script).get(function_num)) {
+                        markers.unreachable = paths
+                            .iter()
+                            .enumerate()
+                            .filter_map(|(index, path)| Some((index + 1, path.contradiction.clone()?)))
+                            .collect();
+                    }
+                    if self.ui_state.show_debugger {
+                        let breakpoints = self.ui_state.debugger.breakpoints.iter();
+                        markers.breakpoints = Some(
+                            breakpoints
+                                .filter(|l| l.script == script && l.function == function_num)
+                                .map(|l| l.instruction + 1)
+                                .collect(),
+                        );
+                    }
+                    let mut header = egui::CollapsingHeader::new(format!("{}{}", FUNCTION_HEADING_PREFIX, function_num))
+                        .default_open(true);
+                    if markers.scroll_to.is_some() {
+                        header = header.open(Some(true));
+                    }
+                    let picked = header.show(ui, |ui| {
                             display_code_function(
                                 ui,
                                 current_function.clone(),
                                 KEYWORD_COLOR,
                                 FUNCTION_COLOR,
-                                &self.file_data.as_ref().map(|f| &f.opcode_docs).unwrap_or(&HashMap::new()),
+                                self.file_data.as_ref().map(|f| &f.opcode_docs).unwrap_or(&HashMap::new()),
+                                &markers,
+                                functions.get(function_num).map(|f| f.instructions.as_slice()),
+                                &self.name_tables,
                             )
                         });
-                    function_num += 1;
+                    code_action = code_action.or(picked.body_returned.flatten().map(|action| (function_num, action)));
                 }
             });
         });
+
+        match code_action {
+            Some((_, CodeAction::NameSymbol(kind))) => self.name_symbol(kind),
+            Some((_, CodeAction::FindReferences(target))) => {
+                self.ui_state.reference_target = Some(target);
+                self.ui_state.show_references = true;
+            }
+            Some((function, CodeAction::ToggleBreakpoint(instruction))) => {
+                self.ui_state.debugger.toggle_breakpoint(ScriptLocation {
+                    script: self.displayed_script(),
+                    function,
+                    instruction,
+                });
+            }
+            Some((function, CodeAction::Select(instruction))) => {
+                self.ui_state.selected_location = Some(ScriptLocation {
+                    script: self.displayed_script(),
+                    function,
+                    instruction,
+                });
+            }
+            None => {}
+        }
+    }
+
+    /// Draws the displayed script as editable text and writes every edit that assembles into the open room
+    fn show_code_editor(&mut self, ui: &mut egui::Ui) {
+        let Some(ref file_data) = self.file_data else {
+            return;
+        };
+        if self.rdt_file.is_none() {
+            ui.label("Open the room again to edit its scripts");
+            return;
+        }
+        let script = self.displayed_script();
+        let editor = &mut self.ui_state.code_editor;
+        if !editor.is_open_for(script) {
+            editor.open(script, file_data.functions(script), &self.name_tables);
+        }
+        let edit = editor.show(ui, file_data.functions(script), &self.name_tables, &file_data.opcode_docs);
+        if let Some((function, instructions)) = edit {
+            match self.apply_code_edit(function, instructions) {
+                Ok(warnings) => self.ui_state.code_editor.set_warnings(warnings),
+                Err(e) => self.show_error(format!("Error writing script: {}", e)),
+            }
+        }
     }
 
     /// Renders the right panel with raw hex values
     fn render_raw_panel(&mut self, ctx: &egui::Context) {
+        let current_line = self.current_code_line();
         egui::SidePanel::right("raw_code_panel").show(ctx, |ui| {
             ui.heading(RAW_HEX_HEADING);
-            egui::ScrollArea::both().show(ui, |ui| {
-                ui.label(&self.ui_state.raw_code);
+            egui::ScrollArea::both().show(ui, |ui| match current_line {
+                Some(current_line) => display_raw_lines(ui, &self.ui_state.raw_code, current_line),
+                None => {
+                    ui.label(&self.ui_state.raw_code);
+                }
             });
         });
     }
 
+    /// Renders the room map window
+    fn render_room_map(&mut self, ctx: &egui::Context) {
+        let mut open = self.ui_state.show_room_map;
+        let mut clicked = None;
+        egui::Window::new(ROOM_MAP_HEADING)
+            .open(&mut open)
+            .default_size([500.0, 500.0])
+            .show(ctx, |ui| match self.file_data {
+                Some(ref file_data) => {
+                    clicked = self.ui_state.room_map.show(ui, &file_data.room_layout, self.ui_state.selected_location);
+                }
+                None => {
+                    ui.label("Open an RDT file to see its map");
+                }
+            });
+        self.ui_state.show_room_map = open;
+
+        if let Some(location) = clicked {
+            self.jump_to_location(location);
+        }
+    }
+
+    /// Renders the game-wide door graph window
+    fn render_door_graph(&mut self, ctx: &egui::Context) {
+        let mut open = self.ui_state.show_door_graph;
+        let mut clicked_room = None;
+        let mut export = None;
+        egui::Window::new(DOOR_GRAPH_HEADING)
+            .open(&mut open)
+            .default_size([600.0, 400.0])
+            .show(ctx, |ui| match self.game_data {
+                Some(ref game_data) => {
+                    ui.horizontal(|ui| {
+                        ui.label(game_data.dir.display().to_string());
+                        if ui.button("Export DOT…").clicked() {
+                            export = Some(("DOT", "dot", Ok(game_data.door_graph.to_dot(&self.name_tables))));
+                        }
+                        if ui.button("Export JSON…").clicked() {
+                            export = Some(("JSON", "json", game_data.door_graph.to_json()));
+                        }
+                    });
+                    clicked_room = show_door_graph(ui, &game_data.door_graph, &self.picked_path, &self.name_tables);
+                }
+                None => {
+                    ui.label("Use File → Open game directory… to build the door graph");
+                }
+            });
+        self.ui_state.show_door_graph = open;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some((filter_name, extension, contents)) = export {
+            self.save_text_file(filter_name, extension, contents);
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = export;
+
+        let path = clicked_room.and_then(|name| self.game_data.as_ref()?.room_path(&name).map(|p| p.to_path_buf()));
+        if let Some(path) = path {
+            if let Err(e) = self.load_rdt_file(&path) {
+                self.show_error(format!("Error loading file: {}", e));
+            }
+        }
+    }
+
+    /// Renders a game-wide placement table window
+    ///
+    /// `rows_of` picks the rows from the loaded game and `state_of` the window flag and sort order
+    fn render_placement_table<T: PlacementRow>(
+        &mut self,
+        ctx: &egui::Context,
+        heading: &str,
+        empty_text: &str,
+        rows_of: fn(&GameData) -> &[T],
+        state_of: fn(&mut UiState) -> (&mut bool, &mut TableSort),
+    ) {
+        let (show, sort) = state_of(&mut self.ui_state);
+        let mut open = *show;
+        let mut clicked_row = None;
+        let mut export = None;
+        egui::Window::new(heading)
+            .open(&mut open)
+            .default_size([700.0, 400.0])
+            .show(ctx, |ui| match self.game_data {
+                Some(ref game_data) => {
+                    let placements = rows_of(game_data);
+                    ui.horizontal(|ui| {
+                        ui.label(T::summary(placements));
+                        if ui.button("Export CSV…").clicked() {
+                            export = Some(("CSV", "csv", Ok(rows_to_csv(placements))));
+                        }
+                        if ui.button("Export JSON…").clicked() {
+                            export = Some(("JSON", "json", rows_to_json(placements)));
+                        }
+                    });
+                    let rows: Vec<Vec<String>> = placements.iter().map(|p| p.table_row()).collect();
+                    clicked_row = show_sortable_table(ui, heading, T::HEADERS, &rows, sort)
+                        .map(|row| (placements[row].room().to_string(), placements[row].source()));
+                }
+                None => {
+                    ui.label(empty_text);
+                }
+            });
+        *state_of(&mut self.ui_state).0 = open;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some((filter_name, extension, contents)) = export {
+            self.save_text_file(filter_name, extension, contents);
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = export;
+
+        if let Some((room, location)) = clicked_row {
+            self.open_room_location(&room, location);
+        }
+    }
+
+    /// Loads a room of the game directory and jumps to an instruction in it
+    fn open_room_location(&mut self, room: &str, location: ScriptLocation) {
+        let Some(path) = self.game_data.as_ref().and_then(|g| g.room_path(room)).map(|p| p.to_path_buf()) else {
+            return;
+        };
+        if room != self.picked_path {
+            if let Err(e) = self.load_rdt_file(&path) {
+                self.show_error(format!("Error loading file: {}", e));
+                return;
+            }
+        }
+        self.jump_to_location(location);
+    }
+
+    /// Shows the script containing the given instruction and scrolls to it
+    fn jump_to_location(&mut self, location: ScriptLocation) {
+        match location.script {
+            ScriptKind::Init => self.switch_to_init_script(),
+            ScriptKind::Main => self.switch_to_main_script(),
+        }
+        self.ui_state.selected_location = Some(location);
+        self.ui_state.scroll_to_selected = true;
+    }
+
+    /// Function and line of the selected instruction, if it belongs to the displayed script
+    fn selected_code_line(&self) -> Option<(usize, usize)> {
+        let location = self.ui_state.selected_location?;
+        // Line 0 of each function is the "Start Function" header
+        (location.script == self.displayed_script()).then_some((location.function, location.instruction + 1))
+    }
+
+    /// Function and line the debugger is paused at, if it belongs to the displayed script
+    fn current_code_line(&self) -> Option<(usize, usize)> {
+        let location = self.vm.as_ref()?.current_task()?.location()?;
+        (location.script == self.displayed_script()).then_some((location.function, location.instruction + 1))
+    }
+
+    /// The script shown in the code panel
+    fn displayed_script(&self) -> ScriptKind {
+        if self.ui_state.button_code_init_enabled {
+            ScriptKind::Main
+        } else {
+            ScriptKind::Init
+        }
+    }
+
+    /// Loads a name table file on top of the built-in tables and reformats the open scripts
+    fn load_name_tables(&mut self, path: Option<std::path::PathBuf>) -> Result<(), String> {
+        let mut name_tables = match path {
+            Some(ref path) => NameTables::load_file(path)?,
+            None => NameTables::builtin(),
+        };
+        name_tables.symbols = std::mem::take(&mut self.name_tables.symbols);
+        self.name_tables = name_tables;
+        self.name_table_path = path;
+        self.refresh_code_view();
+        Ok(())
+    }
+
+    /// Formats the open scripts again with the current names
+    fn refresh_code_view(&mut self) {
+        if let Some(ref mut file_data) = self.file_data {
+            file_data.refresh_scripts(&self.name_tables);
+            if !self.ui_state.button_code_init_enabled {
+                self.switch_to_init_script();
+            } else if !self.ui_state.button_code_main_enabled {
+                self.switch_to_main_script();
+            }
+        }
+        self.ui_state.diff.refresh();
+    }
+
+    /// Renders the symbol dictionary window
+    fn render_symbols(&mut self, ctx: &egui::Context) {
+        let mut open = self.ui_state.show_symbols;
+        let mut changed = false;
+        let mut import = false;
+        let mut export = false;
+        egui::Window::new(SYMBOLS_HEADING)
+            .open(&mut open)
+            .default_size([400.0, 400.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    import = ui.button("Import…").on_hover_text("Add the names of a dictionary file").clicked();
+                    export = ui.button("Export…").clicked();
+                });
+                changed = self.ui_state.symbol_editor.show(ui, &mut self.name_tables.symbols);
+            });
+        self.ui_state.show_symbols = open;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if import {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("Symbol dictionary", &["json"])
+                    .pick_file()
+                {
+                    match SymbolDictionary::load_file(&path) {
+                        Ok(symbols) => {
+                            self.name_tables.symbols.merge(symbols);
+                            changed = true;
+                        }
+                        Err(e) => self.show_error(format!("Error importing symbols: {}", e)),
+                    }
+                }
+            }
+            if export {
+                self.save_text_file("Symbol dictionary", "json", self.name_tables.symbols.to_json());
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = (import, export);
+
+        if changed {
+            self.refresh_code_view();
+        }
+    }
+
+    /// Renders the reads and writes of the flag or variable picked in the code view
+    fn render_references(&mut self, ctx: &egui::Context) {
+        let mut open = self.ui_state.show_references;
+        let mut clicked_row = None;
+        egui::Window::new(REFERENCES_HEADING)
+            .open(&mut open)
+            .default_size([600.0, 300.0])
+            .show(ctx, |ui| match (&self.game_data, self.ui_state.reference_target) {
+                (Some(game_data), Some(target)) => {
+                    let references = game_data.xref.find(target);
+                    ui.label(format!("{}: {}", target.label(&self.name_tables.symbols), XrefReference::summary(&references)));
+                    let rows: Vec<Vec<String>> = references
+                        .iter()
+                        .map(|r| {
+                            let mut row = r.table_row();
+                            row[0] = self.name_tables.room_label(&r.room);
+                            row
+                        })
+                        .collect();
+                    let sort = &mut self.ui_state.references_sort;
+                    clicked_row = show_sortable_table(ui, REFERENCES_HEADING, XrefReference::HEADERS, &rows, sort)
+                        .map(|row| (references[row].room.clone(), references[row].source));
+                }
+                (None, _) => {
+                    ui.label("Use File → Open game directory… to search every room");
+                }
+                (_, None) => {
+                    ui.label("Right-click a flag or variable in the code and pick Find references");
+                }
+            });
+        self.ui_state.show_references = open;
+
+        if let Some((room, location)) = clicked_row {
+            self.open_room_location(&room, location);
+        }
+    }
+
+    /// Renders the debugger window and runs the picked command
+    fn render_debugger(&mut self, ctx: &egui::Context) {
+        let mut open = self.ui_state.show_debugger;
+        let mut action = None;
+        egui::Window::new(DEBUGGER_HEADING)
+            .open(&mut open)
+            .default_size([450.0, 500.0])
+            .show(ctx, |ui| {
+                if self.file_data.is_none() {
+                    ui.label("Open an RDT file to run its scripts");
+                    return;
+                }
+                action = self.ui_state.debugger.show(ui, self.vm.as_mut(), &self.name_tables.symbols);
+            });
+        self.ui_state.show_debugger = open;
+
+        match action {
+            Some(DebuggerAction::Start) => {
+                if let Some(ref file_data) = self.file_data {
+                    let vm = Vm::new(file_data, GameState::default(), self.name_tables.clone());
+                    self.ui_state.debugger.status = "Paused before the first instruction".to_string();
+                    self.vm = Some(vm);
+                    self.reveal_current_instruction();
+                }
+            }
+            Some(DebuggerAction::Stop) => {
+                self.vm = None;
+                self.ui_state.debugger.status.clear();
+            }
+            Some(DebuggerAction::Run(mode)) => {
+                if let Some(ref mut vm) = self.vm {
+                    let reason = vm.run_until(mode, &self.ui_state.debugger.breakpoints);
+                    self.ui_state.debugger.set_status(reason, vm);
+                    self.reveal_current_instruction();
+                }
+            }
+            Some(DebuggerAction::Reveal(location)) => self.jump_to_location(location),
+            None => {}
+        }
+    }
+
+    /// Renders the lint problems of the open room below the code panel
+    fn render_problems_panel(&mut self, ctx: &egui::Context) {
+        if !self.ui_state.show_problems {
+            return;
+        }
+        let Some(ref file_data) = self.file_data else {
+            return;
+        };
+        let config = &self.ui_state.problems.config;
+        let problems = self.problems.get_or_insert_with(|| lint_room(file_data, config));
+
+        let mut response = None;
+        egui::TopBottomPanel::bottom("problems_panel").resizable(true).show(ctx, |ui| {
+            ui.heading(PROBLEMS_HEADING);
+            response = Some(self.ui_state.problems.show(ui, problems));
+        });
+
+        let Some(response) = response else {
+            return;
+        };
+        if response.config_changed {
+            self.problems = None;
+        }
+        if let Some(location) = response.clicked {
+            self.jump_to_location(location);
+        }
+    }
+
+    /// Renders the room state window
+    fn render_room_state(&mut self, ctx: &egui::Context) {
+        let mut open = self.ui_state.show_room_state;
+        egui::Window::new(ROOM_STATE_HEADING)
+            .open(&mut open)
+            .default_size([600.0, 500.0])
+            .show(ctx, |ui| match self.file_data {
+                Some(ref file_data) => self.ui_state.room_state.show(ui, file_data, &self.name_tables),
+                None => {
+                    ui.label("Open an RDT file to evaluate its init script");
+                }
+            });
+        self.ui_state.show_room_state = open;
+    }
+
+    /// Renders the script differences between the open room and the compared one
+    fn render_diff(&mut self, ctx: &egui::Context) {
+        let mut open = self.ui_state.show_diff;
+        egui::Window::new(DIFF_HEADING)
+            .open(&mut open)
+            .default_size([900.0, 600.0])
+            .show(ctx, |ui| match self.file_data {
+                Some(ref file_data) => self.ui_state.diff.show(ui, &self.picked_path, file_data, &self.name_tables),
+                None => {
+                    ui.label("Open an RDT file to compare its scripts");
+                }
+            });
+        self.ui_state.show_diff = open;
+    }
+
+    /// Renders the conflicts of the last merge and saves the merged room
+    fn render_merge(&mut self, ctx: &egui::Context) {
+        let mut open = self.ui_state.show_merge;
+        let mut save = false;
+        egui::Window::new(MERGE_HEADING)
+            .open(&mut open)
+            .default_size([900.0, 600.0])
+            .show(ctx, |ui| save = self.ui_state.merge.show(ui, &self.name_tables));
+        self.ui_state.show_merge = open;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if save {
+            let Some(path) = rfd::FileDialog::new().add_filter("RDT Files", &["rdt"]).save_file() else {
+                return;
+            };
+            let result = self
+                .ui_state
+                .merge
+                .write()
+                .and_then(|(bytes, warnings)| std::fs::write(&path, bytes).map(|_| warnings).map_err(|e| e.to_string()));
+            match result {
+                Ok(warnings) if warnings.is_empty() => self.show_toast(format!("Saved {}", path.display())),
+                Ok(warnings) => self.show_toast(format!("Saved {}: {}", path.display(), warnings.join(", "))),
+                Err(e) => self.show_error(format!("Error saving file: {}", e)),
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = save;
+    }
+
+    /// Shows the script the debugger is paused in and scrolls to the paused instruction
+    fn reveal_current_instruction(&mut self) {
+        let Some(location) = self.vm.as_ref().and_then(|vm| vm.current_task()?.location()) else {
+            return;
+        };
+        if location.script != self.displayed_script() {
+            match location.script {
+                ScriptKind::Init => self.switch_to_init_script(),
+                ScriptKind::Main => self.switch_to_main_script(),
+            }
+        }
+        self.ui_state.scroll_to_current = true;
+    }
+
+    /// Opens the symbol dictionary to name a flag or variable picked in the code view
+    fn name_symbol(&mut self, kind: SymbolKind) {
+        self.ui_state.symbol_editor.prefill(kind, &self.name_tables.symbols);
+        self.ui_state.show_symbols = true;
+    }
+
     /// Switches to the init script and updates button states
     fn switch_to_init_script(&mut self) {
         if let Some(ref file_data) = self.file_data {
@@ -283,7 +1227,13 @@ impl TemplateApp {
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
         if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+            let mut app: Self = eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+            app.name_tables.symbols = std::mem::take(&mut app.symbols);
+            if let Err(e) = app.load_name_tables(app.name_table_path.clone()) {
+                app.name_table_path = None;
+                app.show_error(format!("Error loading name tables: {}", e));
+            }
+            return app;
         }
 
         Default::default()
@@ -291,27 +1241,38 @@ impl TemplateApp {
 }
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
-#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Default, serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct TemplateApp {
     file_data: Option<RdtFileData>,
     picked_path: String,
     ui_state: UiState,
-}
-
-impl Default for TemplateApp {
-    fn default() -> Self {
-        Self {
-            file_data: None,
-            picked_path: String::new(),
-            ui_state: UiState::default(),
-        }
-    }
+    /// User name table file applied on top of the built-in tables
+    name_table_path: Option<std::path::PathBuf>,
+    #[serde(skip)]
+    name_tables: NameTables,
+    /// Persisted copy of `name_tables.symbols`
+    symbols: SymbolDictionary,
+    /// Path and contents of the open room, rewritten by the code editor
+    #[serde(skip)]
+    rdt_file: Option<(std::path::PathBuf, Vec<u8>)>,
+    #[serde(skip)]
+    game_data: Option<GameData>,
+    /// Script machine of the debugger, None when it is stopped
+    #[serde(skip)]
+    vm: Option<Vm>,
+    /// Path conditions of the open room, computed when the code panel is first drawn
+    #[serde(skip)]
+    reachability: Option<Reachability>,
+    /// Lint results of the open room, computed when the problems panel is first drawn
+    #[serde(skip)]
+    problems: Option<Vec<Problem>>,
 }
 
 impl eframe::App for TemplateApp {
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.symbols = self.name_tables.symbols.clone();
         eframe::set_value(storage, eframe::APP_KEY, self);
     }
 
@@ -323,39 +1284,173 @@ impl eframe::App for TemplateApp {
 
         self.render_top_panel(ctx, frame);
         self.render_script_panel(ctx);
+        self.render_problems_panel(ctx);
         self.render_code_panel(ctx);
         self.render_raw_panel(ctx);
+        self.render_room_map(ctx);
+        self.render_door_graph(ctx);
+        self.render_symbols(ctx);
+        self.render_references(ctx);
+        self.render_debugger(ctx);
+        self.render_room_state(ctx);
+        self.render_diff(ctx);
+        self.render_merge(ctx);
+        self.render_placement_table(
+            ctx,
+            ITEM_TABLE_HEADING,
+            "Use File → Open game directory… to list every item",
+            |g| &g.items,
+            |s| (&mut s.show_item_table, &mut s.item_table_sort),
+        );
+        self.render_placement_table(
+            ctx,
+            ENEMY_TABLE_HEADING,
+            "Use File → Open game directory… to list every enemy",
+            |g| &g.enemies,
+            |s| (&mut s.show_enemy_table, &mut s.enemy_table_sort),
+        );
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn display_code_function(
     ui: &mut egui::Ui,
     code_string: Vec<String>,
     keyword_color: egui::Color32,
     function_color: egui::Color32,
     opcode_documentation: &HashMap<String, String>,
-) {
+    markers: &LineMarkers,
+    instructions: Option<&[ScdInstruction]>,
+    names: &NameTables,
+) -> Option<CodeAction> {
+    let mut code_action = None;
     let code_iter = code_string.iter();
-    for code_line in code_iter {
-        if code_line.contains(FUNCTION_PARAM_DELIMITER) {
-            let code_line_parts: Vec<&str> = code_line.split(FUNCTION_PARAM_DELIMITER).collect();
-            ui.horizontal(|ui| {
-                // Function name should get a different color
-                let function_name = code_line_parts[0];
-                let mut text_color = function_color;
-                if KEYWORD_LIST.contains(&function_name) {
-                    text_color = keyword_color;
+    for (line_num, code_line) in code_iter.enumerate() {
+        // Line 0 is the "Start Function" header
+        let instruction = line_num.checked_sub(1).and_then(|i| instructions?.get(i));
+        ui.horizontal(|ui| {
+            if let Some(ref breakpoints) = markers.breakpoints {
+                if breakpoint_gutter(ui, instruction.is_some(), breakpoints.contains(&line_num)) {
+                    code_action = Some(CodeAction::ToggleBreakpoint(line_num - 1));
                 }
-                ui.label(egui::RichText::new(function_name).color(text_color))
-                    .on_hover_text(match opcode_documentation.get(function_name) {
-                        Some(v) => v,
-                        None => function_name,
-                    });
-                // Display function parameters
-                ui.label(FUNCTION_PARAM_DELIMITER.to_owned() + &code_line_parts[1..code_line_parts.len()].join(FUNCTION_PARAM_DELIMITER));
-            });
+            }
+            let fill = if markers.current == Some(line_num) {
+                Some(CURRENT_LINE_COLOR)
+            } else if markers.selected == Some(line_num) {
+                Some(SELECTED_LINE_COLOR)
+            } else {
+                None
+            };
+            if let Some(fill) = fill {
+                let response = egui::Frame::none()
+                    .fill(fill)
+                    .show(ui, |ui| ui.label(code_line))
+                    .response;
+                if markers.scroll_to == Some(line_num) {
+                    response.scroll_to_me(Some(egui::Align::Center));
+                }
+            } else if code_line.contains(FUNCTION_PARAM_DELIMITER) {
+                let code_line_parts: Vec<&str> = code_line.split(FUNCTION_PARAM_DELIMITER).collect();
+                ui.horizontal(|ui| {
+                    // Function name should get a different color
+                    let function_name = code_line_parts[0];
+                    let mut text_color = function_color;
+                    if KEYWORD_LIST.contains(&function_name) {
+                        text_color = keyword_color;
+                    }
+                    let mut text = egui::RichText::new(function_name).color(text_color);
+                    let mut hover = match opcode_documentation.get(function_name) {
+                        Some(v) => v.clone(),
+                        None => function_name.to_string(),
+                    };
+                    if let Some(reason) = markers.unreachable.get(&line_num) {
+                        text = text.color(UNREACHABLE_COLOR).strikethrough();
+                        hover = format!("Never runs: {}\n\n{}", reason, hover);
+                    }
+                    let response = ui.add(egui::Label::new(text).sense(egui::Sense::click()));
+                    if response.on_hover_text(hover).clicked() && instruction.is_some() {
+                        code_action = Some(CodeAction::Select(line_num - 1));
+                    }
+                    // Display function parameters, one label each so symbols can show their raw value
+                    match instruction.filter(|i| i.error.is_none() && !i.params.is_empty()) {
+                        Some(instruction) => code_action = display_named_params(ui, instruction, names).or(code_action),
+                        None => {
+                            ui.label(FUNCTION_PARAM_DELIMITER.to_owned() + &code_line_parts[1..code_line_parts.len()].join(FUNCTION_PARAM_DELIMITER));
+                        }
+                    }
+                });
+            } else {
+                ui.label(code_line);
+            }
+        });
+    }
+    code_action
+}
+
+/// Draws the breakpoint marker in front of a code line, returns true when it is clicked
+fn breakpoint_gutter(ui: &mut egui::Ui, has_instruction: bool, is_set: bool) -> bool {
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(14.0, 14.0), egui::Sense::click());
+    if !has_instruction {
+        return false;
+    }
+    if is_set {
+        ui.painter().circle_filled(rect.center(), 5.0, BREAKPOINT_COLOR);
+    } else if response.hovered() {
+        ui.painter().circle_stroke(rect.center(), 5.0, egui::Stroke::new(1.0, BREAKPOINT_COLOR));
+    }
+    response.on_hover_text("Toggle breakpoint").clicked()
+}
+
+/// Draws the raw hex lines with one line of one function highlighted
+///
+/// The lines follow the code panel: a "Start Function" header, then one line per instruction
+fn display_raw_lines(ui: &mut egui::Ui, raw_code: &str, (function, line): (usize, usize)) {
+    let mut line_in_function = 0;
+    let mut current_function = None;
+    for raw_line in raw_code.lines() {
+        if let Some(header) = raw_line.strip_prefix("Start Function ") {
+            current_function = header.trim_end_matches(':').parse::<usize>().ok();
+            line_in_function = 0;
+        }
+        if current_function == Some(function) && line_in_function == line {
+            egui::Frame::none().fill(CURRENT_LINE_COLOR).show(ui, |ui| ui.label(raw_line));
         } else {
-            ui.label(code_line);
+            ui.label(raw_line);
+        }
+        line_in_function += 1;
+    }
+}
+
+/// Draws `(field: value, ...)` with the raw value of symbolic parameters on hover
+///
+/// Flags and variables get a context menu to name them or find their references
+fn display_named_params(ui: &mut egui::Ui, instruction: &ScdInstruction, names: &NameTables) -> Option<CodeAction> {
+    let mut code_action = None;
+    ui.spacing_mut().item_spacing.x = 0.0;
+    ui.label(FUNCTION_PARAM_DELIMITER);
+    for (index, param) in names.named_params(instruction).iter().enumerate() {
+        if index > 0 {
+            ui.label(", ");
+        }
+        let mut response = ui.label(param.to_string());
+        if param.symbol.is_some() {
+            response = response.on_hover_text(format!("{} = {} (0x{:x})", param.field, param.value, param.value));
+        }
+        if let Some(kind) = param.symbol_kind {
+            response.context_menu(|ui| {
+                if ui.button("Name in symbol dictionary…").clicked() {
+                    code_action = Some(CodeAction::NameSymbol(kind));
+                    ui.close_menu();
+                }
+                if let Some(target) = XrefTarget::from_symbol(kind) {
+                    if ui.button("Find references").clicked() {
+                        code_action = Some(CodeAction::FindReferences(target));
+                        ui.close_menu();
+                    }
+                }
+            });
         }
     }
+    ui.label(")");
+    code_action
 }
@@ -0,0 +1,256 @@
+use crate::app::{FUNCTION_COLOR, KEYWORD_COLOR, KEYWORD_LIST};
+use bio2_scd::assembler::{Assembler, AssemblyError, RAW_BYTES};
+use bio2_scd::fileio::name_tables::NameTables;
+use bio2_scd::fileio::rdt_scd::ScdFunction;
+use bio2_scd::fileio::room_layout::ScriptKind;
+use bio2_scd::fileio::symbols::parse_number;
+use bio2_scd::writer::{keep_block_slack, placed_instructions, PlacedInstruction};
+use egui::text::LayoutJob;
+use std::collections::HashMap;
+
+const NUMBER_COLOR: egui::Color32 = egui::Color32::from_rgb(209, 154, 102);
+const LABEL_COLOR: egui::Color32 = egui::Color32::from_rgb(152, 195, 121);
+const COMMENT_COLOR: egui::Color32 = egui::Color32::from_rgb(128, 128, 128);
+const ERROR_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 100, 100);
+const WARNING_COLOR: egui::Color32 = egui::Color32::from_rgb(220, 180, 80);
+
+/// Text of one function as it is being edited
+#[derive(Clone)]
+struct EditedFunction {
+    text: String,
+    /// Lines the assembler rejected, empty when the text is written into the room
+    errors: Vec<AssemblyError>,
+}
+
+/// The functions of one script as editable pseudocode
+#[derive(Clone)]
+struct EditSession {
+    script: ScriptKind,
+    functions: Vec<EditedFunction>,
+    /// Warnings of the last write into the room
+    warnings: Vec<String>,
+}
+
+/// Editable pseudocode of the displayed script, assembled back into the room as it is typed
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct CodeEditor {
+    #[serde(skip)]
+    session: Option<EditSession>,
+}
+
+impl CodeEditor {
+    /// Whether the editor holds the text of the given script
+    pub fn is_open_for(&self, script: ScriptKind) -> bool {
+        self.session.as_ref().map_or(false, |session| session.script == script)
+    }
+
+    /// Starts editing a script, one text per function written so that it assembles to the same bytes
+    pub fn open(&mut self, script: ScriptKind, functions: &[ScdFunction], names: &NameTables) {
+        let assembler = Assembler::new(names);
+        let functions = functions
+            .iter()
+            .map(|function| {
+                let lines: Vec<String> = function.instructions.iter().map(|i| assembler.disassemble(i)).collect();
+                EditedFunction {
+                    text: lines.join("\n"),
+                    errors: Vec::new(),
+                }
+            })
+            .collect();
+        self.session = Some(EditSession {
+            script,
+            functions,
+            warnings: Vec::new(),
+        });
+    }
+
+    /// Shows the warnings of writing the last edit into the room
+    pub fn set_warnings(&mut self, warnings: Vec<String>) {
+        if let Some(ref mut session) = self.session {
+            session.warnings = warnings;
+        }
+    }
+
+    /// Draws an editor per function
+    ///
+    /// Returns the function whose text changed and now assembles, with the instructions to write
+    /// for it. Blocks the edit kept keep the slack they have in `functions`, the decoded script.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        functions: &[ScdFunction],
+        names: &NameTables,
+        opcode_documentation: &HashMap<String, String>,
+    ) -> Option<(usize, Vec<PlacedInstruction>)> {
+        let session = self.session.as_mut()?;
+        if functions.is_empty() {
+            ui.label("The functions of this script could not be decoded");
+            return None;
+        }
+        for warning in &session.warnings {
+            ui.colored_label(WARNING_COLOR, format!("⚠ {}", warning));
+        }
+
+        let assembler = Assembler::new(names);
+        let mut edit = None;
+        for (index, function) in session.functions.iter_mut().enumerate() {
+            let mut title = egui::RichText::new(format!("Function {}", index));
+            if !function.errors.is_empty() {
+                title = title.color(ERROR_COLOR);
+            }
+            let changed = egui::CollapsingHeader::new(title)
+                .id_source((session.script.name(), index))
+                .default_open(true)
+                .show(ui, |ui| code_editor(ui, function, opcode_documentation))
+                .body_returned
+                .unwrap_or_default();
+            if !changed {
+                continue;
+            }
+            match assembler.assemble_function(&function.text) {
+                Ok(assembled) => {
+                    function.errors.clear();
+                    let mut instructions = placed_instructions(&assembled);
+                    if let Some(original) = functions.get(index) {
+                        keep_block_slack(&placed_instructions(original), &mut instructions);
+                    }
+                    edit = Some((index, instructions));
+                }
+                Err(errors) => function.errors = errors,
+            }
+        }
+        edit
+    }
+}
+
+/// Draws the text of a function with line numbers and the assembler errors underlined
+///
+/// Returns true when the text was changed
+fn code_editor(ui: &mut egui::Ui, function: &mut EditedFunction, opcode_documentation: &HashMap<String, String>) -> bool {
+    let font = egui::TextStyle::Monospace.resolve(ui.style());
+    let line_count = function.text.lines().count().max(1);
+    let digit_width = ui.fonts(|f| f.glyph_width(&font, '0'));
+    let gutter_width = digit_width * (line_count.to_string().len() as f32 + 1.0);
+
+    let mut layouter = |ui: &egui::Ui, text: &str, _wrap_width: f32| {
+        let mut job = highlight(text, &font, ui.visuals().text_color());
+        job.wrap.max_width = f32::INFINITY;
+        ui.fonts(|f| f.layout_job(job))
+    };
+    let (gutter, output) = ui
+        .horizontal_top(|ui| {
+            let (gutter, _) = ui.allocate_exact_size(egui::vec2(gutter_width, 0.0), egui::Sense::hover());
+            let output = egui::TextEdit::multiline(&mut function.text)
+                .code_editor()
+                .desired_rows(1)
+                .desired_width(f32::INFINITY)
+                .layouter(&mut layouter)
+                .show(ui);
+            (gutter, output)
+        })
+        .inner;
+
+    // Rows are lines since nothing wraps; the text ending in a newline adds an empty last row
+    let painter = ui.painter();
+    let hover = output.response.hover_pos();
+    let mut hovered_line = None;
+    for (line, row) in output.galley.rows.iter().enumerate() {
+        let rect = row.rect.translate(output.text_draw_pos.to_vec2());
+        painter.text(
+            egui::pos2(gutter.right(), rect.top()),
+            egui::Align2::RIGHT_TOP,
+            (line + 1).to_string(),
+            font.clone(),
+            ui.visuals().weak_text_color(),
+        );
+        if function.errors.iter().any(|error| error.line == line) {
+            squiggle(painter, rect.left(), rect.right().max(rect.left() + digit_width), rect.bottom());
+        }
+        if hover.map_or(false, |pos| pos.y >= rect.top() && pos.y < rect.bottom()) {
+            hovered_line = Some(line);
+        }
+    }
+    if let Some(line) = hovered_line {
+        let code = function.text.lines().nth(line).unwrap_or_default();
+        let messages: Vec<&str> = function
+            .errors
+            .iter()
+            .filter(|error| error.line == line)
+            .map(|error| error.message.as_str())
+            .collect();
+        let docs = code.split_once('(').and_then(|(name, _)| opcode_documentation.get(name.trim()));
+        if !messages.is_empty() || docs.is_some() {
+            egui::show_tooltip_at_pointer(ui.ctx(), output.response.id.with("line"), |ui| {
+                for message in messages {
+                    ui.colored_label(ERROR_COLOR, message);
+                }
+                if let Some(docs) = docs {
+                    ui.label(docs);
+                }
+            });
+        }
+    }
+
+    for error in &function.errors {
+        ui.colored_label(ERROR_COLOR, format!("Line {}: {}", error.line + 1, error.message));
+    }
+    output.response.changed()
+}
+
+/// Draws a wavy line under the text between two x positions
+fn squiggle(painter: &egui::Painter, left: f32, right: f32, y: f32) {
+    const STEP: f32 = 3.0;
+    let points = (0..=((right - left) / STEP) as usize)
+        .map(|step| {
+            let offset = if step % 2 == 0 { 0.0 } else { -STEP / 2.0 };
+            egui::pos2(left + step as f32 * STEP, y + offset)
+        })
+        .collect();
+    painter.add(egui::Shape::line(points, egui::Stroke::new(1.0, ERROR_COLOR)));
+}
+
+/// Colours the lines of a function: instruction names, block keywords, parameter names,
+/// numbers and comments
+///
+/// Follows the syntax of [`Assembler`], so a line that is not `Name(...)` is left plain
+fn highlight(text: &str, font: &egui::FontId, text_color: egui::Color32) -> LayoutJob {
+    let mut job = LayoutJob::default();
+    let mut append = |text: &str, color: egui::Color32| {
+        job.append(text, 0.0, egui::TextFormat::simple(font.clone(), color));
+    };
+    for line in text.split_inclusive('\n') {
+        if line.trim_start().starts_with(';') {
+            append(line, COMMENT_COLOR);
+            continue;
+        }
+        let Some((name, args)) = line.split_once('(') else {
+            append(line, text_color);
+            continue;
+        };
+        let name_color = match name.trim() {
+            name if KEYWORD_LIST.contains(&name) => KEYWORD_COLOR,
+            _ => FUNCTION_COLOR,
+        };
+        append(name, name_color);
+        append("(", text_color);
+
+        // Each value is coloured by the delimiter that ends it, a ':' making it a parameter name
+        let is_raw = name.trim() == RAW_BYTES;
+        let mut start = 0;
+        for (position, delimiter) in args.match_indices([',', ':', '(', ')', '\n']) {
+            let value = &args[start..position];
+            let color = if delimiter == ":" {
+                LABEL_COLOR
+            } else if is_raw || parse_number(value).is_some() {
+                NUMBER_COLOR
+            } else {
+                text_color
+            };
+            append(value, color);
+            append(delimiter, text_color);
+            start = position + delimiter.len();
+        }
+        append(&args[start..], text_color);
+    }
+    job
+}
@@ -0,0 +1,223 @@
+use bio2_scd::analysis::xref::XrefTarget;
+use bio2_scd::fileio::room_layout::ScriptLocation;
+use bio2_scd::fileio::symbols::SymbolDictionary;
+use bio2_scd::vm::debugger::{StepMode, StopReason};
+use bio2_scd::vm::machine::Vm;
+use bio2_scd::vm::state::GameState;
+use std::collections::HashSet;
+
+/// Most recent world-state log entries shown in the debugger window
+const LOG_LINES: usize = 200;
+
+/// Breakpoints and watched flags of the debugger window
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct DebuggerView {
+    pub breakpoints: HashSet<ScriptLocation>,
+    watches: Vec<XrefTarget>,
+    new_watch: String,
+    /// Outcome of the last run command
+    pub status: String,
+}
+
+/// Command picked in the debugger window
+pub enum DebuggerAction {
+    Start,
+    Stop,
+    Run(StepMode),
+    /// Show the next instruction of a task in the code panel
+    Reveal(ScriptLocation),
+}
+
+impl DebuggerView {
+    /// Keeps the watches for another room, breakpoints belong to the old one
+    pub fn for_new_room(&self) -> Self {
+        Self {
+            watches: self.watches.clone(),
+            ..Default::default()
+        }
+    }
+
+    pub fn toggle_breakpoint(&mut self, location: ScriptLocation) {
+        if !self.breakpoints.remove(&location) {
+            self.breakpoints.insert(location);
+        }
+    }
+
+    /// Describes why the machine paused
+    pub fn set_status(&mut self, reason: StopReason, vm: &Vm) {
+        self.status = match reason {
+            StopReason::Step => format!("Paused at frame {}", vm.frame),
+            StopReason::Breakpoint(location) => format!(
+                "Breakpoint at {} function {} line {}, frame {}",
+                location.script.name(),
+                location.function,
+                location.instruction,
+                vm.frame
+            ),
+            StopReason::Finished => format!("Every task ended after {} frames", vm.frame),
+            StopReason::FrameLimit => format!("Paused after running to frame {}", vm.frame),
+        };
+    }
+
+    /// Draws the run controls, tasks, watches and log
+    pub fn show(&mut self, ui: &mut egui::Ui, vm: Option<&mut Vm>, symbols: &SymbolDictionary) -> Option<DebuggerAction> {
+        let mut action = None;
+        let Some(vm) = vm else {
+            ui.label("Runs the init script, then the main script, from a cleared game state. Click the gutter of a code line to set a breakpoint.");
+            if ui.button("▶ Start").clicked() {
+                action = Some(DebuggerAction::Start);
+            }
+            ui.label(format!("{} breakpoints", self.breakpoints.len()));
+            return action;
+        };
+
+        ui.horizontal(|ui| {
+            if ui.button("⟲ Restart").clicked() {
+                action = Some(DebuggerAction::Start);
+            }
+            if ui.button("⏹ Stop").clicked() {
+                action = Some(DebuggerAction::Stop);
+            }
+            ui.separator();
+            ui.add_enabled_ui(!vm.is_finished(), |ui| {
+                for (label, mode, hint) in [
+                    ("Step into", StepMode::Into, "Run one instruction"),
+                    ("Step over", StepMode::Over, "Run to the next instruction of this function, past GoSub calls and sleeps"),
+                    ("Step out", StepMode::Out, "Run until the current function returns"),
+                    ("Continue ▶", StepMode::Continue, "Run until a breakpoint is reached"),
+                ] {
+                    if ui.button(label).on_hover_text(hint).clicked() {
+                        action = Some(DebuggerAction::Run(mode));
+                    }
+                }
+            });
+        });
+        ui.horizontal(|ui| {
+            ui.label(&self.status);
+            if !self.breakpoints.is_empty() && ui.small_button(format!("Clear {} breakpoints", self.breakpoints.len())).clicked() {
+                self.breakpoints.clear();
+            }
+        });
+        ui.separator();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::CollapsingHeader::new("Tasks").default_open(true).show(ui, |ui| {
+                if let Some(reveal) = show_tasks(ui, vm) {
+                    action = Some(reveal);
+                }
+            });
+            egui::CollapsingHeader::new("Watch").default_open(true).show(ui, |ui| {
+                self.show_watches(ui, &mut vm.state, symbols);
+            });
+            egui::CollapsingHeader::new("Game state").show(ui, |ui| {
+                show_state(ui, &mut vm.state, symbols);
+            });
+            egui::CollapsingHeader::new(format!("World log ({})", vm.log.len())).show(ui, |ui| {
+                for event in &vm.log[vm.log.len().saturating_sub(LOG_LINES)..] {
+                    ui.monospace(event.to_string());
+                }
+            });
+        });
+        action
+    }
+
+    fn show_watches(&mut self, ui: &mut egui::Ui, state: &mut GameState, symbols: &SymbolDictionary) {
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.new_watch).hint_text("flag:1:5 or var:ROOM_STATE"));
+            let target = XrefTarget::parse(self.new_watch.trim(), symbols);
+            if ui.add_enabled(target.is_ok(), egui::Button::new("Watch")).clicked() {
+                if let Ok(target) = target {
+                    if !self.watches.contains(&target) {
+                        self.watches.push(target);
+                    }
+                    self.new_watch.clear();
+                }
+            }
+        });
+        let mut removed = None;
+        egui::Grid::new("debugger_watches").striped(true).show(ui, |ui| {
+            for (index, target) in self.watches.iter().enumerate() {
+                ui.label(target.label(symbols));
+                value_editor(ui, state, *target);
+                if ui.button("🗑").on_hover_text("Remove").clicked() {
+                    removed = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(index) = removed {
+            self.watches.remove(index);
+        }
+    }
+}
+
+/// Lists the running tasks, returns the location of a clicked task
+fn show_tasks(ui: &mut egui::Ui, vm: &Vm) -> Option<DebuggerAction> {
+    let mut action = None;
+    let current = vm.current_task().map(|task| task.id);
+    egui::Grid::new("debugger_tasks").striped(true).show(ui, |ui| {
+        for header in ["", "Task", "Slot", "Next instruction", "Depth", "Sleep"] {
+            ui.strong(header);
+        }
+        ui.end_row();
+        for task in &vm.tasks {
+            ui.label(if current == Some(task.id) { "▶" } else { "" });
+            ui.label(task.id.to_string());
+            ui.label(task.slot.to_string());
+            match task.location() {
+                Some(location) => {
+                    let text = format!("{} {}:{}", location.script.name(), location.function, location.instruction);
+                    if ui.link(text).clicked() {
+                        action = Some(DebuggerAction::Reveal(location));
+                    }
+                }
+                None => {
+                    ui.label("");
+                }
+            }
+            ui.label(task.frames.len().to_string());
+            ui.label(task.sleep.map(|s| s.to_string()).unwrap_or_default());
+            ui.end_row();
+        }
+    });
+    action
+}
+
+/// Lists every set flag and non-zero variable
+fn show_state(ui: &mut egui::Ui, state: &mut GameState, symbols: &SymbolDictionary) {
+    let flags = state
+        .flags
+        .iter()
+        .flat_map(|(&array, bits)| bits.iter().map(move |&bit| XrefTarget::Flag(array, bit)));
+    let variables = state.variables.keys().map(|&var_id| XrefTarget::Variable(var_id));
+    let members = state.members.keys().map(|&member| XrefTarget::Member(member));
+    let targets: Vec<XrefTarget> = flags.chain(variables).chain(members).collect();
+    if let Some(camera) = state.camera {
+        ui.label(format!("Camera {}", camera));
+    }
+    egui::Grid::new("debugger_state").striped(true).show(ui, |ui| {
+        for target in targets {
+            ui.label(target.label(symbols));
+            value_editor(ui, state, target);
+            ui.end_row();
+        }
+    });
+}
+
+/// Checkbox for flags, number field for variables and members
+fn value_editor(ui: &mut egui::Ui, state: &mut GameState, target: XrefTarget) {
+    let mut value = state.value(target);
+    let changed = match target {
+        XrefTarget::Flag(..) => {
+            let mut set = value != 0;
+            let changed = ui.checkbox(&mut set, "").changed();
+            value = set as i32;
+            changed
+        }
+        XrefTarget::Variable(_) => ui.add(egui::DragValue::new(&mut value).clamp_range(i16::MIN..=i16::MAX)).changed(),
+        XrefTarget::Member(_) => ui.add(egui::DragValue::new(&mut value)).changed(),
+    };
+    if changed {
+        state.set_value(target, value);
+    }
+}
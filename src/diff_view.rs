@@ -0,0 +1,126 @@
+use bio2_scd::analysis::script_diff::{DiffLine, FunctionDiff, LineChange, RoomDiff};
+use bio2_scd::file_handler::RdtFileData;
+use bio2_scd::fileio::name_tables::NameTables;
+use bio2_scd::fileio::room_layout::ScriptKind;
+
+const ADDED_COLOR: egui::Color32 = egui::Color32::from_rgb(100, 200, 100);
+const REMOVED_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 100, 100);
+const CHANGED_COLOR: egui::Color32 = egui::Color32::from_rgb(220, 180, 80);
+
+/// Room compared with the open one and the function shown side by side
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct DiffView {
+    /// Hide the instructions that are the same in both rooms
+    only_changes: bool,
+    /// Index into the changed functions
+    selected: usize,
+    #[serde(skip)]
+    other: Option<(String, RdtFileData)>,
+    #[serde(skip)]
+    diff: Option<RoomDiff>,
+}
+
+impl DiffView {
+    /// Keeps the compared room for another open room, the differences belong to the old one
+    pub fn for_new_room(&self) -> Self {
+        Self {
+            only_changes: self.only_changes,
+            selected: 0,
+            other: self.other.clone(),
+            diff: None,
+        }
+    }
+
+    /// Sets the room to compare the open one with
+    pub fn compare_with(&mut self, name: String, file_data: RdtFileData) {
+        self.other = Some((name, file_data));
+        self.selected = 0;
+        self.diff = None;
+    }
+
+    /// Drops the differences so they are shown with the current names
+    pub fn refresh(&mut self) {
+        self.diff = None;
+    }
+
+    /// Draws the list of changed functions and the selected one with the open room on the left
+    pub fn show(&mut self, ui: &mut egui::Ui, room: &str, file_data: &RdtFileData, names: &NameTables) {
+        let Some((other_name, other)) = &self.other else {
+            ui.label("Use File → Compare with… to pick the room to compare with");
+            return;
+        };
+        let diff = self
+            .diff
+            .get_or_insert_with(|| RoomDiff::new(file_data, other, names, &[ScriptKind::Init, ScriptKind::Main]));
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{} ⇄ {}", room, other_name));
+            ui.separator();
+            let (inserted, removed, changed) = diff.counts();
+            ui.colored_label(ADDED_COLOR, format!("+{}", inserted));
+            ui.colored_label(REMOVED_COLOR, format!("-{}", removed));
+            ui.colored_label(CHANGED_COLOR, format!("~{}", changed));
+            ui.separator();
+            ui.checkbox(&mut self.only_changes, "Only changes");
+        });
+        ui.separator();
+
+        let functions: Vec<&FunctionDiff> = diff.changed().collect();
+        if functions.is_empty() {
+            ui.label("The scripts of both rooms are the same");
+            return;
+        }
+        self.selected = self.selected.min(functions.len() - 1);
+
+        egui::SidePanel::left("diff_functions").resizable(true).show_inside(ui, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (index, function) in functions.iter().enumerate() {
+                    let label = format!("{} {} ({})", function.script.name(), function.function, function.status);
+                    if ui.selectable_label(self.selected == index, label).clicked() {
+                        self.selected = index;
+                    }
+                }
+            });
+        });
+
+        let function = functions[self.selected];
+        egui::ScrollArea::both().show(ui, |ui| {
+            egui::Grid::new("diff_lines").striped(true).show(ui, |ui| {
+                ui.strong("");
+                ui.strong(room);
+                ui.strong("");
+                ui.strong(other_name);
+                ui.end_row();
+                for line in &function.lines {
+                    if self.only_changes && line.change == LineChange::Same {
+                        continue;
+                    }
+                    show_line(ui, line);
+                    ui.end_row();
+                }
+            });
+        });
+    }
+}
+
+/// Both sides of an aligned line, with the changed parameters as hover text
+fn show_line(ui: &mut egui::Ui, line: &DiffLine) {
+    let (old_color, new_color) = match line.change {
+        LineChange::Same => (None, None),
+        LineChange::Inserted => (None, Some(ADDED_COLOR)),
+        LineChange::Removed => (Some(REMOVED_COLOR), None),
+        LineChange::Changed => (Some(CHANGED_COLOR), Some(CHANGED_COLOR)),
+    };
+    let changes: Vec<String> = line.params.iter().map(|param| param.to_string()).collect();
+    for (index, code, color) in [(line.old, &line.old_code, old_color), (line.new, &line.new_code, new_color)] {
+        ui.weak(index.map(|i| i.to_string()).unwrap_or_default());
+        let mut text = egui::RichText::new(code.as_deref().unwrap_or_default()).monospace();
+        if let Some(color) = color {
+            text = text.color(color);
+        }
+        let response = ui.label(text);
+        if !changes.is_empty() {
+            response.on_hover_text(changes.join("\n"));
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use bio2_scd::analysis::door_graph::DoorGraph;
+use bio2_scd::fileio::name_tables::NameTables;
+use bio2_scd::fileio::room_id::RoomId;
+use std::collections::BTreeMap;
+
+// UI Constants
+const NODE_SIZE: egui::Vec2 = egui::vec2(90.0, 26.0);
+const NODE_SPACING: egui::Vec2 = egui::vec2(120.0, 70.0);
+const GRAPH_MARGIN: f32 = 20.0;
+const NODE_COLOR: egui::Color32 = egui::Color32::from_rgb(50, 60, 80);
+const CURRENT_NODE_COLOR: egui::Color32 = egui::Color32::from_rgb(60, 110, 60);
+const MISSING_NODE_COLOR: egui::Color32 = egui::Color32::from_rgb(120, 40, 40);
+const EDGE_COLOR: egui::Color32 = egui::Color32::from_rgb(140, 140, 160);
+const LOCKED_EDGE_COLOR: egui::Color32 = egui::Color32::from_rgb(230, 190, 60);
+const MISSING_EDGE_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 90, 90);
+
+/// Draws the door graph with one row per stage and player, returns the clicked room
+pub fn show_door_graph(ui: &mut egui::Ui, graph: &DoorGraph, current_room: &str, names: &NameTables) -> Option<String> {
+    let missing: Vec<&str> = graph.missing_targets().map(|e| e.to.as_str()).collect();
+    ui.label(format!(
+        "{} rooms, {} doors, {} doors to missing rooms",
+        graph.rooms.len(),
+        graph.edges.len(),
+        missing.len()
+    ));
+
+    // Group rooms into rows keyed by (player, stage)
+    let mut rows: BTreeMap<(u8, u8), Vec<&str>> = BTreeMap::new();
+    for name in graph.rooms.iter().map(|s| s.as_str()).chain(missing.iter().copied()) {
+        let id = RoomId::parse(name).unwrap_or(RoomId { stage: 0xF, room: 0, player: 0 });
+        let row = rows.entry((id.player, id.stage)).or_default();
+        if !row.contains(&name) {
+            row.push(name);
+        }
+    }
+
+    let columns = rows.values().map(|r| r.len()).max().unwrap_or(0) as f32;
+    let size = egui::vec2(
+        columns * NODE_SPACING.x + 2.0 * GRAPH_MARGIN,
+        rows.len() as f32 * NODE_SPACING.y + 2.0 * GRAPH_MARGIN,
+    );
+
+    let mut clicked = None;
+    egui::ScrollArea::both().show(ui, |ui| {
+        let (response, painter) = ui.allocate_painter(size, egui::Sense::click());
+        let origin = response.rect.min + egui::vec2(GRAPH_MARGIN, GRAPH_MARGIN);
+
+        let mut positions: BTreeMap<&str, egui::Rect> = BTreeMap::new();
+        for (row_index, row) in rows.values_mut().enumerate() {
+            row.sort_unstable();
+            for (column, name) in row.iter().enumerate() {
+                let min = origin + egui::vec2(column as f32 * NODE_SPACING.x, row_index as f32 * NODE_SPACING.y);
+                positions.insert(name, egui::Rect::from_min_size(min, NODE_SIZE));
+            }
+        }
+
+        for edge in &graph.edges {
+            let (Some(from), Some(to)) = (positions.get(edge.from.as_str()), positions.get(edge.to.as_str())) else {
+                continue;
+            };
+            let color = if !edge.target_exists {
+                MISSING_EDGE_COLOR
+            } else if edge.key_label().is_some() {
+                LOCKED_EDGE_COLOR
+            } else {
+                EDGE_COLOR
+            };
+            let direction = to.center() - from.center();
+            let length = direction.length();
+            if length > 0.0 {
+                // Stop the arrow at the edge of the target node
+                let shortened = direction * ((length - NODE_SIZE.y) / length);
+                painter.arrow(from.center(), shortened, egui::Stroke::new(1.5, color));
+            }
+        }
+
+        let pointer = response.hover_pos();
+        for (name, rect) in &positions {
+            let fill = if *name == current_room {
+                CURRENT_NODE_COLOR
+            } else if graph.rooms.iter().any(|r| r == name) {
+                NODE_COLOR
+            } else {
+                MISSING_NODE_COLOR
+            };
+            painter.rect_filled(*rect, 4.0, fill);
+            painter.text(rect.center(), egui::Align2::CENTER_CENTER, *name, egui::FontId::monospace(12.0), egui::Color32::WHITE);
+
+            if pointer.map_or(false, |p| rect.contains(p)) {
+                let doors: Vec<String> = graph
+                    .edges
+                    .iter()
+                    .filter(|e| e.from == *name)
+                    .map(|e| {
+                        let key = e.key_label().map(|k| format!(" [{}]", k)).unwrap_or_default();
+                        let missing = if e.target_exists { "" } else { " (missing)" };
+                        format!("AOT {} → {} cam {}{}{}", e.aot, names.room_label(&e.to), e.next_camera, key, missing)
+                    })
+                    .collect();
+                let label = names.room_label(name);
+                let text = if doors.is_empty() {
+                    label
+                } else {
+                    format!("{}\n{}", label, doors.join("\n"))
+                };
+                response.clone().on_hover_text_at_pointer(text);
+                if response.clicked() {
+                    clicked = Some(name.to_string());
+                }
+            }
+        }
+    });
+    clicked
+}
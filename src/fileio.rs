@@ -1,4 +0,0 @@
-pub mod rdt;
-pub mod rdt_scd;
-pub mod utils;
-pub mod opcode_data;
@@ -1,6 +1,14 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 mod app;
-mod file_handler;
-mod fileio;
+mod code_editor_view;
+mod debugger_view;
+mod diff_view;
+mod door_graph_view;
+mod merge_view;
+mod problems_view;
+mod room_map;
+mod room_state_view;
+mod symbols_view;
+mod table_view;
 pub use app::TemplateApp;
@@ -0,0 +1,126 @@
+use bio2_scd::fileio::name_tables::NameTables;
+use bio2_scd::merge::{MergeConflict, Resolution, RoomMerge, RESOLUTIONS};
+use bio2_scd::writer::PlacedInstruction;
+
+const RESOLVED_COLOR: egui::Color32 = egui::Color32::from_rgb(100, 200, 100);
+const CONFLICT_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 100, 100);
+
+/// A merge of two mods of a room in progress
+#[derive(Clone)]
+struct MergeSession {
+    /// File names of the original room and the two mods
+    names: [String; 3],
+    base_contents: Vec<u8>,
+    merge: RoomMerge,
+}
+
+/// Conflicts of the last merge with the resolution picked for each
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct MergeView {
+    #[serde(skip)]
+    session: Option<MergeSession>,
+}
+
+impl MergeView {
+    /// Starts resolving the conflicts of a new merge
+    pub fn start(&mut self, names: [String; 3], base_contents: Vec<u8>, merge: RoomMerge) {
+        self.session = Some(MergeSession {
+            names,
+            base_contents,
+            merge,
+        });
+    }
+
+    /// The merged RDT file and warnings about moved sections
+    pub fn write(&self) -> Result<(Vec<u8>, Vec<String>), String> {
+        let session = self.session.as_ref().ok_or("Nothing to merge")?;
+        session.merge.write(&session.base_contents)
+    }
+
+    /// Draws every conflict with the three versions side by side, returns true when Save is clicked
+    pub fn show(&mut self, ui: &mut egui::Ui, names: &NameTables) -> bool {
+        let Some(session) = self.session.as_mut() else {
+            ui.label("Use File → Merge mods… to pick the original room and two mods of it");
+            return false;
+        };
+        let merge = &mut session.merge;
+        let [base_name, our_name, their_name] = &session.names;
+
+        let mut save = false;
+        ui.label(format!("{} and {} against {}", our_name, their_name, base_name));
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} functions merged automatically, {} conflicts",
+                merge.merged_automatically(),
+                merge.conflicts.len()
+            ));
+            ui.separator();
+            let unresolved = merge.unresolved();
+            match unresolved {
+                0 => ui.colored_label(RESOLVED_COLOR, "all resolved"),
+                _ => ui.colored_label(CONFLICT_COLOR, format!("{} unresolved", unresolved)),
+            };
+            ui.separator();
+            ui.menu_button("Resolve all", |ui| {
+                for resolution in RESOLUTIONS {
+                    if ui.button(resolution_label(resolution)).clicked() {
+                        merge.resolve_all(resolution);
+                        ui.close_menu();
+                    }
+                }
+            });
+            save = ui
+                .add_enabled(unresolved == 0, egui::Button::new("Save merged…"))
+                .on_disabled_hover_text("Resolve every conflict first")
+                .clicked();
+        });
+        ui.separator();
+
+        egui::ScrollArea::both().show(ui, |ui| {
+            for (index, conflict) in merge.conflicts.iter_mut().enumerate() {
+                let color = match conflict.resolution {
+                    Some(_) => RESOLVED_COLOR,
+                    None => CONFLICT_COLOR,
+                };
+                egui::CollapsingHeader::new(egui::RichText::new(conflict.to_string()).color(color))
+                    .id_source(("merge_conflict", index))
+                    .default_open(true)
+                    .show(ui, |ui| show_conflict(ui, index, conflict, &session.names, names));
+            }
+        });
+        save
+    }
+}
+
+/// The original and both edited runs of a conflict with a choice of resolution
+fn show_conflict(ui: &mut egui::Ui, index: usize, conflict: &mut MergeConflict, files: &[String; 3], names: &NameTables) {
+    ui.horizontal(|ui| {
+        for resolution in RESOLUTIONS {
+            ui.radio_value(&mut conflict.resolution, Some(resolution), resolution_label(resolution));
+        }
+    });
+    let columns: [&[PlacedInstruction]; 3] = [&conflict.base, &conflict.ours, &conflict.theirs];
+    egui::Grid::new(("merge_conflict_lines", index)).striped(true).show(ui, |ui| {
+        for file in files {
+            ui.strong(file);
+        }
+        ui.end_row();
+        let rows = columns.iter().map(|column| column.len()).max().unwrap_or(0);
+        for row in 0..rows {
+            for column in columns {
+                let code = column.get(row).map(|placed| names.code_line(&placed.instruction));
+                ui.monospace(code.unwrap_or_default());
+            }
+            ui.end_row();
+        }
+    });
+}
+
+fn resolution_label(resolution: Resolution) -> &'static str {
+    match resolution {
+        Resolution::Ours => "Take first mod",
+        Resolution::Theirs => "Take second mod",
+        Resolution::Both => "Take both",
+        Resolution::Base => "Keep original",
+    }
+}
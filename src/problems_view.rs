@@ -0,0 +1,89 @@
+use bio2_scd::analysis::lint::{LintConfig, Problem, Severity, RULES};
+use bio2_scd::fileio::room_layout::ScriptLocation;
+use crate::table_view::{show_sortable_table, TableSort};
+
+const PROBLEM_HEADERS: &[&str] = &["Location", "Severity", "Rule", "Message"];
+
+/// Lint rule settings and sort order of the problems panel
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct ProblemsView {
+    pub config: LintConfig,
+    sort: TableSort,
+    show_rules: bool,
+}
+
+/// What changed in the problems panel this frame
+#[derive(Default)]
+pub struct ProblemsResponse {
+    /// Location of a clicked problem
+    pub clicked: Option<ScriptLocation>,
+    /// A rule was turned on or off or changed severity
+    pub config_changed: bool,
+}
+
+impl ProblemsView {
+    /// Draws the problem counts, the rule settings and the problem table
+    pub fn show(&mut self, ui: &mut egui::Ui, problems: &[Problem]) -> ProblemsResponse {
+        let mut response = ProblemsResponse::default();
+        ui.horizontal(|ui| {
+            let count = |severity| problems.iter().filter(|p| p.severity == severity).count();
+            ui.label(format!(
+                "{} errors, {} warnings, {} infos",
+                count(Severity::Error),
+                count(Severity::Warning),
+                count(Severity::Info)
+            ));
+            ui.toggle_value(&mut self.show_rules, "Rules…");
+        });
+        if self.show_rules {
+            response.config_changed = self.show_rule_settings(ui);
+            ui.separator();
+        }
+
+        let rows: Vec<Vec<String>> = problems
+            .iter()
+            .map(|p| {
+                let location = format!("{} {}:{}", p.location.script.name(), p.location.function, p.location.instruction);
+                vec![location, p.severity.to_string(), p.rule.clone(), p.message.clone()]
+            })
+            .collect();
+        response.clicked = show_sortable_table(ui, "lint_problems", PROBLEM_HEADERS, &rows, &mut self.sort)
+            .map(|row| problems[row].location);
+        response
+    }
+
+    /// One row per rule with an enable checkbox and a severity picker, returns true on changes
+    fn show_rule_settings(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        let config = &mut self.config;
+        egui::Grid::new("lint_rules").striped(true).show(ui, |ui| {
+            for rule in RULES {
+                let mut enabled = !config.disabled.contains(rule.id);
+                if ui.checkbox(&mut enabled, rule.id).changed() {
+                    if enabled {
+                        config.disabled.remove(rule.id);
+                    } else {
+                        config.disabled.insert(rule.id.to_string());
+                    }
+                    changed = true;
+                }
+                let mut severity = config.severities.get(rule.id).copied().unwrap_or(rule.severity);
+                egui::ComboBox::from_id_source(rule.id)
+                    .selected_text(severity.to_string())
+                    .show_ui(ui, |ui| {
+                        for option in [Severity::Error, Severity::Warning, Severity::Info] {
+                            changed |= ui.selectable_value(&mut severity, option, option.to_string()).changed();
+                        }
+                    });
+                if severity == rule.severity {
+                    config.severities.remove(rule.id);
+                } else {
+                    config.severities.insert(rule.id.to_string(), severity);
+                }
+                ui.label(rule.description);
+                ui.end_row();
+            }
+        });
+        changed
+    }
+}
@@ -0,0 +1,251 @@
+use bio2_scd::fileio::name_tables::enemy_name;
+use bio2_scd::fileio::room_layout::{
+    aot_type_color, aot_type_name, AotShape, RoomLayout, ScriptLocation, FULL_TURN,
+};
+
+// UI Constants
+const COLLISION_COLOR: egui::Color32 = egui::Color32::from_rgb(140, 140, 150);
+const CAMERA_SWITCH_COLOR: egui::Color32 = egui::Color32::from_rgb(90, 90, 110);
+const ENEMY_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 80, 80);
+const ITEM_COLOR: egui::Color32 = egui::Color32::from_rgb(80, 220, 120);
+const DOOR_COLOR: egui::Color32 = egui::Color32::from_rgb(230, 160, 60);
+const SELECTED_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 255, 0);
+const AOT_FILL_ALPHA: u8 = 60;
+const MARKER_RADIUS: f32 = 5.0;
+const ARROW_LENGTH: f32 = 20.0;
+const MAP_MARGIN: f32 = 20.0;
+const ZOOM_SPEED: f32 = 0.002;
+
+/// View settings of the room map window
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct RoomMapView {
+    zoom: f32,
+    pan_x: f32,
+    pan_y: f32,
+    show_collision: bool,
+    show_camera_switches: bool,
+    show_aots: bool,
+    show_doors: bool,
+    show_items: bool,
+    show_enemies: bool,
+}
+
+impl Default for RoomMapView {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            pan_x: 0.0,
+            pan_y: 0.0,
+            show_collision: true,
+            show_camera_switches: false,
+            show_aots: true,
+            show_doors: true,
+            show_items: true,
+            show_enemies: true,
+        }
+    }
+}
+
+/// Maps room coordinates (x right, z up) to screen positions
+struct MapTransform {
+    origin: egui::Pos2,
+    center: (f32, f32),
+    scale: f32,
+}
+
+impl MapTransform {
+    fn to_screen(&self, x: i32, z: i32) -> egui::Pos2 {
+        egui::pos2(
+            self.origin.x + (x as f32 - self.center.0) * self.scale,
+            self.origin.y - (z as f32 - self.center.1) * self.scale,
+        )
+    }
+}
+
+impl RoomMapView {
+    /// Draws the map and returns the source instruction of a clicked shape
+    pub fn show(&mut self, ui: &mut egui::Ui, layout: &RoomLayout, selected: Option<ScriptLocation>) -> Option<ScriptLocation> {
+        ui.horizontal_wrapped(|ui| {
+            ui.checkbox(&mut self.show_collision, "Collision");
+            ui.checkbox(&mut self.show_camera_switches, "Camera switches");
+            ui.checkbox(&mut self.show_aots, "AOTs");
+            ui.checkbox(&mut self.show_doors, "Doors");
+            ui.checkbox(&mut self.show_items, "Items");
+            ui.checkbox(&mut self.show_enemies, "Enemies");
+            if ui.button("Reset view").clicked() {
+                self.zoom = 1.0;
+                self.pan_x = 0.0;
+                self.pan_y = 0.0;
+            }
+        });
+
+        let (response, painter) = ui.allocate_painter(ui.available_size(), egui::Sense::click_and_drag());
+        let rect = response.rect;
+        painter.rect_filled(rect, 0.0, egui::Color32::from_gray(20));
+
+        let Some((min_x, min_z, max_x, max_z)) = layout.bounds() else {
+            painter.text(rect.center(), egui::Align2::CENTER_CENTER, "Nothing to draw", egui::FontId::proportional(14.0), egui::Color32::GRAY);
+            return None;
+        };
+
+        if response.dragged() {
+            self.pan_x += response.drag_delta().x;
+            self.pan_y += response.drag_delta().y;
+        }
+        if response.hovered() {
+            let scroll = ui.input(|i| i.scroll_delta.y);
+            if scroll != 0.0 {
+                self.zoom = (self.zoom * (1.0 + scroll * ZOOM_SPEED)).clamp(0.1, 50.0);
+            }
+        }
+
+        let width = (max_x - min_x).max(1) as f32;
+        let depth = (max_z - min_z).max(1) as f32;
+        let fit_scale = ((rect.width() - 2.0 * MAP_MARGIN) / width).min((rect.height() - 2.0 * MAP_MARGIN) / depth);
+        let transform = MapTransform {
+            origin: rect.center() + egui::vec2(self.pan_x, self.pan_y),
+            center: ((min_x + max_x) as f32 / 2.0, (min_z + max_z) as f32 / 2.0),
+            scale: fit_scale.max(0.0001) * self.zoom,
+        };
+
+        let painter = painter.with_clip_rect(rect);
+        self.paint(&painter, layout, &transform, selected);
+
+        let pointer = response.hover_pos()?;
+        let hovered = self.shape_at(layout, &transform, pointer);
+        if let Some((_, description)) = &hovered {
+            response.clone().on_hover_text_at_pointer(description);
+        }
+        if response.clicked() {
+            return hovered.map(|(location, _)| location);
+        }
+        None
+    }
+
+    fn paint(&self, painter: &egui::Painter, layout: &RoomLayout, t: &MapTransform, selected: Option<ScriptLocation>) {
+        if self.show_camera_switches {
+            for switch in &layout.camera_switches {
+                let points = switch.points.iter().map(|&(x, z)| t.to_screen(x as i32, z as i32)).collect();
+                painter.add(egui::Shape::closed_line(points, egui::Stroke::new(1.0, CAMERA_SWITCH_COLOR)));
+            }
+        }
+
+        if self.show_collision {
+            for b in &layout.collision {
+                let corner_a = t.to_screen(b.x as i32, b.z as i32);
+                let corner_b = t.to_screen(b.x as i32 + b.width as i32, b.z as i32 + b.depth as i32);
+                painter.rect_stroke(egui::Rect::from_two_pos(corner_a, corner_b), 0.0, egui::Stroke::new(1.0, COLLISION_COLOR));
+            }
+        }
+
+        let stroke_for = |area: &AotShape, color: egui::Color32| {
+            if selected == Some(area.source) {
+                egui::Stroke::new(3.0, SELECTED_COLOR)
+            } else {
+                egui::Stroke::new(1.5, color)
+            }
+        };
+
+        if self.show_aots {
+            for aot in &layout.aots {
+                let (r, g, b) = aot_type_color(aot.aot_type);
+                let color = egui::Color32::from_rgb(r, g, b);
+                let points: Vec<egui::Pos2> = aot.points.iter().map(|&(x, z)| t.to_screen(x, z)).collect();
+                painter.add(egui::Shape::convex_polygon(
+                    points,
+                    egui::Color32::from_rgba_unmultiplied(r, g, b, AOT_FILL_ALPHA),
+                    stroke_for(aot, color),
+                ));
+            }
+        }
+
+        if self.show_doors {
+            for door in &layout.doors {
+                let points: Vec<egui::Pos2> = door.area.points.iter().map(|&(x, z)| t.to_screen(x, z)).collect();
+                painter.add(egui::Shape::closed_line(points, stroke_for(&door.area, DOOR_COLOR)));
+
+                // The arrow points in the direction the player faces after going through the door
+                let (cx, cz) = door.area.center();
+                let angle = door.next_dir as f32 / FULL_TURN as f32 * std::f32::consts::TAU;
+                let direction = egui::vec2(angle.sin(), -angle.cos()) * ARROW_LENGTH;
+                painter.arrow(t.to_screen(cx, cz) - direction / 2.0, direction, egui::Stroke::new(2.0, DOOR_COLOR));
+            }
+        }
+
+        if self.show_items {
+            for item in &layout.items {
+                let points: Vec<egui::Pos2> = item.area.points.iter().map(|&(x, z)| t.to_screen(x, z)).collect();
+                painter.add(egui::Shape::closed_line(points, stroke_for(&item.area, ITEM_COLOR)));
+                let (cx, cz) = item.area.center();
+                painter.circle_filled(t.to_screen(cx, cz), MARKER_RADIUS, ITEM_COLOR);
+            }
+        }
+
+        if self.show_enemies {
+            for enemy in &layout.enemies {
+                let center = t.to_screen(enemy.x, enemy.z);
+                let angle = enemy.dir as f32 / FULL_TURN as f32 * std::f32::consts::TAU;
+                let direction = egui::vec2(angle.sin(), -angle.cos()) * (MARKER_RADIUS * 2.5);
+                painter.circle_filled(center, MARKER_RADIUS, ENEMY_COLOR);
+                painter.line_segment([center, center + direction], egui::Stroke::new(2.0, ENEMY_COLOR));
+                if selected == Some(enemy.source) {
+                    painter.circle_stroke(center, MARKER_RADIUS + 2.0, egui::Stroke::new(2.0, SELECTED_COLOR));
+                }
+            }
+        }
+    }
+
+    /// Finds the topmost visible shape under the pointer
+    fn shape_at(&self, layout: &RoomLayout, t: &MapTransform, pointer: egui::Pos2) -> Option<(ScriptLocation, String)> {
+        if self.show_enemies {
+            for enemy in &layout.enemies {
+                if t.to_screen(enemy.x, enemy.z).distance(pointer) <= MARKER_RADIUS + 2.0 {
+                    return Some((enemy.source, format!("SceEmSet {} (slot {})\nx: {} z: {}", enemy_name(enemy.id), enemy.em_no, enemy.x, enemy.z)));
+                }
+            }
+        }
+        if self.show_items {
+            for item in &layout.items {
+                if contains(&item.area, t, pointer) {
+                    return Some((item.area.source, format!(
+                        "ItemAotSet aot {}\nitem: {} amount: {} picked index: {}",
+                        item.area.aot, item.item_id, item.amount, item.item_picked_index
+                    )));
+                }
+            }
+        }
+        if self.show_doors {
+            for door in &layout.doors {
+                if contains(&door.area, t, pointer) {
+                    return Some((door.area.source, format!(
+                        "DoorAotSet aot {}\nto stage {} room {} camera {}",
+                        door.area.aot, door.next_stage, door.next_room, door.next_camera
+                    )));
+                }
+            }
+        }
+        if self.show_aots {
+            for aot in layout.aots.iter().rev() {
+                if contains(aot, t, pointer) {
+                    return Some((aot.source, format!("AOT {} ({})", aot.aot, aot_type_name(aot.aot_type))));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Point-in-polygon test in screen space
+fn contains(area: &AotShape, t: &MapTransform, pointer: egui::Pos2) -> bool {
+    let points: Vec<egui::Pos2> = area.points.iter().map(|&(x, z)| t.to_screen(x, z)).collect();
+    let mut inside = false;
+    let mut j = points.len().wrapping_sub(1);
+    for i in 0..points.len() {
+        let (a, b) = (points[i], points[j]);
+        if (a.y > pointer.y) != (b.y > pointer.y) && pointer.x < (b.x - a.x) * (pointer.y - a.y) / (b.y - a.y) + a.x {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
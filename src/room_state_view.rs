@@ -0,0 +1,164 @@
+use bio2_scd::analysis::room_state::{diff_tables, RoomState, RowChange, StateTable, TableDiff};
+use bio2_scd::file_handler::RdtFileData;
+use bio2_scd::fileio::name_tables::NameTables;
+use bio2_scd::vm::state::GameState;
+
+const ADDED_COLOR: egui::Color32 = egui::Color32::from_rgb(100, 200, 100);
+const REMOVED_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 100, 100);
+const CHANGED_COLOR: egui::Color32 = egui::Color32::from_rgb(220, 180, 80);
+
+/// Result of the last evaluation
+#[derive(Clone)]
+enum StateReport {
+    Tables { tables: Vec<StateTable>, incomplete: bool },
+    Diff(Vec<TableDiff>),
+}
+
+/// Flag states of the room state window
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct RoomStateView {
+    set: String,
+    compare: String,
+    compare_enabled: bool,
+    #[serde(skip)]
+    report: Option<Result<StateReport, String>>,
+}
+
+impl RoomStateView {
+    /// Keeps the flag states for another room, the report belongs to the old one
+    pub fn for_new_room(&self) -> Self {
+        Self {
+            set: self.set.clone(),
+            compare: self.compare.clone(),
+            compare_enabled: self.compare_enabled,
+            report: None,
+        }
+    }
+
+    /// Draws the flag state fields and the tables or differences of the last evaluation
+    pub fn show(&mut self, ui: &mut egui::Ui, file_data: &RdtFileData, names: &NameTables) {
+        egui::Grid::new("room_state_flags").num_columns(2).show(ui, |ui| {
+            ui.label("Flags");
+            ui.add(egui::TextEdit::singleline(&mut self.set).hint_text("flag:1:5=1,var:ROOM_STATE=7"));
+            ui.end_row();
+            ui.checkbox(&mut self.compare_enabled, "Compare with");
+            ui.add_enabled(
+                self.compare_enabled,
+                egui::TextEdit::singleline(&mut self.compare).hint_text("empty for a cleared game state"),
+            );
+            ui.end_row();
+        });
+        if ui.button("Evaluate").on_hover_text("Run the init script from these flags").clicked() {
+            self.report = Some(self.evaluate(file_data, names));
+        }
+        ui.separator();
+
+        match &self.report {
+            None => {
+                ui.label("Lists the AOT slots, entities, doors, items, lights and camera the init script sets up");
+            }
+            Some(Err(e)) => {
+                ui.colored_label(REMOVED_COLOR, e);
+            }
+            Some(Ok(StateReport::Tables { tables, incomplete })) => {
+                if *incomplete {
+                    ui.colored_label(CHANGED_COLOR, "The init script did not end, showing its state so far");
+                }
+                egui::ScrollArea::both().show(ui, |ui| {
+                    for table in tables {
+                        show_table(ui, table);
+                    }
+                });
+            }
+            Some(Ok(StateReport::Diff(diffs))) => {
+                if diffs.is_empty() {
+                    ui.label("Both flag states set up the room the same way");
+                }
+                egui::ScrollArea::both().show(ui, |ui| {
+                    for diff in diffs {
+                        show_diff(ui, diff);
+                    }
+                });
+            }
+        }
+    }
+
+    fn evaluate(&self, file_data: &RdtFileData, names: &NameTables) -> Result<StateReport, String> {
+        let evaluate = |assignments: &str| -> Result<RoomState, String> {
+            let mut state = GameState::default();
+            state.apply_assignments(assignments, &names.symbols)?;
+            Ok(RoomState::evaluate(file_data, state, names))
+        };
+        let room = evaluate(&self.set)?;
+        if self.compare_enabled {
+            let other = evaluate(&self.compare)?;
+            Ok(StateReport::Diff(diff_tables(&room.tables(names), &other.tables(names))))
+        } else {
+            Ok(StateReport::Tables {
+                tables: room.tables(names),
+                incomplete: room.incomplete,
+            })
+        }
+    }
+}
+
+fn show_table(ui: &mut egui::Ui, table: &StateTable) {
+    egui::CollapsingHeader::new(format!("{} ({})", table.name, table.rows.len()))
+        .default_open(!table.rows.is_empty())
+        .show(ui, |ui| {
+            egui::Grid::new(table.name).striped(true).show(ui, |ui| {
+                for header in table.headers {
+                    ui.strong(*header);
+                }
+                ui.end_row();
+                for (_, cells) in &table.rows {
+                    for cell in cells {
+                        ui.label(cell);
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+}
+
+/// Added rows in green, removed rows in red and changed rows with the old and new value of each cell
+fn show_diff(ui: &mut egui::Ui, diff: &TableDiff) {
+    egui::CollapsingHeader::new(format!("{} ({} changes)", diff.name, diff.changes.len()))
+        .default_open(true)
+        .show(ui, |ui| {
+            egui::Grid::new(diff.name).striped(true).show(ui, |ui| {
+                ui.label("");
+                for header in diff.headers {
+                    ui.strong(*header);
+                }
+                ui.end_row();
+                for (_, change) in &diff.changes {
+                    match change {
+                        RowChange::Added(cells) => {
+                            ui.colored_label(ADDED_COLOR, "+");
+                            for cell in cells {
+                                ui.colored_label(ADDED_COLOR, cell);
+                            }
+                        }
+                        RowChange::Removed(cells) => {
+                            ui.colored_label(REMOVED_COLOR, "-");
+                            for cell in cells {
+                                ui.colored_label(REMOVED_COLOR, cell);
+                            }
+                        }
+                        RowChange::Changed { before, after } => {
+                            ui.colored_label(CHANGED_COLOR, "~");
+                            for (old, new) in before.iter().zip(after) {
+                                if old == new {
+                                    ui.label(old);
+                                } else {
+                                    ui.colored_label(CHANGED_COLOR, format!("{} → {}", old, new));
+                                }
+                            }
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+}
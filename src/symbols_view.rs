@@ -0,0 +1,108 @@
+use bio2_scd::fileio::symbols::{parse_number, SymbolDictionary, SymbolKind};
+
+/// Kinds of symbol that can be added, in the order of the add row combo box
+const SYMBOL_KIND_NAMES: &[&str] = &["Bit array", "Flag", "Variable"];
+
+/// Editing state of the symbol dictionary window
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct SymbolEditor {
+    new_kind: usize,
+    new_array: String,
+    new_number: String,
+    new_name: String,
+    filter: String,
+}
+
+impl SymbolEditor {
+    /// Prepares the add row to name the given symbol
+    pub fn prefill(&mut self, kind: SymbolKind, dictionary: &SymbolDictionary) {
+        let (new_kind, array, number) = match kind {
+            SymbolKind::BitArray(array) => (0, array, 0),
+            SymbolKind::Flag(array, bit) => (1, array, bit),
+            SymbolKind::Variable(var_id) => (2, 0, var_id),
+        };
+        self.new_kind = new_kind;
+        self.new_array = array.to_string();
+        self.new_number = number.to_string();
+        self.new_name = dictionary.name(kind).unwrap_or_default().to_string();
+    }
+
+    /// Draws the dictionary as editable lists, returns true if any name changed
+    pub fn show(&mut self, ui: &mut egui::Ui, dictionary: &mut SymbolDictionary) -> bool {
+        let mut changed = self.show_add_row(ui, dictionary);
+        ui.horizontal(|ui| {
+            ui.label("Filter");
+            ui.text_edit_singleline(&mut self.filter);
+        });
+        ui.separator();
+
+        let filter = self.filter.to_lowercase();
+        let mut edits: Vec<(SymbolKind, String)> = Vec::new();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            let bit_arrays = dictionary.bit_arrays.iter().map(|(&a, n)| (SymbolKind::BitArray(a), format!("bit[{}]", a), n));
+            let flags = dictionary.flags.iter().map(|(&(a, b), n)| (SymbolKind::Flag(a, b), format!("bit[{}][{}]", a, b), n));
+            let variables = dictionary.variables.iter().map(|(&v, n)| (SymbolKind::Variable(v), format!("var[{}]", v), n));
+
+            egui::Grid::new("symbol_dictionary").striped(true).show(ui, |ui| {
+                for (kind, key, name) in bit_arrays.chain(flags).chain(variables) {
+                    if !filter.is_empty() && !name.to_lowercase().contains(&filter) && !key.contains(&filter) {
+                        continue;
+                    }
+                    ui.monospace(key);
+                    // Emptied names are ignored so the row stays while retyping; the button removes it
+                    let mut text = name.clone();
+                    if ui.text_edit_singleline(&mut text).changed() && !text.trim().is_empty() {
+                        edits.push((kind, text));
+                    }
+                    if ui.button("🗑").on_hover_text("Remove").clicked() {
+                        edits.push((kind, String::new()));
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+
+        for (kind, name) in edits {
+            dictionary.set_name(kind, name);
+            changed = true;
+        }
+        changed
+    }
+
+    fn show_add_row(&mut self, ui: &mut egui::Ui, dictionary: &mut SymbolDictionary) -> bool {
+        let mut added = false;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("new_symbol_kind")
+                .selected_text(SYMBOL_KIND_NAMES[self.new_kind])
+                .show_ui(ui, |ui| {
+                    for (index, name) in SYMBOL_KIND_NAMES.iter().enumerate() {
+                        ui.selectable_value(&mut self.new_kind, index, *name);
+                    }
+                });
+            if self.new_kind != 2 {
+                ui.label("array");
+                ui.add(egui::TextEdit::singleline(&mut self.new_array).desired_width(40.0));
+            }
+            if self.new_kind != 0 {
+                ui.label(if self.new_kind == 1 { "bit" } else { "id" });
+                ui.add(egui::TextEdit::singleline(&mut self.new_number).desired_width(40.0));
+            }
+            ui.add(egui::TextEdit::singleline(&mut self.new_name).hint_text("NAME"));
+
+            let kind = match (self.new_kind, parse_number(&self.new_array), parse_number(&self.new_number)) {
+                (0, Some(array), _) => Some(SymbolKind::BitArray(array)),
+                (1, Some(array), Some(bit)) => Some(SymbolKind::Flag(array, bit)),
+                (2, _, Some(var_id)) => Some(SymbolKind::Variable(var_id)),
+                _ => None,
+            };
+            let can_add = kind.is_some() && !self.new_name.trim().is_empty();
+            if ui.add_enabled(can_add, egui::Button::new("Set")).clicked() {
+                if let Some(kind) = kind {
+                    dictionary.set_name(kind, std::mem::take(&mut self.new_name));
+                    added = true;
+                }
+            }
+        });
+        added
+    }
+}
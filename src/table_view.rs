@@ -0,0 +1,76 @@
+use std::cmp::Ordering;
+
+/// Sort order of a table, persisted with the UI state
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct TableSort {
+    column: usize,
+    descending: bool,
+}
+
+/// Draws a table whose columns sort when their header is clicked
+///
+/// Returns the index (into `rows`) of a row whose first cell was clicked
+pub fn show_sortable_table(
+    ui: &mut egui::Ui,
+    id: &str,
+    headers: &[&str],
+    rows: &[Vec<String>],
+    sort: &mut TableSort,
+) -> Option<usize> {
+    let mut order: Vec<usize> = (0..rows.len()).collect();
+    order.sort_by(|&a, &b| {
+        let ordering = compare_cells(&rows[a][sort.column], &rows[b][sort.column]);
+        if sort.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+
+    let mut clicked = None;
+    egui::ScrollArea::both().id_source(id).show(ui, |ui| {
+        egui::Grid::new(id).striped(true).show(ui, |ui| {
+            for (column, header) in headers.iter().enumerate() {
+                let arrow = match (sort.column == column, sort.descending) {
+                    (true, false) => " ⏶",
+                    (true, true) => " ⏷",
+                    (false, _) => "",
+                };
+                if ui.button(format!("{}{}", header, arrow)).clicked() {
+                    sort.descending = sort.column == column && !sort.descending;
+                    sort.column = column;
+                }
+            }
+            ui.end_row();
+
+            for &row_index in &order {
+                for (column, cell) in rows[row_index].iter().enumerate() {
+                    if column == 0 {
+                        if ui.link(cell).clicked() {
+                            clicked = Some(row_index);
+                        }
+                    } else {
+                        ui.label(cell);
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    });
+    clicked
+}
+
+/// Compares numerically when both cells are numbers (decimal or 0x hex)
+fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (parse_number(a), parse_number(b)) {
+        (Some(x), Some(y)) => x.cmp(&y),
+        _ => a.cmp(b),
+    }
+}
+
+fn parse_number(cell: &str) -> Option<i64> {
+    match cell.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16).ok(),
+        None => cell.parse().ok(),
+    }
+}